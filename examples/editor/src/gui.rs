@@ -1,4 +1,4 @@
-use rugui2::{colors::Colors, element::Element, events::{ElemEventTypes, EventListener}, styles::{TextWrap, Value}, text::TextRepr, widgets::{SplitOptions, WidgetManager}, Gui};
+use rugui2::{colors::Colors, element::Element, events::{ElemEventTypes, EventListener}, styles::{TextWrap, Value}, text::TextRepr, variables::Spring, widgets::{SplitOptions, WidgetManager}, Gui};
 use rugui2_wgpu::texture::Texture;
 use winit::window::CursorIcon;
 
@@ -20,9 +20,10 @@ pub fn init(widgets: &mut WidgetManager<Msgs, Texture, WidgetData, Actions>, gui
     let beam = gui.add_element(beam);
     let entry_key = gui.add_element(entry);
 
-    widgets.horizontal_split(gui, entry_key, left, right, &SplitOptions::Dynamic {
+    widgets.horizontal_split(gui, entry_key, left, right, &SplitOptions::Spring {
         split: None,
         beam,
+        spring: Spring::critical(400.0),
     }, |_| Actions::None, |_| Actions::None);
 
     gui.set_entry(entry_key);
@@ -36,4 +37,4 @@ pub fn editor_window(gui: &mut Gui<Msgs, Texture>, element: &mut Element<Msgs, T
     styles.text.set(Some(TextRepr::new_editor("danda")));
     styles.font_size.set(Value::Px(18.0));
     styles.text_wrap.set(TextWrap::Overflow);
-}
\ No newline at end of file
+}
\ No newline at end of file
@@ -7,8 +7,9 @@ use rugui2::rich_text::{Text, TextSection};
 use rugui2::*;
 use rugui2::Gui;
 use rugui2_wgpu::texture::Texture;
-use styles::{Container, Gradient, Image, Portion, Position, Value, Values};
+use styles::{Container, Corners, Gradient, Image, Portion, Position, Value, Values};
 use text::{Font, FontIdx, TextRepr};
+use timeline::{Keyframe, Timeline, TimelineKey, Track};
 use widgets::{OnEvent, WidgetControlFlow, WidgetManager};
 use winit::window::CursorIcon;
 
@@ -43,6 +44,8 @@ pub struct GuiManager {
     pub ingame_end_overlay: ElementKey,
     pub settings: ElementKey,
     pub start_btn: ElementKey,
+    /// Fades the menu in whenever it is opened
+    pub intro: TimelineKey,
     pub noto_font: FontIdx,
     pub mono_font: FontIdx,
 }
@@ -242,9 +245,7 @@ impl GuiManager {
         let ingame = new(gui, |_, container| {
             let styles = container.styles_mut();
 
-            styles.image.set(Some(Image {
-                data: game_tex.clone(),
-            }));
+            styles.image.set(Some(Image::new(game_tex.clone())));
             container
                 .events
                 .add(EventListener::new(events::ElemEventTypes::KeyPress).with_msg(Msgs::Ingame));
@@ -308,8 +309,8 @@ impl GuiManager {
         let ingame_end = new(gui, |gui, e| {
             let overlay = new(gui, |_, e| {
                 let styles = e.styles_mut();
-                styles.grad_linear.set(Some(Gradient {
-                    p1: (
+                styles.grad_linear.set(Some(Gradient::new(
+                    (
                         Position {
                             container: Container::This,
                             height: Value::Px(0.0),
@@ -317,7 +318,7 @@ impl GuiManager {
                         },
                         Colors::GREEN.with_alpha(0.3),
                     ),
-                    p2: (
+                    (
                         Position {
                             container: Container::This,
                             height: Value::Px(0.0),
@@ -325,7 +326,7 @@ impl GuiManager {
                         },
                         Colors::RED.with_alpha(0.3),
                     ),
-                }));
+                )));
             });
             ingame_end_overlay = overlay;
 
@@ -354,7 +355,7 @@ impl GuiManager {
         });
 
         let settings = new(gui, |gui, e| {
-            e.styles_mut().image.set(Some(Image { data: imag }));
+            e.styles_mut().image.set(Some(Image::new(imag)));
             e.events
                 .add(EventListener::new(events::ElemEventTypes::KeyPress).with_msg(Msgs::Settings));
             let b = new(gui, |gui, e| {
@@ -390,6 +391,26 @@ impl GuiManager {
 
         gui.set_entry(menu);
         gui.selection.select_element_unchecked(menu);
+        let intro = gui.add_timeline(
+            Timeline::new()
+                .with_track(Track::new(
+                    menu,
+                    |s| &mut s.alpha,
+                    vec![
+                        Keyframe::new(0.0, 0.0),
+                        Keyframe::new(0.5, 1.0).with_easing(animation::Easing::EaseOut),
+                    ],
+                ))
+                .with_track(Track::new(
+                    start_btn,
+                    |s| &mut s.alpha,
+                    vec![
+                        Keyframe::new(0.0, 0.0),
+                        Keyframe::new(0.3, 0.0),
+                        Keyframe::new(0.8, 1.0).with_easing(animation::Easing::EaseOut),
+                    ],
+                )),
+        );
         Self {
             widgets,
             menu,
@@ -398,6 +419,7 @@ impl GuiManager {
             ingame_end,
             ingame_end_overlay,
             start_btn,
+            intro,
             settings,
             page: Pages::Menu,
             noto_font: noto,
@@ -422,6 +444,10 @@ impl GuiManager {
             }
             Pages::Menu => {
                 gui.selection.menu_accessibility = true;
+                if let Some(intro) = gui.timeline_mut(self.intro) {
+                    intro.seek(0.0);
+                    intro.play();
+                }
             }
             Pages::IngamePause => {
                 gui.selection.menu_accessibility = true;
@@ -493,7 +519,7 @@ fn buttonify(
     let styles = e.styles_mut();
     styles.text_wrap.set(styles::TextWrap::Overflow);
     styles.text_align.set(styles::TextAlign::Center);
-    styles.round.set(Some(Value::Value(
+    styles.round.set(Corners::all(Value::Value(
         Container::This,
         Values::Min,
         Portion::Half,
@@ -507,7 +533,7 @@ fn buttonify(
 
     e.children = Some(vec![new(gui, |_, e| {
         let styles = e.styles_mut();
-        styles.round.set(Some(Value::Value(
+        styles.round.set(Corners::all(Value::Value(
             Container::This,
             Values::Min,
             Portion::Half,
@@ -518,8 +544,8 @@ fn buttonify(
             Portion::Mul(0.1),
         )));
         styles.shadow_alpha.set(0.1);
-        styles.grad_linear.set(Some(Gradient {
-            p1: (
+        styles.grad_linear.set(Some(Gradient::new(
+            (
                 Position {
                     container: Container::This,
                     height: Value::Px(0.0),
@@ -527,7 +553,7 @@ fn buttonify(
                 },
                 Colors::WHITE.with_alpha(0.4),
             ),
-            p2: (
+            (
                 Position {
                     container: Container::This,
                     height: Value::Value(Container::This, Values::Height, Portion::Half),
@@ -535,6 +561,6 @@ fn buttonify(
                 },
                 Colors::TRANSPARENT,
             ),
-        }));
+        )));
     })]);
 }
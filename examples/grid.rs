@@ -83,12 +83,12 @@ impl ApplicationHandler for App {
                 container
                     .events
                     .add(EventListener::new(ElemEventTypes::MouseMove));
-                container.styles_mut().round.set(Some(Value::Px(50.0)));
-                container.styles_mut().padding.set(Value::Value(
+                container.styles_mut().round.set(Corners::all(Value::Px(50.0)));
+                container.styles_mut().padding.set(Sides::all(Value::Value(
                     Container::Container,
                     Values::Min,
-                    Portion::Mul(0.1),
-                ));
+                    Portion::Mul(0.05),
+                )));
             },
             |(x, y), element, gui| {
                 element.allow_select = true;
@@ -105,22 +105,22 @@ impl ApplicationHandler for App {
                 let styles = element.styles_mut();
 
                 styles.color.set(Colors::RED);
-                styles.padding.set(Value::Value(
+                styles.padding.set(Sides::all(Value::Value(
                     // it works now even without this yaaay
                     Container::This,
                     Values::Min,
-                    Portion::Mul(0.01),
-                ));
+                    Portion::Mul(0.005),
+                )));
 
                 let inner_list = if (x + y) % 2 == 0 {
                     inner_rows_builder.build(
                         |e, _| {
-                            e.styles_mut().padding.set(Value::Px(50.0));
+                            e.styles_mut().padding.set(Sides::all(Value::Px(25.0)));
                         },
                         |row, element, _| {
                             let styles = element.styles_mut();
 
-                            styles.padding.set(Value::Px(1.0));
+                            styles.padding.set(Sides::all(Value::Px(0.5)));
                             styles.color.set(Colors::FRgba(
                                 row as f32 * 0.1,
                                 row as f32 * 0.1,
@@ -135,12 +135,12 @@ impl ApplicationHandler for App {
                 } else {
                     inner_columns_builder.build(
                         |e, _| {
-                            e.styles_mut().padding.set(Value::Px(50.0));
+                            e.styles_mut().padding.set(Sides::all(Value::Px(25.0)));
                         },
                         |row, element, _| {
                             let styles = element.styles_mut();
 
-                            styles.padding.set(Value::Px(1.0));
+                            styles.padding.set(Sides::all(Value::Px(0.5)));
                             styles.color.set(Colors::FRgba(
                                 row as f32 * 0.1,
                                 row as f32 * 0.1,
@@ -233,8 +233,8 @@ impl ApplicationHandler for App {
                 },
                 ElemEvents::CursorMove { pos, .. } => {
                     let pos = pos / element.instance().container.size + 0.5;
-                    element.styles_mut().grad_radial.set(Some(Gradient {
-                        p1: (
+                    element.styles_mut().grad_radial.set(Some(Gradient::new(
+                        (
                             Position {
                                 container: Container::This,
                                 height: Value::Value(
@@ -250,7 +250,7 @@ impl ApplicationHandler for App {
                             },
                             Colors::YELLOW,
                         ),
-                        p2: (
+                        (
                             Position {
                                 container: Container::This,
                                 height: Value::Value(
@@ -266,7 +266,7 @@ impl ApplicationHandler for App {
                             },
                             Colors::TRANSPARENT,
                         ),
-                    }));
+                    )));
                 }
                 _ => (),
             }
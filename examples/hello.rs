@@ -6,10 +6,10 @@ use common::{
 };
 use rugui2::{
     colors::Colors,
-    element::{Element, ElementKey},
+    element::ElementKey,
     events::{ElemEventTypes, EventListener},
-    styles::{Container, Overflow, Portion, Rotation, Value, Values},
-    Gui,
+    styles::{Container, Corners, Overflow, Portion, Rotation, Value, Values},
+    ui, Gui,
 };
 use tokio::runtime::Runtime;
 use winit::{
@@ -58,55 +58,41 @@ impl ApplicationHandler for App {
             NonZero::new(window.inner_size().height).unwrap(),
         ));
 
-        let mut elem = Element::default();
-        elem.label = Some(String::from("First"));
-        elem.styles_mut().max_width.set(Some(Value::Px(500.0)));
-        elem.styles_mut().color.set(Colors::RED);
-        elem.styles_mut().width.set(Value::Value(
-            Container::Container,
-            Values::Width,
-            Portion::Half,
-        ));
-        elem.styles_mut().height.set(Value::Value(
-            Container::Container,
-            Values::Height,
-            Portion::Full,
-        ));
-        elem.styles_mut().rotation.set(Rotation {
-            rot: rugui2::styles::Rotations::Deg(1.0),
-            cont: Container::This,
-        });
-        elem.styles_mut().round.set(Some(Value::Value(Container::This, Values::Min, Portion::Half)));
-        elem.styles_mut().scroll_y.set(Value::Px(0.0));
-        elem.styles_mut().overflow.set(Overflow::Hidden);
-        elem.events.add(EventListener {
-            event: rugui2::events::ElemEventTypes::Scroll,
-            msg: None,
-            kind: rugui2::events::ListenerTypes::Listen,
+        let tree = ui!(&mut gui, {
+            label: "First",
+            styles {
+                max_width: Some(Value::Px(500.0)),
+                color: Colors::RED,
+                width: Value::Value(Container::Container, Values::Width, Portion::Half),
+                height: Value::Value(Container::Container, Values::Height, Portion::Full),
+                rotation: Rotation {
+                    rot: rugui2::styles::Rotations::Deg(1.0),
+                    cont: Container::This,
+                },
+                round: Corners::all(Value::Value(Container::This, Values::Min, Portion::Half)),
+                scroll_y: Value::Px(0.0),
+                overflow: Overflow::Hidden,
+            },
+            events [EventListener {
+                event: rugui2::events::ElemEventTypes::Scroll,
+                msg: None,
+                kind: rugui2::events::ListenerTypes::Listen,
+            }],
+            children [
+                second @ {
+                    label: "Second",
+                    styles {
+                        height: Value::Value(Container::Container, Values::Height, Portion::Half),
+                        round: Corners::all(Value::Value(Container::This, Values::Min, Portion::Half)),
+                        color: Colors::GREEN,
+                    },
+                    events [EventListener::new(ElemEventTypes::MouseMove)],
+                },
+            ],
         });
+        let element_key = tree.root;
+        let element_key2 = tree["second"];
 
-        let mut elem2 = Element::default();
-        elem2.label = Some(String::from("Second"));
-        /*elem2.styles_mut().width.set(Value::Value(
-            Container::Container,
-            Values::Width,
-            Portion::Mul(0.65),
-        ));*/
-        elem2.styles_mut().height.set(Value::Value(
-            Container::Container,
-            Values::Height,
-            Portion::Half,
-        ));
-        elem2.styles_mut().round.set(Some(Value::Value(Container::This, Values::Min, Portion::Half)));
-        elem2.styles_mut().color.set(Colors::GREEN);
-        elem2
-            .events
-            .add(EventListener::new(ElemEventTypes::MouseMove));
-        let element_key2 = gui.add_element(elem2);
-
-        elem.children = Some(vec![element_key2]);
-
-        let element_key = gui.add_element(elem);
         gui.set_entry(element_key);
 
         let program = Program {
@@ -148,8 +134,8 @@ impl ApplicationHandler for App {
                 }*/
                 rugui2::events::ElemEvents::Scroll { delta, pos: _ } => {
                     let elem = this.gui.get_element_mut(e.element_key).unwrap();
-                    if let Value::Px(px) = elem.styles_mut().scroll_y.get_mut() {
-                        *px += delta.1 * 65.0;
+                    if let Value::Px(px) = elem.styles_mut().scroll_y.get_mut() {
+                        *px += delta.1 * 65.0;
                     }
                 }
                 _ => (),
@@ -8,7 +8,7 @@ use rugui2::{
     colors::Colors,
     element::{Element, ElementKey},
     events::{ElemEvents, EventListener, SelectionStates},
-    styles::{Container, Gradient, Image, Portion, Position, Rotation, Value, Values},
+    styles::{Container, Corners, Gradient, Image, Portion, Position, Rotation, Value, Values},
     Gui,
 };
 use tokio::runtime::Runtime;
@@ -59,20 +59,20 @@ impl ApplicationHandler for App {
 
         let mut elem = Element::default();
         elem.label = Some(String::from("Container"));
-        elem.styles_mut().image.set(Some(Image {
-            data: Texture::from_file(
+        elem.styles_mut().image.set(Some(Image::new(
+            Texture::from_file(
                 &drawing.device,
                 &drawing.queue,
                 "examples/imag.png",
                 Some("BG (bad game)"),
             )
             .unwrap(),
-        }));
+        )));
         elem.styles_mut().rotation.set(Rotation {
             cont: Container::This,
             rot: rugui2::styles::Rotations::CalcDeg(Value::Time),
         });
-        elem.styles_mut().round.set(Some(Value::Px(100.0)));
+        elem.styles_mut().round.set(Corners::all(Value::Px(100.0)));
         elem.styles_mut()
             .width
             .set(Value::Value(Container::Image, Values::Width, Portion::Full));
@@ -105,8 +105,8 @@ impl ApplicationHandler for App {
                 Values::Height,
                 Portion::Mul(ratio),
             ));
-            styles.grad_linear.set(Some(Gradient {
-                p1: (
+            styles.grad_linear.set(Some(Gradient::new(
+                (
                     Position {
                         container: Container::This,
                         width: Value::Zero,
@@ -114,7 +114,7 @@ impl ApplicationHandler for App {
                     },
                     Colors::GREEN.with_alpha(0.3),
                 ),
-                p2: (
+                (
                     Position {
                         container: Container::This,
                         width: Value::Zero,
@@ -122,7 +122,7 @@ impl ApplicationHandler for App {
                     },
                     Colors::BLUE.with_alpha(0.3),
                 ),
-            }));
+            )));
 
             child.events.add(EventListener {
                 event: rugui2::events::ElemEventTypes::Click,
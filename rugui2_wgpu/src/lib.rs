@@ -1,4 +1,11 @@
-use std::{collections::HashMap, mem::size_of, num::NonZero};
+use std::{
+    collections::HashMap,
+    hash::{DefaultHasher, Hash, Hasher},
+    mem::size_of,
+    num::NonZero,
+    ops::Range,
+    sync::{Arc, Weak},
+};
 
 use etagere::{euclid::Size2D, Allocation, BucketedAtlasAllocator, Size};
 use image::DynamicImage;
@@ -11,8 +18,9 @@ use texture::{DepthBuffer, Texture};
 use wgpu::{include_wgsl, PipelineLayoutDescriptor, RenderPipelineDescriptor, VertexAttribute};
 
 use rugui2::{
-    element::{Container, ElementInstance, ElementKey, Flags},
+    element::{Container, ElementInstance, ElementKey, Flags, ShadowInstance},
     rich_text::{GlyphFlags, TextShape},
+    styles::{Gradient, Spread},
     text::{GlyphKey, PhysicalChar, TextProccesor},
 };
 
@@ -24,6 +32,14 @@ pub const GLYPH_ATLAS_SIDE: usize = 2048;
 pub const GLYPH_ATLAS_DEPTH: usize = 3;
 pub const GLYPH_BUFFER_SIZE: u64 = (1 << 20) / size_of::<WGPUGlyphInstance>() as u64;
 pub const GLYPH_BUFFER_BYTES: u64 = GLYPH_BUFFER_SIZE * size_of::<WGPUGlyphInstance>() as u64;
+pub const SHADOW_BUFFER_SIZE: u64 = (1 << 20) / size_of::<WGPUShadowInstance>() as u64;
+pub const SHADOW_BUFFER_BYTES: u64 = SHADOW_BUFFER_SIZE * size_of::<WGPUShadowInstance>() as u64;
+pub const GRADIENT_RAMP_WIDTH: usize = 256;
+/// Rows addressable by the 16 bit row field packed by `prepare_ramp`
+pub const GRADIENT_RAMP_MAX_ROWS: usize = 1 << 16;
+pub const IMAGE_ATLAS_SIDE: usize = 2048;
+/// Images wider or taller than this keep their own binding instead of going into the atlas
+pub const IMAGE_ATLAS_MAX_SIDE: u32 = 512;
 
 pub struct Rugui2WGPU {
     pub dimensions_buffer: wgpu::Buffer,
@@ -32,12 +48,17 @@ pub struct Rugui2WGPU {
     pub size: (u32, u32),
 
     instance_buffers: Vec<(wgpu::Buffer, Vec<WGPUElementInstance>, Vec<PerElementData>)>,
+    /// Instance slot of every element by its key, slots are given out in drawing order
+    element_slots: Vec<u64>,
+    element_instances: u64,
 
     pub dummy_texture: Texture,
 
     pub pipeline: wgpu::RenderPipeline,
     pub stencil_pipeline: wgpu::RenderPipeline,
     pub end_stencil_pipeline: wgpu::RenderPipeline,
+    pub border_pipeline: wgpu::RenderPipeline,
+    pub shadow_pipeline: wgpu::RenderPipeline,
 
     scaler_ctx: ScaleContext,
     scaler_image: Image,
@@ -51,6 +72,21 @@ pub struct Rugui2WGPU {
     last_written_glyph_atlas: u32,
     empty_glyph_key: (Allocation, Placement, u32),
     cursor_glyph_key: (Allocation, Placement, u32),
+    shadow_instance_buffers: Vec<(wgpu::Buffer, Vec<WGPUShadowInstance>)>,
+    shadow_instances: usize,
+    ramp_img: Vec<u8>,
+    ramp_tex: Texture,
+    ramp_rows: usize,
+    /// Most rows the ramp texture may have on this device
+    ramp_row_limit: usize,
+    /// Rows of this frame by the hash of their texels
+    ramp_cache: HashMap<u64, usize>,
+    image_atlas_tex: Texture,
+    image_atlas_allocator: BucketedAtlasAllocator,
+    /// Atlas areas by the address of the image texture, entries of dropped textures are freed
+    image_atlas_map: HashMap<usize, (Weak<wgpu::Texture>, Allocation)>,
+    image_atlas_copies: Vec<(Arc<wgpu::Texture>, Allocation)>,
+    stats: RenderStats,
 }
 
 impl Rugui2WGPU {
@@ -72,17 +108,17 @@ impl Rugui2WGPU {
     pub const VERTEX_BUFFER_LAYOUT: wgpu::VertexBufferLayout<'static> = wgpu::VertexBufferLayout {
         array_stride: size_of::<WGPUElementInstance>() as u64,
         attributes: &[
-            // center
+            // center, size
             VertexAttribute {
-                format: wgpu::VertexFormat::Float32x2,
+                format: wgpu::VertexFormat::Float32x4,
                 shader_location: 0,
                 offset: 0,
             },
-            // size
+            // dash, gap, outline width, outline offset
             VertexAttribute {
-                format: wgpu::VertexFormat::Float32x2,
+                format: wgpu::VertexFormat::Float32x4,
                 shader_location: 1,
-                offset: 8,
+                offset: 168,
             },
             // rotation
             VertexAttribute {
@@ -102,71 +138,71 @@ impl Rugui2WGPU {
                 shader_location: 4,
                 offset: 36,
             },
-            // round
+            // corner exponent, shadow, alpha, shadow_alpha
             VertexAttribute {
-                format: wgpu::VertexFormat::Float32,
+                format: wgpu::VertexFormat::Float32x4,
                 shader_location: 5,
                 offset: 40,
             },
-            // shadow
-            VertexAttribute {
-                format: wgpu::VertexFormat::Float32,
-                shader_location: 6,
-                offset: 44,
-            },
-            // alpha
-            VertexAttribute {
-                format: wgpu::VertexFormat::Float32,
-                shader_location: 7,
-                offset: 48,
-            },
             // lin_grad_p1+p2
             VertexAttribute {
                 format: wgpu::VertexFormat::Float32x4,
                 shader_location: 8,
-                offset: 52,
+                offset: 56,
             },
-            // lin_grad_p1_color
+            // rad_grad_p1+p2
             VertexAttribute {
                 format: wgpu::VertexFormat::Float32x4,
                 shader_location: 9,
-                offset: 68,
+                offset: 72,
             },
-            // lin_grad_p2_color
+            // con_grad_p1+p2
             VertexAttribute {
                 format: wgpu::VertexFormat::Float32x4,
                 shader_location: 10,
-                offset: 84,
+                offset: 88,
             },
-            // rad_grad_p1+p2
+            // image_tint
             VertexAttribute {
                 format: wgpu::VertexFormat::Float32x4,
                 shader_location: 11,
-                offset: 100,
+                offset: 104,
             },
-            // rad_grad_p1_color
+            // border
             VertexAttribute {
                 format: wgpu::VertexFormat::Float32x4,
-                shader_location: 12,
-                offset: 116,
+                shader_location: 6,
+                offset: 120,
             },
-            // rad_grad_p2_color
+            // border color, outline color, image uv min, image uv max
+            VertexAttribute {
+                format: wgpu::VertexFormat::Uint32x4,
+                shader_location: 7,
+                offset: 136,
+            },
+            // border_grad_p1+p2
             VertexAttribute {
                 format: wgpu::VertexFormat::Float32x4,
-                shader_location: 13,
-                offset: 132,
+                shader_location: 15,
+                offset: 152,
             },
-            // image_tint
+            // corner radii
             VertexAttribute {
                 format: wgpu::VertexFormat::Float32x4,
-                shader_location: 14,
-                offset: 148,
+                shader_location: 12,
+                offset: 184,
             },
-            // shadow_alpha
+            // linear, radial, conic and border gradient ramps
             VertexAttribute {
-                format: wgpu::VertexFormat::Float32,
-                shader_location: 15,
-                offset: 164,
+                format: wgpu::VertexFormat::Uint32x4,
+                shader_location: 13,
+                offset: 200,
+            },
+            // image scale and offset or nine-slice insets
+            VertexAttribute {
+                format: wgpu::VertexFormat::Float32x4,
+                shader_location: 14,
+                offset: 216,
             },
         ],
         step_mode: wgpu::VertexStepMode::Instance,
@@ -214,6 +250,55 @@ impl Rugui2WGPU {
             ],
             step_mode: wgpu::VertexStepMode::Instance,
         };
+    pub const SHADOW_VERTEX_BUFFER_LAYOUT: wgpu::VertexBufferLayout<'static> =
+        wgpu::VertexBufferLayout {
+            array_stride: size_of::<WGPUShadowInstance>() as u64,
+            attributes: &[
+                // center, size
+                VertexAttribute {
+                    format: wgpu::VertexFormat::Float32x4,
+                    shader_location: 0,
+                    offset: 0,
+                },
+                // corner radii
+                VertexAttribute {
+                    format: wgpu::VertexFormat::Float32x4,
+                    shader_location: 1,
+                    offset: 16,
+                },
+                // x, y, blur, spread
+                VertexAttribute {
+                    format: wgpu::VertexFormat::Float32x4,
+                    shader_location: 2,
+                    offset: 32,
+                },
+                // rotation
+                VertexAttribute {
+                    format: wgpu::VertexFormat::Float32,
+                    shader_location: 3,
+                    offset: 48,
+                },
+                // corner exponent
+                VertexAttribute {
+                    format: wgpu::VertexFormat::Float32,
+                    shader_location: 4,
+                    offset: 52,
+                },
+                // color
+                VertexAttribute {
+                    format: wgpu::VertexFormat::Uint32,
+                    shader_location: 5,
+                    offset: 56,
+                },
+                // inset
+                VertexAttribute {
+                    format: wgpu::VertexFormat::Uint32,
+                    shader_location: 6,
+                    offset: 60,
+                },
+            ],
+            step_mode: wgpu::VertexStepMode::Instance,
+        };
 
     pub fn new(queue: &wgpu::Queue, device: &wgpu::Device, size: (u32, u32)) -> Self {
         let dummy_texture =
@@ -255,9 +340,14 @@ impl Rugui2WGPU {
 
         let depth_buffer = DepthBuffer::new(device, size);
 
+        // the third group holds the gradient ramps, it is swapped for the glyph atlas while drawing text
         let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
             label: Some("Rugui2 Pipeline Layout Descriptor"),
-            bind_group_layouts: &[&dimensions_bind_group_layout, &texture_bind_group_layout],
+            bind_group_layouts: &[
+                &dimensions_bind_group_layout,
+                &texture_bind_group_layout,
+                &texture_bind_group_layout,
+            ],
             push_constant_ranges: &[],
         });
 
@@ -281,6 +371,8 @@ impl Rugui2WGPU {
                     constants: &HashMap::from([
                         ("LIN_GRADIENT".to_string(), Flags::LinearGradient.into()),
                         ("RAD_GRADIENT".to_string(), Flags::RadialGradient.into()),
+                        ("CONIC_GRADIENT".to_string(), Flags::ConicGradient.into()),
+                        ("NINE_SLICE".to_string(), Flags::NineSlice.into()),
                         ("TEXTURE".to_string(), Flags::Image.into()),
                     ]),
                     ..Default::default()
@@ -293,6 +385,8 @@ impl Rugui2WGPU {
                     constants: &HashMap::from([
                         ("LIN_GRADIENT".to_string(), Flags::LinearGradient.into()),
                         ("RAD_GRADIENT".to_string(), Flags::RadialGradient.into()),
+                        ("CONIC_GRADIENT".to_string(), Flags::ConicGradient.into()),
+                        ("NINE_SLICE".to_string(), Flags::NineSlice.into()),
                         ("TEXTURE".to_string(), Flags::Image.into()),
                     ]),
                     ..Default::default()
@@ -416,7 +510,11 @@ impl Rugui2WGPU {
 
         let stencil_pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
             label: Some("Rugui2 Stencil Pipeline Layout Descriptor"),
-            bind_group_layouts: &[&dimensions_bind_group_layout, &texture_bind_group_layout],
+            bind_group_layouts: &[
+                &dimensions_bind_group_layout,
+                &texture_bind_group_layout,
+                &texture_bind_group_layout,
+            ],
             push_constant_ranges: &[],
         });
 
@@ -550,6 +648,135 @@ impl Rugui2WGPU {
             cache: None,
         });
 
+        let stencil_state = wgpu::StencilFaceState {
+            compare: wgpu::CompareFunction::Equal,
+            fail_op: wgpu::StencilOperation::Keep,
+            depth_fail_op: wgpu::StencilOperation::Keep,
+            pass_op: wgpu::StencilOperation::Keep,
+        };
+
+        let border_constants = HashMap::from([
+            ("BORDER".to_string(), Flags::Border.into()),
+            ("BORDER_GRADIENT".to_string(), Flags::BorderGradient.into()),
+            ("OUTLINE".to_string(), Flags::Outline.into()),
+        ]);
+
+        let border_pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("Rugui2 Border Render Pipeline"),
+            layout: Some(&stencil_pipeline_layout),
+            vertex: wgpu::VertexState {
+                entry_point: Some("vs_border"),
+                module: &stencil_shaders,
+                buffers: &[Self::VERTEX_BUFFER_LAYOUT],
+                compilation_options: wgpu::PipelineCompilationOptions {
+                    constants: &border_constants,
+                    ..Default::default()
+                },
+            },
+            fragment: Some(wgpu::FragmentState {
+                entry_point: Some("fs_border"),
+                module: &stencil_shaders,
+                compilation_options: wgpu::PipelineCompilationOptions {
+                    constants: &border_constants,
+                    ..Default::default()
+                },
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: wgpu::TextureFormat::Bgra8UnormSrgb,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: None,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                conservative: false,
+                ..Default::default()
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: wgpu::TextureFormat::Stencil8,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::Always,
+                stencil: wgpu::StencilState {
+                    front: stencil_state,
+                    back: stencil_state,
+                    read_mask: 0xff,
+                    write_mask: 0xff,
+                },
+                bias: wgpu::DepthBiasState {
+                    constant: 0,
+                    slope_scale: 0.0,
+                    clamp: 0.0,
+                },
+            }),
+            multisample: wgpu::MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+            cache: None,
+        });
+
+        let shadow_pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("Rugui2 Shadow Render Pipeline"),
+            layout: Some(&stencil_pipeline_layout),
+            vertex: wgpu::VertexState {
+                entry_point: Some("vs_shadow"),
+                module: &stencil_shaders,
+                buffers: &[Self::SHADOW_VERTEX_BUFFER_LAYOUT],
+                compilation_options: wgpu::PipelineCompilationOptions {
+                    ..Default::default()
+                },
+            },
+            fragment: Some(wgpu::FragmentState {
+                entry_point: Some("fs_shadow"),
+                module: &stencil_shaders,
+                compilation_options: wgpu::PipelineCompilationOptions {
+                    ..Default::default()
+                },
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: wgpu::TextureFormat::Bgra8UnormSrgb,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: None,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                conservative: false,
+                ..Default::default()
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: wgpu::TextureFormat::Stencil8,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::Always,
+                stencil: wgpu::StencilState {
+                    front: stencil_state,
+                    back: stencil_state,
+                    read_mask: 0xff,
+                    write_mask: 0xff,
+                },
+                bias: wgpu::DepthBiasState {
+                    constant: 0,
+                    slope_scale: 0.0,
+                    clamp: 0.0,
+                },
+            }),
+            multisample: wgpu::MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+            cache: None,
+        });
+
         let scaler_ctx = ScaleContext::new();
         let scaler_image = Image::new();
 
@@ -591,6 +818,18 @@ impl Rugui2WGPU {
         }
         let glyph_atlas_tex = Texture::atlas(device);
 
+        let ramp_rows = 16;
+        let ramp_img = vec![0; ramp_rows * GRADIENT_RAMP_WIDTH * 4];
+        let ramp_tex = Texture::ramp(device, ramp_rows as u32);
+        let ramp_row_limit =
+            GRADIENT_RAMP_MAX_ROWS.min(device.limits().max_texture_dimension_2d as usize);
+
+        let image_atlas_tex = Texture::image_atlas(device);
+        let image_atlas_allocator = BucketedAtlasAllocator::new(Size2D::new(
+            IMAGE_ATLAS_SIDE as i32,
+            IMAGE_ATLAS_SIDE as i32,
+        ));
+
         Self {
             dimensions_buffer,
             dimensions_bind_group,
@@ -599,8 +838,12 @@ impl Rugui2WGPU {
             pipeline,
             stencil_pipeline,
             end_stencil_pipeline,
+            border_pipeline,
+            shadow_pipeline,
             dummy_texture,
             instance_buffers: Vec::new(),
+            element_slots: Vec::new(),
+            element_instances: 0,
             scaler_ctx,
             scaler_image,
             glyph_atlas_img,
@@ -613,6 +856,18 @@ impl Rugui2WGPU {
             last_written_glyph_atlas: 0,
             empty_glyph_key,
             cursor_glyph_key,
+            shadow_instance_buffers: Vec::new(),
+            shadow_instances: 0,
+            ramp_img,
+            ramp_tex,
+            ramp_rows: 0,
+            ramp_row_limit,
+            ramp_cache: HashMap::new(),
+            image_atlas_tex,
+            image_atlas_allocator,
+            image_atlas_map: HashMap::new(),
+            image_atlas_copies: Vec::new(),
+            stats: RenderStats::default(),
         }
     }
 
@@ -629,6 +884,21 @@ impl Rugui2WGPU {
         None
     }
 
+    /// Counts of the last `render`
+    pub fn stats(&self) -> RenderStats {
+        self.stats
+    }
+
+    /// Removes `image` from the image atlas, it is copied again the next time it is drawn
+    ///
+    /// Images are copied into the atlas once, call this after writing to their texture.
+    pub fn forget_image(&mut self, image: &Texture) {
+        let key = Arc::as_ptr(&image.texture) as usize;
+        if let Some((_, allocation)) = self.image_atlas_map.remove(&key) {
+            self.image_atlas_allocator.deallocate(allocation.id);
+        }
+    }
+
     pub fn get_depth_stencil_attachment(&self) -> wgpu::RenderPassDepthStencilAttachment {
         wgpu::RenderPassDepthStencilAttachment {
             depth_ops: None,
@@ -669,9 +939,55 @@ impl Rugui2WGPU {
         self.resize(gui, queue, device);
         self.prepare_buffers(gui.elements() as u64, device);
         self.glyph_instances = 0;
+        self.shadow_instances = 0;
+        self.ramp_rows = 0;
+        self.ramp_cache.clear();
+        self.element_instances = 0;
+        let allocator = &mut self.image_atlas_allocator;
+        self.image_atlas_map.retain(|_, (texture, allocation)| {
+            let alive = texture.strong_count() > 0;
+            if !alive {
+                allocator.deallocate(allocation.id);
+            }
+            alive
+        });
         if let Some(entry) = gui.get_entry() {
             self.prepare_element(entry, gui, device);
         }
+        if !self.image_atlas_copies.is_empty() {
+            let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Rugui2 image atlas copies"),
+            });
+            for (texture, allocation) in self.image_atlas_copies.drain(..) {
+                copy_into_atlas(&mut encoder, &texture, &self.image_atlas_tex.texture, allocation);
+            }
+            queue.submit([encoder.finish()]);
+        }
+        let ramp_capacity = self.ramp_img.len() / (GRADIENT_RAMP_WIDTH * 4);
+        if self.ramp_tex.texture.height() as usize != ramp_capacity {
+            self.ramp_tex = Texture::ramp(device, ramp_capacity as u32);
+        }
+        if self.ramp_rows > 0 {
+            queue.write_texture(
+                wgpu::TexelCopyTextureInfo {
+                    aspect: wgpu::TextureAspect::All,
+                    texture: &self.ramp_tex.texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d::ZERO,
+                },
+                &self.ramp_img[..self.ramp_rows * GRADIENT_RAMP_WIDTH * 4],
+                wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(GRADIENT_RAMP_WIDTH as u32 * 4),
+                    rows_per_image: None,
+                },
+                wgpu::Extent3d {
+                    width: GRADIENT_RAMP_WIDTH as u32,
+                    height: self.ramp_rows as u32,
+                    depth_or_array_layers: 1,
+                },
+            );
+        }
         for (buffer, data, _) in &self.instance_buffers {
             match queue.write_buffer_with(buffer, 0, NonZero::new(BUFFER_BYTES).unwrap()) {
                 Some(mut b) => {
@@ -688,6 +1004,12 @@ impl Rugui2WGPU {
                 _ => (),
             }
         }
+        for (buffer, data) in &self.shadow_instance_buffers {
+            let size = NonZero::new(SHADOW_BUFFER_BYTES).unwrap();
+            if let Some(mut b) = queue.write_buffer_with(buffer, 0, size) {
+                b.copy_from_slice(bytemuck::cast_slice(data));
+            }
+        }
         queue.write_texture(
             wgpu::TexelCopyTextureInfo {
                 aspect: wgpu::TextureAspect::All,
@@ -718,9 +1040,62 @@ impl Rugui2WGPU {
         let e = gui.get_element_unchecked(key);
         let elem_instance = e.instance();
         let cont = elem_instance.container;
-        let (buffer, idx) = self.get_buffer_idx(key.raw());
-        self.instance_buffers[buffer].1[idx as usize] =
-            WGPUElementInstance::from_instance(*elem_instance);
+        if self.element_slots.len() <= key.index() {
+            self.element_slots.resize(key.index() + 1, 0);
+        }
+        self.element_slots[key.index()] = self.element_instances;
+        let (buffer, idx) = self.get_buffer_idx(self.element_instances);
+        self.element_instances += 1;
+        let mut instance = WGPUElementInstance::from_instance(*elem_instance);
+        let (styles, ei) = (e.styles(), elem_instance);
+        let ramps = [
+            (
+                Flags::LinearGradient,
+                styles.grad_linear.get().as_ref(),
+                (ei.lin_grad_color1, ei.lin_grad_color2),
+            ),
+            (
+                Flags::RadialGradient,
+                styles.grad_radial.get().as_ref(),
+                (ei.rad_grad_color1, ei.rad_grad_color2),
+            ),
+            (
+                Flags::ConicGradient,
+                styles.grad_conic.get().as_ref(),
+                (ei.con_grad_color1, ei.con_grad_color2),
+            ),
+            (
+                Flags::BorderGradient,
+                styles.border.get().gradient.as_ref(),
+                (ei.border_grad_color1, ei.border_grad_color2),
+            ),
+        ]
+        .map(|(flag, gradient, (first, last))| match gradient {
+            Some(gradient) if flag.contained_in(ei.flags) => {
+                self.prepare_ramp(gradient, first, last).unwrap_or_else(|| {
+                    // out of ramp rows, the gradient is left out
+                    instance.flags &= !u32::from(flag);
+                    0
+                })
+            }
+            _ => 0,
+        });
+        instance.ramps = ramps;
+        let image = styles.image.get().as_ref().filter(|_| Flags::Image.contained_in(ei.flags));
+        let atlas = image.and_then(|image| self.atlas_image(&image.data));
+        if let Some(area) = atlas {
+            let uv = |x: f32, y: f32| {
+                pack_uv(
+                    area[0] + x * (area[2] - area[0]),
+                    area[1] + y * (area[3] - area[1]),
+                )
+            };
+            instance.packed[2] = uv(ei.image_uv[0], ei.image_uv[1]);
+            instance.packed[3] = uv(ei.image_uv[2], ei.image_uv[3]);
+        }
+        self.instance_buffers[buffer].1[idx as usize] = instance;
+        self.instance_buffers[buffer].2[idx as usize].atlas = atlas.is_some();
+        self.prepare_shadows(elem_instance, e.shadows(), buffer, idx, device);
         if let Some(text) = e.styles().rich_text.get() {
             text.with_shape(None, |shape, _, _| {
                 let text_start = self.get_glyph_instance_index(self.glyph_instances as _);
@@ -856,6 +1231,134 @@ impl Rugui2WGPU {
         }
     }
 
+    /// Finds `image` in the image atlas or queues a copy of it into a free area
+    ///
+    /// Returns the area of the image in atlas texture coordinates: min x, min y, max x, max y.
+    /// Large images, images that are rendered into and other formats keep their own binding.
+    fn atlas_image(&mut self, image: &Texture) -> Option<[f32; 4]> {
+        let texture = &image.texture;
+        let (w, h) = (texture.width(), texture.height());
+        let key = Arc::as_ptr(texture) as usize;
+        let allocation = match self.image_atlas_map.get(&key) {
+            Some((_, allocation)) => *allocation,
+            None => {
+                let usage = texture.usage();
+                if w == 0
+                    || h == 0
+                    || w > IMAGE_ATLAS_MAX_SIDE
+                    || h > IMAGE_ATLAS_MAX_SIDE
+                    || texture.format() != wgpu::TextureFormat::Rgba8UnormSrgb
+                    || texture.dimension() != wgpu::TextureDimension::D2
+                    || texture.sample_count() != 1
+                    || !usage.contains(wgpu::TextureUsages::COPY_SRC)
+                    || usage.contains(wgpu::TextureUsages::RENDER_ATTACHMENT)
+                {
+                    return None;
+                }
+                // one pixel around the image for linear filtering
+                let size = Size2D::new(w as i32 + 2, h as i32 + 2);
+                let allocation = self.image_atlas_allocator.allocate(size)?;
+                self.image_atlas_map
+                    .insert(key, (Arc::downgrade(texture), allocation));
+                self.image_atlas_copies.push((texture.clone(), allocation));
+                allocation
+            }
+        };
+        let side = IMAGE_ATLAS_SIDE as f32;
+        let (x, y) = (
+            allocation.rectangle.min.x as f32 + 1.0,
+            allocation.rectangle.min.y as f32 + 1.0,
+        );
+        Some([
+            x / side,
+            y / side,
+            (x + w as f32) / side,
+            (y + h as f32) / side,
+        ])
+    }
+
+    /// Samples a gradient into a row of the ramp texture, gradients with the same texels share a row
+    ///
+    /// Returns the row packed together with the spread mode as read by `ramp` in the shaders,
+    /// or `None` once the ramp texture can't grow any further.
+    fn prepare_ramp(
+        &mut self,
+        gradient: &Gradient,
+        first: [f32; 4],
+        last: [f32; 4],
+    ) -> Option<u32> {
+        const ROW_BYTES: usize = GRADIENT_RAMP_WIDTH * 4;
+        let mut texels = [0; ROW_BYTES];
+        for (i, texel) in texels.chunks_exact_mut(4).enumerate() {
+            let t = i as f32 / (GRADIENT_RAMP_WIDTH - 1) as f32;
+            let color = gradient.sample(first, last, t);
+            for (byte, c) in texel.iter_mut().zip(color) {
+                *byte = (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+            }
+        }
+        let mut hasher = DefaultHasher::new();
+        texels.hash(&mut hasher);
+        let hash = hasher.finish();
+        let cached = self
+            .ramp_cache
+            .get(&hash)
+            .copied()
+            .filter(|row| self.ramp_img[row * ROW_BYTES..(row + 1) * ROW_BYTES] == texels);
+        let row = match cached {
+            Some(row) => row,
+            None => {
+                let row = self.ramp_rows;
+                if row >= self.ramp_row_limit {
+                    return None;
+                }
+                if (row + 1) * ROW_BYTES > self.ramp_img.len() {
+                    let len = (self.ramp_img.len() * 2).min(self.ramp_row_limit * ROW_BYTES);
+                    self.ramp_img.resize(len, 0);
+                }
+                self.ramp_img[row * ROW_BYTES..(row + 1) * ROW_BYTES].copy_from_slice(&texels);
+                self.ramp_cache.insert(hash, row);
+                self.ramp_rows += 1;
+                row
+            }
+        };
+        debug_assert!(row < GRADIENT_RAMP_MAX_ROWS);
+        let spread = match gradient.spread {
+            Spread::Pad => 0,
+            Spread::Repeat => 1,
+            Spread::Reflect => 2,
+        };
+        Some(row as u32 | spread << 16)
+    }
+
+    /// Writes outer shadows followed by inset shadows into a single shadow buffer
+    fn prepare_shadows(
+        &mut self,
+        elem_instance: &ElementInstance,
+        shadows: &[ShadowInstance],
+        buffer: usize,
+        idx: u64,
+        device: &wgpu::Device,
+    ) {
+        self.reserve_shadows(shadows.len(), device);
+        let (shadow_buffer, start) = self.get_shadow_instance_index(self.shadow_instances as _);
+        let outer = shadows.iter().filter(|s| !s.inset);
+        let inset = shadows.iter().filter(|s| s.inset);
+        for shadow in outer.chain(inset) {
+            let (_, i) = self.get_shadow_instance_index(self.shadow_instances as _);
+            self.shadow_instance_buffers[shadow_buffer].1[i as usize] =
+                WGPUShadowInstance::new(elem_instance, shadow);
+            self.shadow_instances += 1;
+        }
+        let start = start as u32;
+        let split = start + shadows.iter().filter(|s| !s.inset).count() as u32;
+        let end = start + shadows.len() as u32;
+
+        let pi_data = &mut self.instance_buffers[buffer].2[idx as usize];
+        pi_data.shadow_buffer = shadow_buffer;
+        pi_data.outer_shadows = (start, split);
+        pi_data.inset_shadows = (split, end);
+    }
+
     fn try_get_or_cache_glyph(
         &mut self,
         ctx: &TextProccesor,
@@ -1032,6 +1535,26 @@ impl Rugui2WGPU {
         }
     }
 
+    /// Makes room for `count` shadows that do not cross the end of a buffer
+    fn reserve_shadows(&mut self, count: usize, device: &wgpu::Device) {
+        let size = SHADOW_BUFFER_SIZE as usize;
+        let in_buffer = self.shadow_instances % size;
+        if in_buffer + count > size {
+            self.shadow_instances += size - in_buffer;
+        }
+        let fit_to = self.shadow_instances + count;
+        while self.shadow_instance_buffers.len() * size < fit_to {
+            let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Rugui2 Shadow Instance Buffer"),
+                size: SHADOW_BUFFER_BYTES,
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+            let cache = vec![WGPUShadowInstance::default(); size];
+            self.shadow_instance_buffers.push((buffer, cache));
+        }
+    }
+
     pub fn get_buffer_idx(&self, i: u64) -> (usize, u64) {
         let buffer_idx = i / BUFFER_SIZE;
         let idx = i % BUFFER_SIZE;
@@ -1044,6 +1567,12 @@ impl Rugui2WGPU {
         (buffer_idx as usize, idx)
     }
 
+    pub fn get_shadow_instance_index(&self, i: u64) -> (usize, u64) {
+        let buffer_idx = i / SHADOW_BUFFER_SIZE;
+        let idx = i % SHADOW_BUFFER_SIZE;
+        (buffer_idx as usize, idx)
+    }
+
     pub fn render<'a, Msg: Clone>(
         &'a mut self,
         gui: &mut rugui2::Gui<Msg, Texture>,
@@ -1054,91 +1583,178 @@ impl Rugui2WGPU {
         } else {
             return;
         };
+        self.stats = RenderStats {
+            atlas_images: self.image_atlas_map.len() as u32,
+            ..Default::default()
+        };
         pass.set_pipeline(&self.pipeline);
         pass.set_bind_group(0, &self.dimensions_bind_group, &[]);
         pass.set_bind_group(1, self.dummy_texture.bind_group.as_ref(), &[]);
-        pass.set_bind_group(2, self.glyph_atlas_tex.bind_group.as_ref(), &[]);
-        pass.set_vertex_buffer(0, self.instance_buffers[0].0.slice(..));
+        pass.set_bind_group(2, self.ramp_tex.bind_group.as_ref(), &[]);
 
-        self.render_element(gui, entry, pass, 0, &mut 0);
+        let mut state = RenderState::default();
+        self.render_element(gui, entry, pass, 0, &mut state);
+        self.flush(pass, &mut state);
     }
 
     fn render_element<'a, Msg: Clone>(
         &mut self,
-        gui: &mut rugui2::Gui<Msg, Texture>,
+        gui: &rugui2::Gui<Msg, Texture>,
         key: ElementKey,
         pass: &mut wgpu::RenderPass<'a>,
         mut stencil_index: u32,
-        instance_buffer: &mut usize,
+        state: &mut RenderState,
     ) {
-        let (buffer, i) = self.get_buffer_idx(key.raw());
+        let (buffer, i) = self.get_buffer_idx(self.element_slots[key.index()]);
         let i = i as u32;
-        let prev_buffer_idx = *instance_buffer;
-        let change_buffer = buffer != *instance_buffer;
-        if change_buffer {
-            pass.set_vertex_buffer(0, self.instance_buffers[buffer].0.slice(..));
-            *instance_buffer = buffer;
+        let e = gui.get_element_unchecked(key);
+        let flags = e.instance().flags;
+        let overflow_hidden = Flags::OverflowHidden.contained_in(flags);
+
+        let pi_data = self.instance_buffers[buffer].2[i as usize];
+        let image = match e.styles().image.get() {
+            Some(_) if pi_data.atlas => Some(self.image_atlas_tex.bind_group.clone()),
+            Some(tex) if Flags::Image.contained_in(flags) => Some(tex.data.bind_group.clone()),
+            _ => None,
+        };
+
+        self.draw_shadows(pass, state, pi_data.shadow_buffer, pi_data.outer_shadows);
+        self.batch(pass, state, buffer, i, image);
+        self.draw_shadows(pass, state, pi_data.shadow_buffer, pi_data.inset_shadows);
+
+        if Flags::Border.contained_in(flags) || Flags::Outline.contained_in(flags) {
+            self.flush(pass, state);
+            pass.set_pipeline(&self.border_pipeline);
+            self.draw(pass, state, buffer, i..i + 1);
+            pass.set_pipeline(&self.pipeline);
         }
-        let e = gui.get_element_mut_unchecked(key);
-        let overflow_hidden = Flags::OverflowHidden.contained_in(e.instance().flags);
 
+        // the element and its border are drawn before clipping to the area inside the border
         if overflow_hidden {
+            self.flush(pass, state);
             pass.set_pipeline(&self.stencil_pipeline);
             pass.set_stencil_reference(stencil_index);
             stencil_index += 1;
-            pass.draw(0..6, i..i + 1);
+            self.draw(pass, state, buffer, i..i + 1);
 
             pass.set_stencil_reference(stencil_index);
             pass.set_pipeline(&self.pipeline);
         }
-        if let Some(tex) = e.styles().image.get() {
-            pass.set_bind_group(1, tex.data.bind_group.as_ref(), &[]);
-        }
-
-        pass.draw(0..6, i..i + 1);
-
-        let pi_data = &self.instance_buffers[buffer].2[i as usize];
-        if pi_data.text {
-            if pi_data.text_start != pi_data.text_end {
-                pass.set_pipeline(&self.glyph_pipeline);
-                pass.set_vertex_buffer(
-                    0,
-                    self.glyph_instance_buffers
-                        .get(pi_data.text_start.0)
-                        .expect(&format!("Font at: '{}' not loaded.", pi_data.text_start.0))
-                        .0
-                        .slice(..),
-                );
-                pass.draw(
-                    0..6,
-                    pi_data.text_start.1 as u32..pi_data.text_end.1 as u32,
-                );
-                pass.set_pipeline(&self.pipeline);
-                pass.set_vertex_buffer(0, self.instance_buffers[buffer].0.slice(..));
-            }
 
+        if pi_data.text && pi_data.text_start != pi_data.text_end {
+            self.flush(pass, state);
+            pass.set_pipeline(&self.glyph_pipeline);
+            pass.set_bind_group(2, self.glyph_atlas_tex.bind_group.as_ref(), &[]);
+            pass.set_vertex_buffer(
+                0,
+                self.glyph_instance_buffers
+                    .get(pi_data.text_start.0)
+                    .unwrap_or_else(|| {
+                        panic!(
+                            "Glyph instance buffer {} not allocated.",
+                            pi_data.text_start.0
+                        )
+                    })
+                    .0
+                    .slice(..),
+            );
+            state.buffer = None;
+            self.stats.draw_calls += 1;
+            pass.draw(
+                0..6,
+                pi_data.text_start.1 as u32..pi_data.text_end.1 as u32,
+            );
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(2, self.ramp_tex.bind_group.as_ref(), &[]);
         }
 
-        if let Some(children) = e.children.take() {
-            for child in &children {
-                self.render_element(gui, *child, pass, stencil_index, instance_buffer);
+        if let Some(children) = &e.children {
+            for child in children {
+                self.render_element(gui, *child, pass, stencil_index, state);
             }
-            gui.get_element_mut_unchecked(key).children = Some(children);
         }
 
         if overflow_hidden {
+            self.flush(pass, state);
             pass.set_pipeline(&self.end_stencil_pipeline);
             pass.set_stencil_reference(stencil_index);
-            pass.draw(0..6, i..i + 1);
+            self.draw(pass, state, buffer, i..i + 1);
 
             pass.set_pipeline(&self.pipeline);
             pass.set_stencil_reference(stencil_index - 1);
         }
+    }
+
+    /// Adds the element at `i` to the waiting elements, which are drawn first
+    /// when it does not directly follow them or needs another image
+    fn batch(
+        &mut self,
+        pass: &mut wgpu::RenderPass,
+        state: &mut RenderState,
+        buffer: usize,
+        i: u32,
+        image: Option<Arc<wgpu::BindGroup>>,
+    ) {
+        self.stats.elements += 1;
+        if let Some(batch) = &mut state.batch {
+            if batch.accepts(buffer, i, image.as_ref()) {
+                batch.push(image);
+                return;
+            }
+        }
+        self.flush(pass, state);
+        state.batch = Some(Batch::new(buffer, i, image));
+    }
+
+    /// Draws the waiting elements with one draw call
+    fn flush(&mut self, pass: &mut wgpu::RenderPass, state: &mut RenderState) {
+        let batch = match state.batch.take() {
+            Some(batch) => batch,
+            None => return,
+        };
+        if let Some(image) = batch.image {
+            if !state.image.as_ref().is_some_and(|bound| Arc::ptr_eq(bound, &image)) {
+                pass.set_bind_group(1, image.as_ref(), &[]);
+                self.stats.image_binds += 1;
+                state.image = Some(image);
+            }
+        }
+        self.draw(pass, state, batch.buffer, batch.instances);
+    }
+
+    /// Draws `instances` of an instance buffer with the current pipeline
+    fn draw(
+        &mut self,
+        pass: &mut wgpu::RenderPass,
+        state: &mut RenderState,
+        buffer: usize,
+        instances: Range<u32>,
+    ) {
+        if state.buffer != Some(buffer) {
+            pass.set_vertex_buffer(0, self.instance_buffers[buffer].0.slice(..));
+            state.buffer = Some(buffer);
+        }
+        self.stats.draw_calls += 1;
+        pass.draw(0..6, instances);
+    }
 
-        if change_buffer {
-            *instance_buffer = prev_buffer_idx;
-            pass.set_vertex_buffer(0, self.instance_buffers[prev_buffer_idx].0.slice(..));
+    fn draw_shadows(
+        &mut self,
+        pass: &mut wgpu::RenderPass,
+        state: &mut RenderState,
+        shadow_buffer: usize,
+        range: (u32, u32),
+    ) {
+        if range.0 == range.1 {
+            return;
         }
+        self.flush(pass, state);
+        pass.set_pipeline(&self.shadow_pipeline);
+        pass.set_vertex_buffer(0, self.shadow_instance_buffers[shadow_buffer].0.slice(..));
+        state.buffer = None;
+        self.stats.draw_calls += 1;
+        pass.draw(0..6, range.0..range.1);
+        pass.set_pipeline(&self.pipeline);
     }
 
     fn raster_glyph(
@@ -1185,23 +1801,45 @@ struct WGPUElementInstance {
     pub rotation: f32,
     pub color: [f32; 4],
     pub flags: u32,
-    pub round: f32,
+    /// exponent of the corner superellipse
+    pub corner: f32,
     pub shadow: f32,
     pub alpha: f32,
+    pub shadow_alpha: f32,
     /// x, y
     pub lin_grad_p1: [f32; 2],
     /// x, y
     pub lin_grad_p2: [f32; 2],
-    pub lin_grad_color1: [f32; 4],
-    pub lin_grad_color2: [f32; 4],
     /// x, y
     pub rad_grad_p1: [f32; 2],
     /// x, y
     pub rad_grad_p2: [f32; 2],
-    pub rad_grad_color1: [f32; 4],
-    pub rad_grad_color2: [f32; 4],
+    /// x, y
+    pub con_grad_p1: [f32; 2],
+    /// x, y
+    pub con_grad_p2: [f32; 2],
     pub image_tint: [f32; 4],
-    pub shadow_alpha: f32,
+    /// top, right, bottom, left
+    pub border: [f32; 4],
+    /// Words read by the shaders by slot:
+    /// 0. border color packed by `pack_color`
+    /// 1. outline color packed by `pack_color`
+    /// 2. min image uv packed by `pack_uv`, in the image atlas for atlas images
+    /// 3. max image uv packed by `pack_uv`, in the image atlas for atlas images
+    pub packed: [u32; 4],
+    /// x, y
+    pub border_grad_p1: [f32; 2],
+    /// x, y
+    pub border_grad_p2: [f32; 2],
+    /// dash, gap, outline width, outline offset
+    pub border_params: [f32; 4],
+    /// top left, top right, bottom right, bottom left
+    pub round: [f32; 4],
+    /// linear, radial, conic, border; ramp row and spread packed by `prepare_ramp`
+    pub ramps: [u32; 4],
+    /// Scale x, y and offset x, y from pixels of the element to the drawn image,
+    /// or nine-slice insets top, right, bottom, left with `Flags::NineSlice`
+    pub image_fit: [f32; 4],
 }
 
 impl WGPUElementInstance {
@@ -1220,15 +1858,22 @@ impl From<ElementInstance> for WGPUElementInstance {
             alpha,
             lin_grad_p1,
             lin_grad_p2,
-            lin_grad_color1,
-            lin_grad_color2,
+            lin_grad_color1: _,
+            lin_grad_color2: _,
             rad_grad_p1,
             rad_grad_p2,
-            rad_grad_color1,
-            rad_grad_color2,
+            rad_grad_color1: _,
+            rad_grad_color2: _,
+            con_grad_p1,
+            con_grad_p2,
+            con_grad_color1: _,
+            con_grad_color2: _,
             image_tint,
-            shadow,
             image_size: _,
+            image_offset,
+            image_draw_size,
+            image_uv,
+            image_slice,
             scroll: _,
             padding: _,
             shadow_alpha,
@@ -1238,6 +1883,16 @@ impl From<ElementInstance> for WGPUElementInstance {
             text_wrap: _,
             text_align: _,
             margin: _,
+            border,
+            border_color,
+            border_grad_p1,
+            border_grad_p2,
+            border_grad_color1: _,
+            border_grad_color2: _,
+            border_dash,
+            outline_width,
+            outline_offset,
+            outline_color,
         } = value;
         Self {
             pos: container.pos.into(),
@@ -1245,28 +1900,225 @@ impl From<ElementInstance> for WGPUElementInstance {
             rotation: container.rotation.into(),
             color,
             flags,
-            round,
-            shadow,
+            corner: round.shape.exponent(),
+            // the `shadow` style is drawn by the shadow pipeline
+            shadow: 0.0,
             alpha,
             lin_grad_p1: lin_grad_p1.into(),
             lin_grad_p2: lin_grad_p2.into(),
-            lin_grad_color1,
-            lin_grad_color2,
             rad_grad_p1: rad_grad_p1.into(),
             rad_grad_p2: rad_grad_p2.into(),
-            rad_grad_color1,
-            rad_grad_color2,
+            con_grad_p1: con_grad_p1.into(),
+            con_grad_p2: con_grad_p2.into(),
             image_tint,
             shadow_alpha,
+            border: [border.top, border.right, border.bottom, border.left],
+            packed: [
+                pack_color(border_color),
+                pack_color(outline_color),
+                pack_uv(image_uv[0], image_uv[1]),
+                pack_uv(image_uv[2], image_uv[3]),
+            ],
+            border_grad_p1: border_grad_p1.into(),
+            border_grad_p2: border_grad_p2.into(),
+            border_params: [border_dash.0, border_dash.1, outline_width, outline_offset],
+            round: [
+                round.top_left,
+                round.top_right,
+                round.bottom_right,
+                round.bottom_left,
+            ],
+            ramps: [0; 4],
+            image_fit: if Flags::NineSlice.contained_in(flags) {
+                [image_slice.top, image_slice.right, image_slice.bottom, image_slice.left]
+            } else if image_draw_size.0 > 0.0 && image_draw_size.1 > 0.0 {
+                [
+                    1.0 / image_draw_size.0,
+                    1.0 / image_draw_size.1,
+                    -image_offset.0 / image_draw_size.0,
+                    -image_offset.1 / image_draw_size.1,
+                ]
+            } else {
+                // every pixel falls outside of the image
+                [0.0, 0.0, -1.0, -1.0]
+            },
         }
     }
 }
 
+/// Copies `image` into `atlas` at `allocation`, surrounded by a copy of its edge pixels
+/// so that linear filtering does not blend in the neighbouring images
+fn copy_into_atlas(
+    encoder: &mut wgpu::CommandEncoder,
+    image: &wgpu::Texture,
+    atlas: &wgpu::Texture,
+    allocation: Allocation,
+) {
+    let (w, h) = (image.width(), image.height());
+    let min = allocation.rectangle.min;
+    let (x, y) = (min.x as u32 + 1, min.y as u32 + 1);
+    // source, destination, size
+    let copies = [
+        ((0, 0), (x, y), (w, h)),
+        ((0, 0), (x - 1, y), (1, h)),
+        ((w - 1, 0), (x + w, y), (1, h)),
+        ((0, 0), (x, y - 1), (w, 1)),
+        ((0, h - 1), (x, y + h), (w, 1)),
+    ];
+    for ((sx, sy), (dx, dy), (width, height)) in copies {
+        encoder.copy_texture_to_texture(
+            wgpu::TexelCopyTextureInfo {
+                aspect: wgpu::TextureAspect::All,
+                texture: image,
+                mip_level: 0,
+                origin: wgpu::Origin3d { x: sx, y: sy, z: 0 },
+            },
+            wgpu::TexelCopyTextureInfo {
+                aspect: wgpu::TextureAspect::All,
+                texture: atlas,
+                mip_level: 0,
+                origin: wgpu::Origin3d { x: dx, y: dy, z: 0 },
+            },
+            wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+    }
+}
+
+/// Packs a texture coordinate into 16 bits per axis, read by `unpack2x16unorm` in the shaders
+fn pack_uv(x: f32, y: f32) -> u32 {
+    let unorm = |c: f32| (c.clamp(0.0, 1.0) * 65535.0).round() as u32;
+    unorm(x) | unorm(y) << 16
+}
+
+/// Packs a color into 8 bits per channel, read by `unpack4x8unorm` in the shaders
+fn pack_color(color: [f32; 4]) -> u32 {
+    color
+        .iter()
+        .enumerate()
+        .map(|(i, c)| ((c.clamp(0.0, 1.0) * 255.0).round() as u32) << (i * 8))
+        .sum()
+}
+
 #[derive(Debug, Copy, Clone, Default)]
 struct PerElementData {
     pub text: bool,
     pub text_start: (usize, u64),
     pub text_end: (usize, u64),
+    pub shadow_buffer: usize,
+    /// Range of `shadow_buffer` drawn under the element
+    pub outer_shadows: (u32, u32),
+    /// Range of `shadow_buffer` drawn over the background of the element
+    pub inset_shadows: (u32, u32),
+    /// The image of the element is drawn from the image atlas
+    pub atlas: bool,
+}
+
+/// Bindings of the render pass and the elements waiting to be drawn together
+#[derive(Debug, Default)]
+struct RenderState {
+    /// Elements waiting to be drawn
+    batch: Option<Batch<wgpu::BindGroup>>,
+    /// Instance buffer bound to the first vertex slot
+    buffer: Option<usize>,
+    /// Image bound to the second group
+    image: Option<Arc<wgpu::BindGroup>>,
+}
+
+/// Consecutive elements of an instance buffer drawn with one draw call
+#[derive(Debug)]
+struct Batch<I> {
+    buffer: usize,
+    instances: Range<u32>,
+    /// Image of the elements, `None` when none of them draws one
+    image: Option<Arc<I>>,
+}
+
+impl<I> Batch<I> {
+    fn new(buffer: usize, i: u32, image: Option<Arc<I>>) -> Self {
+        Self {
+            buffer,
+            instances: i..i + 1,
+            image,
+        }
+    }
+
+    /// The element at `i` directly follows the batch and draws no other image
+    fn accepts(&self, buffer: usize, i: u32, image: Option<&Arc<I>>) -> bool {
+        let same_image = match (&self.image, image) {
+            (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+            _ => true,
+        };
+        self.buffer == buffer && self.instances.end == i && same_image
+    }
+
+    /// Adds the next element, check it with `accepts` first
+    fn push(&mut self, image: Option<Arc<I>>) {
+        self.instances.end += 1;
+        if image.is_some() {
+            self.image = image;
+        }
+    }
+}
+
+/// Counts of the last `Rugui2WGPU::render`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RenderStats {
+    /// Draw calls of all pipelines
+    pub draw_calls: u32,
+    /// Elements drawn, consecutive elements with the same image share one draw call
+    pub elements: u32,
+    /// Times an element image was bound, images in the image atlas share one binding
+    pub image_binds: u32,
+    /// Images held by the image atlas
+    pub atlas_images: u32,
+}
+
+#[derive(bytemuck::Zeroable, bytemuck::NoUninit, Debug, Copy, Clone, Default, PartialEq)]
+#[repr(C)]
+struct WGPUShadowInstance {
+    /// center, size of the element
+    pub rect: [f32; 4],
+    /// top left, top right, bottom right, bottom left
+    pub round: [f32; 4],
+    /// x, y, blur, spread
+    pub params: [f32; 4],
+    pub rotation: f32,
+    /// exponent of the corner superellipse
+    pub corner: f32,
+    /// packed by `pack_color`
+    pub color: u32,
+    pub inset: u32,
+}
+
+impl WGPUShadowInstance {
+    fn new(elem: &ElementInstance, shadow: &ShadowInstance) -> Self {
+        let Container {
+            pos,
+            size,
+            rotation,
+        } = elem.container;
+        let round = elem.round;
+        let mut color = shadow.color;
+        color[3] *= elem.alpha;
+        Self {
+            rect: [pos.0, pos.1, size.0, size.1],
+            round: [
+                round.top_left,
+                round.top_right,
+                round.bottom_right,
+                round.bottom_left,
+            ],
+            params: [shadow.offset.0, shadow.offset.1, shadow.blur, shadow.spread],
+            rotation,
+            corner: round.shape.exponent(),
+            color: pack_color(color),
+            inset: shadow.inset as u32,
+        }
+    }
 }
 
 #[derive(bytemuck::Zeroable, bytemuck::NoUninit, Debug, Copy, Clone, Default, PartialEq)]
@@ -1279,3 +2131,28 @@ struct WGPUGlyphInstance {
     pub origin: [f32; 2],
     pub rotation: f32,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn batching() {
+        let (icon, other) = (Arc::new(0u8), Arc::new(0u8));
+        let mut batch = Batch::new(0, 3, None);
+        assert!(batch.accepts(0, 4, None));
+        assert!(batch.accepts(0, 4, Some(&icon)));
+        batch.push(Some(icon.clone()));
+        // the image of the batch stays for elements without one
+        assert!(batch.accepts(0, 5, None));
+        batch.push(None);
+        assert!(batch.image.as_ref().is_some_and(|image| Arc::ptr_eq(image, &icon)));
+        assert!(batch.accepts(0, 6, Some(&icon)));
+        // equal images of different textures are bound separately
+        assert!(!batch.accepts(0, 6, Some(&other)));
+        assert!(!batch.accepts(0, 7, Some(&icon)));
+        assert!(!batch.accepts(0, 5, None));
+        assert!(!batch.accepts(1, 6, None));
+        assert_eq!(batch.instances, 3..6);
+    }
+}
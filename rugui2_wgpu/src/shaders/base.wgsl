@@ -3,25 +3,31 @@
 @group(1)@binding(0) var t_diffuse: texture_2d<f32>;
 @group(1)@binding(1) var t_sampler: sampler;
 
+@group(2)@binding(0) var t_ramp: texture_2d<f32>;
+@group(2)@binding(1) var s_ramp: sampler;
+
 struct VertexInput {
     @builtin(vertex_index) index: u32,
     @builtin(instance_index) instance_index: u32,
-    @location(0) position: vec2<f32>,
-    @location(1) size: vec2<f32>,
+    // center, size
+    @location(0) rect: vec4<f32>,
     @location(2) rotation: f32,
     @location(3) color: vec4<f32>,
     @location(4) flags: u32,
-    @location(5) round: f32,
-    @location(6) shadow: f32,
-    @location(7) alpha: f32,
+    // corner exponent, shadow, alpha, shadow_alpha
+    @location(5) shape: vec4<f32>,
+    // border color, outline color, image uv min, image uv max
+    @location(7) packed: vec4<u32>,
     @location(8) lin_grad_p1p2: vec4<f32>,
-    @location(9) lin_grad_p1_color: vec4<f32>,
-    @location(10) lin_grad_p2_color: vec4<f32>,
-    @location(11) rad_grad_p1p2: vec4<f32>,
-    @location(12) rad_grad_p1_color: vec4<f32>,
-    @location(13) rad_grad_p2_color: vec4<f32>,
-    @location(14) texture_tint: vec4<f32>,
-    @location(15) shadow_alpha: f32,
+    @location(9) rad_grad_p1p2: vec4<f32>,
+    @location(10) con_grad_p1p2: vec4<f32>,
+    @location(11) texture_tint: vec4<f32>,
+    // top left, top right, bottom right, bottom left
+    @location(12) round: vec4<f32>,
+    // linear, radial, conic, border
+    @location(13) ramps: vec4<u32>,
+    // image scale and offset or nine-slice insets
+    @location(14) image_fit: vec4<f32>,
 }
 
 struct VertexOutput {
@@ -31,18 +37,16 @@ struct VertexOutput {
     @location(2) @interpolate(flat) color: vec4<f32>,
     @location(3) @interpolate(flat) flags: u32,
     @location(4) @interpolate(flat) size: vec2<f32>,
-    @location(5) @interpolate(flat) round: f32,
-    @location(6) @interpolate(flat) shadow: f32,
-    @location(7) @interpolate(flat) alpha: f32,
-    @location(8) @interpolate(flat) lin_grad_p1p2: vec4<f32>,
-    @location(9) @interpolate(flat) lin_grad_p1_color: vec4<f32>,
-    @location(10) @interpolate(flat) lin_grad_p2_color: vec4<f32>,
-    @location(11) @interpolate(flat) rad_grad_p1p2: vec4<f32>,
-    @location(12) @interpolate(flat) rad_grad_p1_color: vec4<f32>,
-    @location(13) @interpolate(flat) rad_grad_p2_color: vec4<f32>,
-    @location(14) @interpolate(flat) texture_tint: vec4<f32>,
-    @location(15) @interpolate(flat) shadow_alpha: f32,
-    @location(16) uv: vec2<f32>,
+    @location(5) @interpolate(flat) round: vec4<f32>,
+    // corner exponent, shadow, alpha, shadow_alpha
+    @location(6) @interpolate(flat) shape: vec4<f32>,
+    @location(7) @interpolate(flat) lin_grad_p1p2: vec4<f32>,
+    @location(8) @interpolate(flat) rad_grad_p1p2: vec4<f32>,
+    @location(9) @interpolate(flat) con_grad_p1p2: vec4<f32>,
+    @location(10) @interpolate(flat) texture_tint: vec4<f32>,
+    @location(11) @interpolate(flat) image_fit: vec4<f32>,
+    @location(12) @interpolate(flat) ramps: vec4<u32>,
+    @location(13) @interpolate(flat) image_uv: vec4<f32>,
 }
 
 
@@ -50,26 +54,23 @@ struct VertexOutput {
 fn vs_main(in: VertexInput) -> VertexOutput {
     var out: VertexOutput;
 
-    var size_wshadow = in.size + in.shadow*2.0;
+    var size_wshadow = in.rect.zw + in.shape.y*2.0;
 
     // Calculate vertex position
     var position = vertex_position(in.index);
-    out.uv = position + 0.5;
     out.size = size_wshadow * 0.5;
 
     out.round = in.round;
-    out.shadow = in.shadow;
-    out.alpha = in.alpha;
+    out.shape = in.shape;
     out.color = in.color;
     out.flags = in.flags;
     out.lin_grad_p1p2 = in.lin_grad_p1p2;
-    out.lin_grad_p1_color = in.lin_grad_p1_color;
-    out.lin_grad_p2_color = in.lin_grad_p2_color;
     out.rad_grad_p1p2 = in.rad_grad_p1p2;
-    out.rad_grad_p1_color = in.rad_grad_p1_color;
-    out.rad_grad_p2_color = in.rad_grad_p2_color;
+    out.con_grad_p1p2 = in.con_grad_p1p2;
     out.texture_tint = in.texture_tint;
-    out.shadow_alpha = in.shadow_alpha;
+    out.image_fit = in.image_fit;
+    out.image_uv = vec4(unpack2x16unorm(in.packed.z), unpack2x16unorm(in.packed.w));
+    out.ramps = in.ramps;
 
     // Scale and rotate the position
     var scale = size_wshadow * position;
@@ -82,7 +83,7 @@ fn vs_main(in: VertexInput) -> VertexOutput {
     );
     
     // Translate to the new position
-    var pixel_position = in.position + rotated_position;
+    var pixel_position = in.rect.xy + rotated_position;
     out.pixel_pos = pixel_position;
     
     // Convert to screen space
@@ -96,7 +97,11 @@ fn vs_main(in: VertexInput) -> VertexOutput {
 
 override LIN_GRADIENT: u32;
 override RAD_GRADIENT: u32;
+override CONIC_GRADIENT: u32;
 override TEXTURE: u32;
+override NINE_SLICE: u32;
+
+const TAU = 6.28318530718;
 
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0)vec4<f32> {
@@ -105,48 +110,112 @@ fn fs_main(in: VertexOutput) -> @location(0)vec4<f32> {
     var color = vec3(0.0);
     var max_alpha = 0.0;
     if bool(in.flags & TEXTURE) {
-        var c = textureSample(t_diffuse, t_sampler, in.uv) * in.texture_tint;
+        var elem_size = (in.size - in.shape.y) * 2.0;
+        var local = in.pixel_size + elem_size * 0.5;
+        // position in the drawn image, 0.0 to 1.0 inside of it
+        var s = local * in.image_fit.xy + in.image_fit.zw;
+        if bool(in.flags & NINE_SLICE) {
+            var source = (in.image_uv.zw - in.image_uv.xy) * vec2<f32>(textureDimensions(t_diffuse));
+            var insets = in.image_fit;
+            s = vec2(
+                slice_axis(local.x, elem_size.x, insets.w, insets.y, source.x),
+                slice_axis(local.y, elem_size.y, insets.x, insets.z, source.y),
+            );
+        }
+        var uv = mix(in.image_uv.xy, in.image_uv.zw, clamp(s, vec2(0.0), vec2(1.0)));
+        var c = textureSample(t_diffuse, t_sampler, uv) * in.texture_tint;
+        c.a *= f32(all(s >= vec2(0.0)) && all(s <= vec2(1.0)));
+        color = mix(color, c.rgb, c.a);
+        max_alpha = max(max_alpha, c.a);
+    }
+    if bool(in.flags & CONIC_GRADIENT) {
+        var p1 = in.con_grad_p1p2.rg;
+        var p2 = in.con_grad_p1p2.ba;
+        var d = in.pixel_pos - p1;
+        var start = p2 - p1;
+
+        var turn = (atan2(d.y, d.x) - atan2(start.y, start.x)) / TAU;
+        var c = ramp(in.ramps.z, fract(turn));
         color = mix(color, c.rgb, c.a);
         max_alpha = max(max_alpha, c.a);
     }
     if bool(in.flags & RAD_GRADIENT) {
         var p1 = in.rad_grad_p1p2.rg;
         var p2 = in.rad_grad_p1p2.ba;
-        var p1color = in.rad_grad_p1_color;
-        var p2color = in.rad_grad_p2_color;
-        
-        var c = mix(p1color, p2color, distance(p1, in.pixel_pos) / distance(p1, p2));
+
+        var c = ramp(in.ramps.y, distance(p1, in.pixel_pos) / distance(p1, p2));
         color = mix(color, c.rgb, c.a);
         max_alpha = max(max_alpha, c.a);
     }
     if bool(in.flags & LIN_GRADIENT) {
         var p1 = in.lin_grad_p1p2.rg;
         var p2 = in.lin_grad_p1p2.ba;
-        var p1color = in.lin_grad_p1_color;
-        var p2color = in.lin_grad_p2_color;
 
         var gradient_factor = dot(in.pixel_pos - p1, p2 - p1) / dot(p2 - p1, p2 - p1);
-        var c = mix(p1color, p2color, clamp(gradient_factor, 0.0, 1.0));
+        var c = ramp(in.ramps.x, gradient_factor);
         color = mix(color, c.rgb, c.a);
         max_alpha = max(max_alpha, c.a);
     }
     color = mix(color, in.color.rgb, in.color.a);
     max_alpha = max(max_alpha, in.color.a);
-    var pos_abs = abs(in.pixel_size);
-    if pos_abs.x > (in.size.x - in.round - in.shadow) && pos_abs.y > (in.size.y - in.round - in.shadow) {
-        let the_d = distance(pos_abs, in.size - in.round - in.shadow);
-        let new_alpha = clamp(1.0-((the_d - in.round) / in.shadow), 0.0, 1.0);
-        if new_alpha == 1.0 {
-            max_alpha *= new_alpha;
+    var shadow = in.shape.y;
+    var d = rounded_box(in.pixel_size, in.size - shadow, in.round, in.shape.x);
+    if d > 0.0 {
+        if shadow > 0.0 {
+            max_alpha *= clamp(1.0 - d / shadow, 0.0, 1.0) * in.shape.w;
         } else {
-            max_alpha *= new_alpha * in.shadow_alpha;
+            discard;
         }
-    } else if pos_abs.x > (in.size.x - in.shadow) {
-        max_alpha *= (1.0 - (pos_abs.x - in.size.x + in.shadow) / (in.shadow)) * in.shadow_alpha;
-    } else if pos_abs.y > (in.size.y- in.shadow) {
-        max_alpha *= (1.0 - (pos_abs.y - in.size.y + in.shadow) / (in.shadow)) * in.shadow_alpha;
     }
-    return vec4(pow(color, vec3(gamma_exp))*gamma_mul, max_alpha * in.alpha);
+    return vec4(pow(color, vec3(gamma_exp))*gamma_mul, max_alpha * in.shape.z);
+}
+
+// Nine-slice position along one axis of the source, from 0.0 to 1.0
+//
+// `d` is the distance from the start of the element of length `size`, `lo` and `hi`
+// are the insets at both ends in pixels of the source, which is `source` pixels long.
+// Insets keep their size unless they do not fit into the element together.
+fn slice_axis(d: f32, size: f32, lo: f32, hi: f32, source: f32) -> f32 {
+    var k = min(1.0, size / max(lo + hi, 1e-4));
+    if d < lo * k {
+        return d / k / source;
+    }
+    if d > size - hi * k {
+        return 1.0 - (size - d) / k / source;
+    }
+    var middle = max(size - (lo + hi) * k, 1e-4);
+    return (lo + (d - lo * k) / middle * (source - lo - hi)) / source;
+}
+
+// Color of a gradient at `t`, 0.0 is the first end point and 1.0 the second
+//
+// `packed` holds the row of the gradient in the ramp texture in the low 16 bits
+// and the spread mode above them: 0 pads, 1 repeats and 2 reflects.
+fn ramp(packed: u32, t: f32) -> vec4<f32> {
+    var spread = packed >> 16u;
+    var x = clamp(t, 0.0, 1.0);
+    if spread == 1u {
+        x = fract(t);
+    } else if spread == 2u {
+        x = 1.0 - abs(fract(t * 0.5) * 2.0 - 1.0);
+    }
+    var dims = vec2<f32>(textureDimensions(t_ramp));
+    var row = f32(packed & 0xffffu);
+    // sample between the centers of the first and last texel
+    var uv = vec2((x * (dims.x - 1.0) + 0.5) / dims.x, (row + 0.5) / dims.y);
+    return textureSampleLevel(t_ramp, s_ramp, uv, 0.0);
+}
+
+// Signed distance from the edge of a box with rounded corners
+//
+// Corners are superellipses with the exponent `corner`, 2.0 gives circular corners.
+fn rounded_box(p: vec2<f32>, size: vec2<f32>, round: vec4<f32>, corner: f32) -> f32 {
+    var r = select(select(round.x, round.y, p.x > 0.0), select(round.w, round.z, p.x > 0.0), p.y > 0.0);
+    r = min(r, min(size.x, size.y));
+    var q = abs(p) - size + r;
+    var m = max(q, vec2(0.0));
+    var outside = pow(pow(m.x, corner) + pow(m.y, corner), 1.0 / corner);
+    return outside + min(max(q.x, q.y), 0.0) - r;
 }
 
 fn vertex_position(vertex_index: u32) -> vec2<f32> {
@@ -1,30 +1,29 @@
 @group(0)@binding(0) var<uniform> screen_size: vec2<f32>;
 
+@group(2)@binding(0) var t_ramp: texture_2d<f32>;
+@group(2)@binding(1) var s_ramp: sampler;
+
 struct VertexInput {
     @builtin(vertex_index) index: u32,
     @builtin(instance_index) instance_index: u32,
-    @location(0) position: vec2<f32>,
-    @location(1) size: vec2<f32>,
+    // center, size
+    @location(0) rect: vec4<f32>,
     @location(2) rotation: f32,
     @location(3) color: vec4<f32>,
     @location(4) flags: u32,
-    @location(5) round: f32,
-    @location(6) shadow: f32,
-    @location(7) alpha: f32,
-    @location(8) lin_grad_p1p2: vec4<f32>,
-    @location(9) lin_grad_p1_color: vec4<f32>,
-    @location(10) lin_grad_p2_color: vec4<f32>,
-    @location(11) rad_grad_p1p2: vec4<f32>,
-    @location(12) rad_grad_p1_color: vec4<f32>,
-    @location(13) rad_grad_p2_color: vec4<f32>,
-    @location(14) texture_tint: vec4<f32>,
+    // corner exponent, shadow, alpha, shadow_alpha
+    @location(5) shape: vec4<f32>,
+    // top, right, bottom, left
+    @location(6) border: vec4<f32>,
+    // top left, top right, bottom right, bottom left
+    @location(12) round: vec4<f32>,
 }
 
 struct VertexOutput {
     @builtin(position) position: vec4<f32>,
     @location(0) pixel_size: vec2<f32>,
-    @location(1) @interpolate(flat) round: f32,
-    @location(2) @interpolate(flat) shadow: f32,
+    @location(1) @interpolate(flat) round: vec4<f32>,
+    @location(2) @interpolate(flat) corner: f32,
     @location(3) @interpolate(flat) size: vec2<f32>,
 }
 
@@ -33,24 +32,30 @@ struct VertexOutput {
 fn vs_main(in: VertexInput) -> VertexOutput {
     var out: VertexOutput;
 
+    // Clip to the area inside the border
+    var inner = max(in.rect.zw - vec2(in.border.y + in.border.w, in.border.x + in.border.z), vec2(0.0));
+    var offset = vec2(in.border.w - in.border.y, in.border.x - in.border.z) * 0.5;
+    var widest = max(max(in.border.x, in.border.y), max(in.border.z, in.border.w));
+
     // Calculate vertex position
     var position = vertex_position(in.index);
-    out.size = in.size * 0.5;
-    out.round = in.round;
-    out.shadow = in.shadow;
+    out.size = inner * 0.5;
+    out.round = max(in.round - widest, vec4(0.0));
+    out.corner = in.shape.x;
 
     // Scale and rotate the position
-    var scale = in.size * position;
+    var scale = inner * position;
     out.pixel_size = scale;
+    var shifted = scale + offset;
     var cos_angle = cos(in.rotation);
     var sin_angle = sin(in.rotation);
     var rotated_position = vec2(
-        scale.x * cos_angle - scale.y * sin_angle,
-        scale.x * sin_angle + scale.y * cos_angle
+        shifted.x * cos_angle - shifted.y * sin_angle,
+        shifted.x * sin_angle + shifted.y * cos_angle
     );
     
     // Translate to the new position
-    var pixel_position = in.position + rotated_position;
+    var pixel_position = in.rect.xy + rotated_position;
     
     // Convert to screen space
     var screen_space = pixel_position / screen_size * 2.0 - 1.0;
@@ -63,16 +68,262 @@ fn vs_main(in: VertexInput) -> VertexOutput {
 
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0)vec4<f32> {
-    var pos_abs = abs(in.pixel_size);
-    if pos_abs.x > (in.size.x - in.round) && pos_abs.y > (in.size.y - in.round) {
-        let the_d = distance(pos_abs, in.size - in.round);
-        if the_d > in.round {
-            discard;
-        }
+    if rounded_box(in.pixel_size, in.size, in.round, in.corner) > 0.0 {
+        discard;
     }
     return vec4(0.0);
 }
 
+override BORDER: u32;
+override BORDER_GRADIENT: u32;
+override OUTLINE: u32;
+
+struct BorderInput {
+    @builtin(vertex_index) index: u32,
+    // center, size
+    @location(0) rect: vec4<f32>,
+    // dash, gap, outline width, outline offset
+    @location(1) params: vec4<f32>,
+    @location(2) rotation: f32,
+    @location(4) flags: u32,
+    // corner exponent, shadow, alpha, shadow_alpha
+    @location(5) shape: vec4<f32>,
+    // top, right, bottom, left
+    @location(6) border: vec4<f32>,
+    // border color, outline color, image uv min, image uv max
+    @location(7) packed: vec4<u32>,
+    // top left, top right, bottom right, bottom left
+    @location(12) round: vec4<f32>,
+    // linear, radial, conic, border
+    @location(13) ramps: vec4<u32>,
+    @location(15) grad_p1p2: vec4<f32>,
+}
+
+struct BorderOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) pixel_size: vec2<f32>,
+    @location(1) pixel_pos: vec2<f32>,
+    @location(2) @interpolate(flat) size: vec2<f32>,
+    @location(3) @interpolate(flat) round: vec4<f32>,
+    @location(4) @interpolate(flat) border: vec4<f32>,
+    @location(5) @interpolate(flat) params: vec4<f32>,
+    @location(6) @interpolate(flat) packed: vec4<u32>,
+    @location(7) @interpolate(flat) grad_p1p2: vec4<f32>,
+    @location(8) @interpolate(flat) flags: u32,
+    @location(9) @interpolate(flat) corner: f32,
+    @location(10) @interpolate(flat) ramp: u32,
+}
+
+@vertex
+fn vs_border(in: BorderInput) -> BorderOutput {
+    var out: BorderOutput;
+
+    // The outline is drawn outside of the element
+    var grow = max(in.params.z + in.params.w, 0.0);
+    var size = in.rect.zw + grow * 2.0;
+
+    var position = vertex_position(in.index);
+    out.size = in.rect.zw * 0.5;
+    out.round = in.round;
+    out.corner = in.shape.x;
+    out.border = in.border;
+    out.params = in.params;
+    out.packed = in.packed;
+    out.grad_p1p2 = in.grad_p1p2;
+    out.ramp = in.ramps.w;
+    out.flags = in.flags;
+
+    // Scale and rotate the position
+    var scale = size * position;
+    out.pixel_size = scale;
+    var cos_angle = cos(in.rotation);
+    var sin_angle = sin(in.rotation);
+    var rotated_position = vec2(
+        scale.x * cos_angle - scale.y * sin_angle,
+        scale.x * sin_angle + scale.y * cos_angle
+    );
+
+    // Translate to the new position
+    var pixel_position = in.rect.xy + rotated_position;
+    out.pixel_pos = pixel_position;
+
+    // Convert to screen space
+    var screen_space = pixel_position / screen_size * 2.0 - 1.0;
+    var invert_y = vec2(screen_space.x, -screen_space.y);
+
+    out.position = vec4<f32>(invert_y, 0.0, 1.0);
+
+    return out;
+}
+
+@fragment
+fn fs_border(in: BorderOutput) -> @location(0)vec4<f32> {
+    var outer = rounded_box(in.pixel_size, in.size, in.round, in.corner);
+    var color = vec4(0.0);
+    if bool(in.flags & BORDER) {
+        var b = in.border;
+        var inner_size = max(in.size - vec2(b.y + b.w, b.x + b.z) * 0.5, vec2(0.0));
+        var inner_center = vec2(b.w - b.y, b.x - b.z) * 0.5;
+        var inner_round = max(in.round - max(max(b.x, b.y), max(b.z, b.w)), vec4(0.0));
+        var inner = rounded_box(in.pixel_size - inner_center, inner_size, inner_round, in.corner);
+        var coverage = clamp(0.5 - outer, 0.0, 1.0) * clamp(0.5 + inner, 0.0, 1.0)
+            * dash(in.pixel_size, in.size, in.params.xy);
+        var c = unpack4x8unorm(in.packed.x);
+        if bool(in.flags & BORDER_GRADIENT) {
+            var p1 = in.grad_p1p2.xy;
+            var p2 = in.grad_p1p2.zw;
+            var gradient_factor = dot(in.pixel_pos - p1, p2 - p1) / dot(p2 - p1, p2 - p1);
+            c = ramp(in.ramp, gradient_factor);
+        }
+        color = vec4(c.rgb, c.a * coverage);
+    }
+    if bool(in.flags & OUTLINE) {
+        var d = outer - in.params.w;
+        var coverage = clamp(0.5 + d, 0.0, 1.0) * clamp(0.5 - d + in.params.z, 0.0, 1.0);
+        var c = unpack4x8unorm(in.packed.y);
+        var alpha = c.a * coverage;
+        color = vec4(mix(color.rgb, c.rgb, alpha), alpha + color.a * (1.0 - alpha));
+    }
+    if color.a <= 0.0 {
+        discard;
+    }
+    return color;
+}
+
+struct ShadowInput {
+    @builtin(vertex_index) index: u32,
+    // center, size
+    @location(0) rect: vec4<f32>,
+    // top left, top right, bottom right, bottom left
+    @location(1) round: vec4<f32>,
+    // x, y, blur, spread
+    @location(2) params: vec4<f32>,
+    @location(3) rotation: f32,
+    @location(4) corner: f32,
+    @location(5) color: u32,
+    @location(6) inset: u32,
+}
+
+struct ShadowOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) pixel_size: vec2<f32>,
+    @location(1) @interpolate(flat) size: vec2<f32>,
+    @location(2) @interpolate(flat) round: vec4<f32>,
+    @location(3) @interpolate(flat) params: vec4<f32>,
+    @location(4) @interpolate(flat) corner: f32,
+    @location(5) @interpolate(flat) color: vec4<f32>,
+    @location(6) @interpolate(flat) inset: u32,
+}
+
+@vertex
+fn vs_shadow(in: ShadowInput) -> ShadowOutput {
+    var out: ShadowOutput;
+
+    // Inset shadows stay inside of the element, outer ones reach past the offset and the blur
+    var grow = abs(in.params.xy) + max(in.params.w, 0.0) + in.params.z + 1.0;
+    var size = in.rect.zw + select(grow * 2.0, vec2(0.0), bool(in.inset));
+
+    var position = vertex_position(in.index);
+    out.size = in.rect.zw * 0.5;
+    out.round = in.round;
+    out.params = in.params;
+    out.corner = in.corner;
+    out.color = unpack4x8unorm(in.color);
+    out.inset = in.inset;
+
+    // Scale and rotate the position
+    var scale = size * position;
+    out.pixel_size = scale;
+    var cos_angle = cos(in.rotation);
+    var sin_angle = sin(in.rotation);
+    var rotated_position = vec2(
+        scale.x * cos_angle - scale.y * sin_angle,
+        scale.x * sin_angle + scale.y * cos_angle
+    );
+
+    // Translate to the new position
+    var pixel_position = in.rect.xy + rotated_position;
+
+    // Convert to screen space
+    var screen_space = pixel_position / screen_size * 2.0 - 1.0;
+    var invert_y = vec2(screen_space.x, -screen_space.y);
+
+    out.position = vec4<f32>(invert_y, 0.0, 1.0);
+
+    return out;
+}
+
+@fragment
+fn fs_shadow(in: ShadowOutput) -> @location(0)vec4<f32> {
+    var blur = in.params.z;
+    var spread = in.params.w;
+    var element = rounded_box(in.pixel_size, in.size, in.round, in.corner);
+    var p = in.pixel_size - in.params.xy;
+    var coverage = 0.0;
+    if bool(in.inset) {
+        // Cast by the edges of a hole in the element, shrunk by the spread
+        var hole_size = max(in.size - spread, vec2(0.0));
+        var hole = rounded_box(p, hole_size, max(in.round - spread, vec4(0.0)), in.corner);
+        coverage = falloff(-hole, blur) * clamp(0.5 - element, 0.0, 1.0);
+    } else {
+        // Hidden under the element
+        var shadow_size = max(in.size + spread, vec2(0.0));
+        var shadow = rounded_box(p, shadow_size, max(in.round + spread, vec4(0.0)), in.corner);
+        coverage = falloff(shadow, blur) * clamp(0.5 + element, 0.0, 1.0);
+    }
+    if coverage <= 0.0 {
+        discard;
+    }
+    return vec4(in.color.rgb, in.color.a * coverage);
+}
+
+// Coverage of a shape blurred over `blur` pixels, `d` is the distance from its edge
+fn falloff(d: f32, blur: f32) -> f32 {
+    var t = clamp(0.5 - d / max(blur, 1.0), 0.0, 1.0);
+    return t * t * (3.0 - 2.0 * t);
+}
+
+// Signed distance from the edge of a box with rounded corners
+//
+// Corners are superellipses with the exponent `corner`, 2.0 gives circular corners.
+fn rounded_box(p: vec2<f32>, size: vec2<f32>, round: vec4<f32>, corner: f32) -> f32 {
+    var r = select(select(round.x, round.y, p.x > 0.0), select(round.w, round.z, p.x > 0.0), p.y > 0.0);
+    r = min(r, min(size.x, size.y));
+    var q = abs(p) - size + r;
+    var m = max(q, vec2(0.0));
+    var outside = pow(pow(m.x, corner) + pow(m.y, corner), 1.0 / corner);
+    return outside + min(max(q.x, q.y), 0.0) - r;
+}
+
+// 1.0 on dashes, 0.0 in gaps, measured from the top or left end of the nearest side
+fn dash(p: vec2<f32>, size: vec2<f32>, pattern: vec2<f32>) -> f32 {
+    var period = pattern.x + pattern.y;
+    if pattern.x <= 0.0 || pattern.y <= 0.0 {
+        return 1.0;
+    }
+    var edge = size - abs(p);
+    var along = p.y + size.y;
+    if edge.y < edge.x {
+        along = p.x + size.x;
+    }
+    return f32(along - floor(along / period) * period < pattern.x);
+}
+
+// Color of a gradient at `t`, see `ramp` in base.wgsl
+fn ramp(packed: u32, t: f32) -> vec4<f32> {
+    var spread = packed >> 16u;
+    var x = clamp(t, 0.0, 1.0);
+    if spread == 1u {
+        x = fract(t);
+    } else if spread == 2u {
+        x = 1.0 - abs(fract(t * 0.5) * 2.0 - 1.0);
+    }
+    var dims = vec2<f32>(textureDimensions(t_ramp));
+    var row = f32(packed & 0xffffu);
+    var uv = vec2((x * (dims.x - 1.0) + 0.5) / dims.x, (row + 0.5) / dims.y);
+    return textureSampleLevel(t_ramp, s_ramp, uv, 0.0);
+}
+
 fn vertex_position(vertex_index: u32) -> vec2<f32> {
     // i: 0 1 2 3 4 5
     // x: + + - - - +
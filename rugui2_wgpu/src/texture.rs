@@ -5,7 +5,7 @@ use std::sync::Arc;
 use image::{DynamicImage, GenericImageView};
 use rugui2::styles::ImageData;
 
-use crate::{GLYPH_ATLAS_DEPTH, GLYPH_ATLAS_SIDE};
+use crate::{GLYPH_ATLAS_DEPTH, GLYPH_ATLAS_SIDE, GRADIENT_RAMP_WIDTH, IMAGE_ATLAS_SIDE};
 
 #[derive(Debug, Clone)]
 pub struct Texture {
@@ -127,7 +127,9 @@ impl Texture {
                 sample_count: 1,
                 dimension: wgpu::TextureDimension::D2,
                 format: wgpu::TextureFormat::Rgba8UnormSrgb,
-                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING
+                    | wgpu::TextureUsages::COPY_DST
+                    | wgpu::TextureUsages::COPY_SRC,
                 view_formats: &[],
             }));
     
@@ -286,6 +288,110 @@ impl Texture {
                 bind_group,
             }
         }
+
+        /// Element images that are small enough to share one binding, see `Rugui2WGPU::atlas_image`
+        pub(crate) fn image_atlas(device: &wgpu::Device) -> Self {
+            let size = wgpu::Extent3d {
+                width: IMAGE_ATLAS_SIDE as u32,
+                height: IMAGE_ATLAS_SIDE as u32,
+                depth_or_array_layers: 1,
+            };
+            let texture = Arc::new(device.create_texture(&wgpu::TextureDescriptor {
+                label: Some("Rugui2 image atlas"),
+                size,
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::Rgba8UnormSrgb,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                view_formats: &[],
+            }));
+
+            let view = Arc::new(texture.create_view(&wgpu::TextureViewDescriptor::default()));
+            let sampler = Arc::new(device.create_sampler(&wgpu::SamplerDescriptor {
+                address_mode_u: wgpu::AddressMode::ClampToEdge,
+                address_mode_v: wgpu::AddressMode::ClampToEdge,
+                address_mode_w: wgpu::AddressMode::ClampToEdge,
+                mag_filter: wgpu::FilterMode::Linear,
+                min_filter: wgpu::FilterMode::Nearest,
+                mipmap_filter: wgpu::FilterMode::Nearest,
+                ..Default::default()
+            }));
+
+            let bind_group = Arc::new(device.create_bind_group(&wgpu::BindGroupDescriptor {
+                layout: &device.create_bind_group_layout(&Self::BIND_GROUP_LAYOUT),
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(&view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(&sampler),
+                    },
+                ],
+                label: None,
+            }));
+
+            Self {
+                texture,
+                view,
+                sampler,
+                bind_group,
+            }
+        }
+
+        /// Gradient color ramps, one row of `GRADIENT_RAMP_WIDTH` texels per gradient
+        pub(crate) fn ramp(device: &wgpu::Device, rows: u32) -> Self {
+            let size = wgpu::Extent3d {
+                width: GRADIENT_RAMP_WIDTH as u32,
+                height: rows,
+                depth_or_array_layers: 1,
+            };
+            let texture = Arc::new(device.create_texture(&wgpu::TextureDescriptor {
+                label: Some("Rugui2 gradient ramps"),
+                size,
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::Rgba8Unorm,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                view_formats: &[],
+            }));
+
+            let view = Arc::new(texture.create_view(&wgpu::TextureViewDescriptor::default()));
+            let sampler = Arc::new(device.create_sampler(&wgpu::SamplerDescriptor {
+                address_mode_u: wgpu::AddressMode::ClampToEdge,
+                address_mode_v: wgpu::AddressMode::ClampToEdge,
+                address_mode_w: wgpu::AddressMode::ClampToEdge,
+                mag_filter: wgpu::FilterMode::Linear,
+                min_filter: wgpu::FilterMode::Linear,
+                mipmap_filter: wgpu::FilterMode::Nearest,
+                ..Default::default()
+            }));
+
+            let bind_group = Arc::new(device.create_bind_group(&wgpu::BindGroupDescriptor {
+                layout: &device.create_bind_group_layout(&Self::BIND_GROUP_LAYOUT),
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(&view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(&sampler),
+                    },
+                ],
+                label: None,
+            }));
+
+            Self {
+                texture,
+                view,
+                sampler,
+                bind_group,
+            }
+        }
 }
 
 #[cfg(feature = "image")]
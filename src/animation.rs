@@ -0,0 +1,387 @@
+//! Animated styles
+//!
+//! A `Transition` set on a `StyleComponent` makes the element move from the
+//! value it displays to the new one whenever the style is `set` to a different
+//! value, instead of jumping to it. Transitions are driven by the `time` passed to `Gui::update`
+//! and end with an `ElemEvents::TransitionEnd` event.
+
+use crate::{
+    colors::{ColorSpace, Colors},
+    styles::{Corners, Sides, Style},
+    Vector,
+};
+
+/// Interpolation of a style between its old and new value
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Transition {
+    /// Length of the transition in the units of `Gui::update` time
+    pub duration: f32,
+    /// Time between the change of the style and the start of the transition
+    pub delay: f32,
+    pub easing: Easing,
+}
+
+/// Shape of the progress of an animation
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Easing {
+    #[default]
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+    /// Control points of a CSS-like cubic bezier, the curve starts at (0, 0) and ends at (1, 1)
+    CubicBezier(f32, f32, f32, f32),
+}
+
+impl Transition {
+    pub fn new(duration: f32) -> Self {
+        Self {
+            duration,
+            delay: 0.0,
+            easing: Easing::Linear,
+        }
+    }
+
+    pub fn with_delay(mut self, delay: f32) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    pub fn with_easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    /// Eased progress at `elapsed` time since the change, `None` once finished
+    pub fn progress(&self, elapsed: f32) -> Option<f32> {
+        let t = (elapsed - self.delay) / self.duration;
+        if t >= 1.0 || !t.is_finite() {
+            return None;
+        }
+        Some(self.easing.apply(t.max(0.0)))
+    }
+}
+
+impl Easing {
+    /// Maps linear progress in the range 0..=1 to the eased one
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match *self {
+            Easing::Linear => t,
+            Easing::EaseIn => Self::bezier(0.42, 0.0, 1.0, 1.0, t),
+            Easing::EaseOut => Self::bezier(0.0, 0.0, 0.58, 1.0, t),
+            Easing::EaseInOut => Self::bezier(0.42, 0.0, 0.58, 1.0, t),
+            Easing::CubicBezier(x1, y1, x2, y2) => Self::bezier(x1, y1, x2, y2, t),
+        }
+    }
+
+    fn bezier(x1: f32, y1: f32, x2: f32, y2: f32, x: f32) -> f32 {
+        let curve = |a: f32, b: f32, s: f32| {
+            3.0 * a * s * (1.0 - s) * (1.0 - s) + 3.0 * b * s * s * (1.0 - s) + s * s * s
+        };
+        // the curve is monotonic in x, bisect for the parameter
+        let (mut low, mut high) = (0.0, 1.0);
+        let mut s = x;
+        for _ in 0..24 {
+            let cx = curve(x1, x2, s);
+            if (cx - x).abs() < 1e-5 {
+                break;
+            }
+            if cx < x {
+                low = s;
+            } else {
+                high = s;
+            }
+            s = (low + high) * 0.5;
+        }
+        curve(y1, y2, s)
+    }
+}
+
+/// Values that can be interpolated by a transition
+pub(crate) trait Lerp: Copy {
+    fn lerp(self, to: Self, t: f32) -> Self;
+    fn wrap(self) -> Resolved;
+    fn unwrap(resolved: Resolved) -> Option<Self>;
+}
+
+/// Resolved value a transition starts from
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Resolved {
+    Scalar(f32),
+    Color(BlendColor),
+    Point(Vector),
+    Gradient(GradientInstance),
+    Sides(Sides<f32>),
+    Corners(Corners<f32>),
+}
+
+/// Resolved sRGB color and the space a transition towards it blends in
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct BlendColor(pub [f32; 4], pub ColorSpace);
+
+/// Resolved points and colors of a gradient
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct GradientInstance {
+    pub p1: Vector,
+    pub p2: Vector,
+    pub color1: [f32; 4],
+    pub color2: [f32; 4],
+    /// Space the colors are blended in
+    pub space: ColorSpace,
+}
+
+impl From<Colors> for BlendColor {
+    fn from(color: Colors) -> Self {
+        Self(color.rgba(), color.space())
+    }
+}
+
+/// Only the displayed color counts, the space just shapes the way there
+impl PartialEq for BlendColor {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl Lerp for f32 {
+    fn lerp(self, to: Self, t: f32) -> Self {
+        self + (to - self) * t
+    }
+
+    fn wrap(self) -> Resolved {
+        Resolved::Scalar(self)
+    }
+
+    fn unwrap(resolved: Resolved) -> Option<Self> {
+        match resolved {
+            Resolved::Scalar(v) => Some(v),
+            _ => None,
+        }
+    }
+}
+
+impl Lerp for BlendColor {
+    fn lerp(self, to: Self, t: f32) -> Self {
+        Self(to.1.mix(self.0, to.0, t), to.1)
+    }
+
+    fn wrap(self) -> Resolved {
+        Resolved::Color(self)
+    }
+
+    fn unwrap(resolved: Resolved) -> Option<Self> {
+        match resolved {
+            Resolved::Color(v) => Some(v),
+            _ => None,
+        }
+    }
+}
+
+impl Lerp for Vector {
+    fn lerp(self, to: Self, t: f32) -> Self {
+        Vector(self.0.lerp(to.0, t), self.1.lerp(to.1, t))
+    }
+
+    fn wrap(self) -> Resolved {
+        Resolved::Point(self)
+    }
+
+    fn unwrap(resolved: Resolved) -> Option<Self> {
+        match resolved {
+            Resolved::Point(v) => Some(v),
+            _ => None,
+        }
+    }
+}
+
+impl Lerp for GradientInstance {
+    fn lerp(self, to: Self, t: f32) -> Self {
+        Self {
+            p1: self.p1.lerp(to.p1, t),
+            p2: self.p2.lerp(to.p2, t),
+            color1: to.space.mix(self.color1, to.color1, t),
+            color2: to.space.mix(self.color2, to.color2, t),
+            space: to.space,
+        }
+    }
+
+    fn wrap(self) -> Resolved {
+        Resolved::Gradient(self)
+    }
+
+    fn unwrap(resolved: Resolved) -> Option<Self> {
+        match resolved {
+            Resolved::Gradient(v) => Some(v),
+            _ => None,
+        }
+    }
+}
+
+impl Lerp for Sides<f32> {
+    fn lerp(self, to: Self, t: f32) -> Self {
+        Sides::new(
+            self.top.lerp(to.top, t),
+            self.right.lerp(to.right, t),
+            self.bottom.lerp(to.bottom, t),
+            self.left.lerp(to.left, t),
+        )
+    }
+
+    fn wrap(self) -> Resolved {
+        Resolved::Sides(self)
+    }
+
+    fn unwrap(resolved: Resolved) -> Option<Self> {
+        match resolved {
+            Resolved::Sides(v) => Some(v),
+            _ => None,
+        }
+    }
+}
+
+impl Lerp for Corners<f32> {
+    /// The shape of the corners changes at once
+    fn lerp(self, to: Self, t: f32) -> Self {
+        Corners::new(
+            self.top_left.lerp(to.top_left, t),
+            self.top_right.lerp(to.top_right, t),
+            self.bottom_right.lerp(to.bottom_right, t),
+            self.bottom_left.lerp(to.bottom_left, t),
+        )
+        .with_shape(to.shape)
+    }
+
+    fn wrap(self) -> Resolved {
+        Resolved::Corners(self)
+    }
+
+    fn unwrap(resolved: Resolved) -> Option<Self> {
+        match resolved {
+            Resolved::Corners(v) => Some(v),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct ActiveTransition {
+    style: Style,
+    transition: Transition,
+    from: Resolved,
+    /// Value the transition heads to
+    to: Resolved,
+    start: f32,
+}
+
+/// Transitions running on an element
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Transitions {
+    active: Vec<ActiveTransition>,
+    /// Styles whose transition ended during the last layout
+    pub finished: Vec<Style>,
+    /// Element was laid out before, there is an old value to start from
+    pub ready: bool,
+}
+
+impl Transitions {
+    pub fn is_running(&self) -> bool {
+        !self.active.is_empty()
+    }
+
+    /// Value of the style to display
+    ///
+    /// `current` is the value displayed so far and `target` the resolved new value,
+    /// `started` is the transition of a style that was set since the last layout.
+    /// A transition starts only when the style was set to a value other than
+    /// the one it displays or already heads to.
+    pub fn resolve<R: Lerp + PartialEq>(
+        &mut self,
+        style: Style,
+        started: Option<Transition>,
+        current: R,
+        target: R,
+        time: f32,
+    ) -> R {
+        let idx = self.active.iter().position(|a| a.style == style);
+        if let (Some(transition), true) = (started, self.ready) {
+            let heading = match idx {
+                Some(idx) => R::unwrap(self.active[idx].to),
+                None => Some(current),
+            };
+            if heading != Some(target) {
+                self.active.retain(|a| a.style != style);
+                self.active.push(ActiveTransition {
+                    style,
+                    transition,
+                    from: current.wrap(),
+                    to: target.wrap(),
+                    start: time,
+                });
+            }
+        }
+        let Some(idx) = self.active.iter().position(|a| a.style == style) else {
+            return target;
+        };
+        let active = &mut self.active[idx];
+        active.to = target.wrap();
+        match (
+            active.transition.progress(time - active.start),
+            R::unwrap(active.from),
+        ) {
+            (Some(t), Some(from)) => from.lerp(target, t),
+            _ => {
+                self.active.swap_remove(idx);
+                self.finished.push(style);
+                target
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn easing_curves() {
+        for easing in [
+            Easing::Linear,
+            Easing::EaseIn,
+            Easing::EaseOut,
+            Easing::EaseInOut,
+        ] {
+            assert!(easing.apply(0.0).abs() < 1e-3);
+            assert!((easing.apply(1.0) - 1.0).abs() < 1e-3);
+        }
+        assert!(Easing::EaseIn.apply(0.5) < 0.5);
+        assert!(Easing::EaseOut.apply(0.5) > 0.5);
+        assert!((Easing::EaseInOut.apply(0.5) - 0.5).abs() < 1e-3);
+
+        let transition = Transition::new(2.0).with_delay(1.0);
+        assert_eq!(transition.progress(0.5), Some(0.0));
+        assert_eq!(transition.progress(2.0), Some(0.5));
+        assert_eq!(transition.progress(3.0), None);
+    }
+
+    #[test]
+    fn color_transitions_blend_in_target_space() {
+        let mut transitions = Transitions {
+            ready: true,
+            ..Default::default()
+        };
+        let red = [1.0, 0.0, 0.0, 1.0];
+        let blue = BlendColor::from(Colors::FOklch(0.45, 0.31, 264.0, 1.0));
+        let transition = Some(Transition::new(1.0));
+        let start = BlendColor(red, blue.1);
+        transitions.resolve(Style::Color, transition, start, blue, 0.0);
+        let half = transitions.resolve(Style::Color, None, start, blue, 0.5);
+        assert_eq!(half.0, ColorSpace::Oklch.mix(red, blue.0, 0.5));
+        assert_ne!(half.0, ColorSpace::Srgb.mix(red, blue.0, 0.5));
+        let end = transitions.resolve(Style::Color, None, half, blue, 1.0);
+        assert_eq!(end, blue);
+        assert_eq!(transitions.finished, [Style::Color]);
+    }
+}
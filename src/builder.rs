@@ -0,0 +1,273 @@
+use std::{collections::HashMap, ops::Index};
+
+use crate::{
+    stylesheet::{PseudoState, StylePatch},
+    Element, ElementKey, EventListener, Gui, ImageData, Styles, Value,
+};
+
+/// Fluent description of an element and its subtree
+///
+/// Nothing is added to the `Gui` until `ElementBuilder::build` is called,
+/// see also the `ui!` macro.
+pub struct ElementBuilder<Msg: Clone, Img: Clone + ImageData> {
+    element: Element<Msg, Img>,
+    name: Option<String>,
+    children: Vec<Child<Msg, Img>>,
+}
+
+enum Child<Msg: Clone, Img: Clone + ImageData> {
+    Builder(Box<ElementBuilder<Msg, Img>>),
+    Key(ElementKey),
+}
+
+/// Keys of a subtree added by `ElementBuilder::build`
+#[derive(Debug, Clone)]
+pub struct BuiltTree {
+    pub root: ElementKey,
+    /// Elements that were given a name with `ElementBuilder::name`
+    pub handles: HashMap<String, ElementKey>,
+}
+
+impl<Msg: Clone, Img: Clone + ImageData> ElementBuilder<Msg, Img> {
+    pub fn new() -> Self {
+        Self::from_element(Element::default())
+    }
+
+    pub fn from_element(element: Element<Msg, Img>) -> Self {
+        Self {
+            element,
+            name: None,
+            children: Vec::new(),
+        }
+    }
+
+    /// Sets `Element::label`
+    pub fn label(mut self, label: impl Into<String>) -> Self {
+        self.element.label = Some(label.into());
+        self
+    }
+
+    /// Registers the element in `BuiltTree::handles` under the given name
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    pub fn class(mut self, class: impl Into<String>) -> Self {
+        self.element.add_class(class);
+        self
+    }
+
+    pub fn style(mut self, f: impl FnOnce(&mut Styles<Img>)) -> Self {
+        f(self.element.styles_mut());
+        self
+    }
+
+    /// See `Element::state_style_mut`
+    pub fn state_style(mut self, state: PseudoState, styles: StylePatch<Img>) -> Self {
+        *self.element.state_style_mut(state) = styles;
+        self
+    }
+
+    pub fn disabled(mut self, disabled: bool) -> Self {
+        self.element.set_disabled(disabled);
+        self
+    }
+
+    pub fn listen(mut self, listener: EventListener<Msg>) -> Self {
+        self.element.events.add(listener);
+        self
+    }
+
+    pub fn procedure(mut self, procedure: Value) -> Self {
+        self.element.procedures.push(procedure);
+        self
+    }
+
+    pub fn child(mut self, child: ElementBuilder<Msg, Img>) -> Self {
+        self.children.push(Child::Builder(Box::new(child)));
+        self
+    }
+
+    /// Attaches an element that is already part of the `Gui`
+    ///
+    /// The element is detached from its current parent when the tree is built,
+    /// stale keys are skipped.
+    pub fn child_key(mut self, key: ElementKey) -> Self {
+        self.children.push(Child::Key(key));
+        self
+    }
+
+    pub fn children(
+        mut self,
+        children: impl IntoIterator<Item = ElementBuilder<Msg, Img>>,
+    ) -> Self {
+        self.children
+            .extend(children.into_iter().map(|c| Child::Builder(Box::new(c))));
+        self
+    }
+
+    /// Adds the whole subtree to the `Gui`, the root is not attached to any parent
+    pub fn build(self, gui: &mut Gui<Msg, Img>) -> BuiltTree {
+        let mut handles = HashMap::new();
+        let root = self.build_inner(gui, &mut handles);
+        BuiltTree { root, handles }
+    }
+
+    fn build_inner(
+        self,
+        gui: &mut Gui<Msg, Img>,
+        handles: &mut HashMap<String, ElementKey>,
+    ) -> ElementKey {
+        let Self {
+            mut element,
+            name,
+            children,
+        } = self;
+        for child in children {
+            let key = match child {
+                Child::Builder(builder) => builder.build_inner(gui, handles),
+                Child::Key(key) => {
+                    let attached = element.children.as_ref().is_some_and(|c| c.contains(&key));
+                    if !gui.contains(key) || attached {
+                        continue;
+                    }
+                    gui.detach(key);
+                    key
+                }
+            };
+            element.add_child(key);
+        }
+        let key = gui.add_element(element);
+        if let Some(name) = name {
+            handles.insert(name, key);
+        }
+        key
+    }
+}
+
+impl<Msg: Clone, Img: Clone + ImageData> Default for ElementBuilder<Msg, Img> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BuiltTree {
+    pub fn get(&self, name: &str) -> Option<ElementKey> {
+        self.handles.get(name).copied()
+    }
+}
+
+impl Index<&str> for BuiltTree {
+    type Output = ElementKey;
+
+    /// # Panic
+    ///
+    /// Panics if there is no element with the given name
+    fn index(&self, name: &str) -> &Self::Output {
+        &self.handles[name]
+    }
+}
+
+/// Builds a subtree of elements in a single expression
+///
+/// Evaluates to `BuiltTree`. Every element is written as `{ ... }`, optionally
+/// prefixed with `name @` to register it in `BuiltTree::handles`. The body
+/// contains comma separated sections:
+///
+/// - `label: expr`
+/// - `classes [class, ...]`
+/// - `styles { style: value, ... }`, each value is passed to `StyleComponent::set`
+/// - `events [listener, ...]`
+/// - `procedures [value, ...]`
+/// - `children [element, ...]`, an element may also be `key expr` to attach
+///   an existing `ElementKey`
+///
+/// ```
+/// use std::num::NonZero;
+/// use rugui2::{ui, colors::Colors, styles::Value, Gui};
+///
+/// let mut gui: Gui = Gui::new((NonZero::new(800).unwrap(), NonZero::new(600).unwrap()));
+/// let tree = ui!(&mut gui, {
+///     label: "menu",
+///     styles { color: Colors::BLACK },
+///     children [
+///         start @ {
+///             styles { width: Value::Px(200.0), height: Value::Px(50.0) },
+///         },
+///         {},
+///     ],
+/// });
+/// gui.set_entry(tree.root);
+/// assert_eq!(gui.parent_of(tree["start"]), Some(tree.root));
+/// ```
+#[macro_export]
+macro_rules! ui {
+    (@node $name:ident @ { $($body:tt)* }) => {
+        $crate::ui!(@body $crate::builder::ElementBuilder::new().name(stringify!($name)); $($body)*)
+    };
+    (@node { $($body:tt)* }) => {
+        $crate::ui!(@body $crate::builder::ElementBuilder::new(); $($body)*)
+    };
+
+    (@body $b:expr; ) => { $b };
+    (@body $b:expr; label: $label:expr $(, $($rest:tt)*)?) => {
+        $crate::ui!(@body $b.label($label); $($($rest)*)?)
+    };
+    (@body $b:expr; classes [ $($class:expr),* $(,)? ] $(, $($rest:tt)*)?) => {
+        $crate::ui!(@body $b$(.class($class))*; $($($rest)*)?)
+    };
+    (@body $b:expr; styles { $($style:ident : $value:expr),* $(,)? } $(, $($rest:tt)*)?) => {
+        $crate::ui!(@body $b.style(|styles| { $(styles.$style.set($value);)* }); $($($rest)*)?)
+    };
+    (@body $b:expr; events [ $($listener:expr),* $(,)? ] $(, $($rest:tt)*)?) => {
+        $crate::ui!(@body $b$(.listen($listener))*; $($($rest)*)?)
+    };
+    (@body $b:expr; procedures [ $($procedure:expr),* $(,)? ] $(, $($rest:tt)*)?) => {
+        $crate::ui!(@body $b$(.procedure($procedure))*; $($($rest)*)?)
+    };
+    (@body $b:expr; children [ $($children:tt)* ] $(, $($rest:tt)*)?) => {
+        $crate::ui!(@body $crate::ui!(@children $b; $($children)*); $($($rest)*)?)
+    };
+
+    (@children $b:expr; ) => { $b };
+    (@children $b:expr; $name:ident @ { $($body:tt)* } $(, $($rest:tt)*)?) => {
+        $crate::ui!(@children $b.child($crate::ui!(@node $name @ { $($body)* })); $($($rest)*)?)
+    };
+    (@children $b:expr; key $key:expr $(, $($rest:tt)*)?) => {
+        $crate::ui!(@children $b.child_key($key); $($($rest)*)?)
+    };
+    (@children $b:expr; { $($body:tt)* } $(, $($rest:tt)*)?) => {
+        $crate::ui!(@children $b.child($crate::ui!(@node { $($body)* })); $($($rest)*)?)
+    };
+
+    ($gui:expr, $($node:tt)+) => {
+        $crate::ui!(@node $($node)+).build($gui)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZero;
+
+    use super::*;
+
+    #[test]
+    fn child_key_moves_attached_elements() {
+        let mut gui: Gui = Gui::new((NonZero::new(800).unwrap(), NonZero::new(800).unwrap()));
+        let a = gui.add_element(Element::default());
+        let c = gui.add_element(Element::default());
+        let mut root = Element::default();
+        root.children = Some(vec![a, c]);
+        let root = gui.add_element(root);
+
+        // building around an attached element moves it
+        let built = ElementBuilder::new()
+            .child_key(c)
+            .child_key(c)
+            .build(&mut gui);
+        assert_eq!(gui.parent_of(c), Some(built.root));
+        assert_eq!(gui.get_element(root).unwrap().children, Some(vec![a]));
+        assert_eq!(gui.get_element(built.root).unwrap().children, Some(vec![c]));
+    }
+}
@@ -1,7 +1,23 @@
+use std::{fmt, str::FromStr};
+
+/// Color in one of several spaces
+///
+/// Hues are in degrees, every other channel goes from 0.0 to 1.0. All variants
+/// are converted to sRGB by `Colors::rgba` before they are drawn.
 #[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Colors {
     FRgba(f32, f32, f32, f32),
+    /// hue, saturation, lightness
     FHsl(f32, f32, f32),
+    /// hue, saturation, value, alpha
+    FHsva(f32, f32, f32, f32),
+    /// red, green, blue and alpha without the sRGB transfer function
+    FLinearRgba(f32, f32, f32, f32),
+    /// lightness, a, b, alpha
+    FOklab(f32, f32, f32, f32),
+    /// lightness, chroma, hue, alpha
+    FOklch(f32, f32, f32, f32),
 }
 
 impl Colors {
@@ -28,32 +44,133 @@ impl Colors {
     pub const ALPHA_HALF: Self = Self::FRgba(1.0, 1.0, 1.0, 0.5);
     pub const ALPHA_ZERO: Self = Self::FRgba(1.0, 1.0, 1.0, 0.0);
 
-    pub fn with_alpha(&self, alpha: f32) -> Self {
-        match self {
-            Colors::FHsl(_, _, _) => *self,
-            Colors::FRgba(r, g, b, _) => Self::FRgba(*r, *g, *b, alpha),
+    /// Red, green, blue and alpha in sRGB
+    pub fn rgba(&self) -> [f32; 4] {
+        match *self {
+            Colors::FRgba(r, g, b, a) => [r, g, b, a],
+            Colors::FHsl(h, s, l) => {
+                let (r, g, b) = Colors::hsl_to_rgb(h, s, l);
+                [r, g, b, 1.0]
+            }
+            Colors::FHsva(h, s, v, a) => {
+                let (r, g, b) = Colors::hsv_to_rgb(h, s, v);
+                [r, g, b, a]
+            }
+            Colors::FLinearRgba(r, g, b, a) => ColorSpace::LinearSrgb.to_srgb([r, g, b, a]),
+            Colors::FOklab(l, a, b, alpha) => ColorSpace::Oklab.to_srgb([l, a, b, alpha]),
+            Colors::FOklch(l, c, h, alpha) => ColorSpace::Oklch.to_srgb([l, c, h, alpha]),
         }
     }
 
-    pub fn with_red(&self, red: f32) -> Self {
+    pub fn alpha(&self) -> f32 {
+        self.rgba()[3]
+    }
+
+    /// Space the variant is given in, transitions towards this color blend in it
+    pub fn space(&self) -> ColorSpace {
         match self {
-            Colors::FHsl(_, _, _) => *self,
-            Colors::FRgba(_, g, b, a) => Self::FRgba(red, *g, *b, *a),
+            Colors::FLinearRgba(..) => ColorSpace::LinearSrgb,
+            Colors::FOklab(..) => ColorSpace::Oklab,
+            Colors::FOklch(..) => ColorSpace::Oklch,
+            _ => ColorSpace::Srgb,
         }
     }
 
-    pub fn with_green(&self, green: f32) -> Self {
-        match self {
-            Colors::FHsl(_, _, _) => *self,
-            Colors::FRgba(r, _, b, a) => Self::FRgba(*r, green, *b, *a),
+    /// `FHsl` has no alpha channel and becomes `FRgba`
+    pub fn with_alpha(&self, alpha: f32) -> Self {
+        match *self {
+            Colors::FHsva(h, s, v, _) => Self::FHsva(h, s, v, alpha),
+            Colors::FLinearRgba(r, g, b, _) => Self::FLinearRgba(r, g, b, alpha),
+            Colors::FOklab(l, a, b, _) => Self::FOklab(l, a, b, alpha),
+            Colors::FOklch(l, c, h, _) => Self::FOklch(l, c, h, alpha),
+            Colors::FRgba(..) | Colors::FHsl(..) => {
+                let [r, g, b, _] = self.rgba();
+                Self::FRgba(r, g, b, alpha)
+            }
         }
     }
 
+    /// Sets the sRGB red channel, the result is `FRgba`
+    pub fn with_red(&self, red: f32) -> Self {
+        let [_, g, b, a] = self.rgba();
+        Self::FRgba(red, g, b, a)
+    }
+
+    /// Sets the sRGB green channel, the result is `FRgba`
+    pub fn with_green(&self, green: f32) -> Self {
+        let [r, _, b, a] = self.rgba();
+        Self::FRgba(r, green, b, a)
+    }
+
+    /// Sets the sRGB blue channel, the result is `FRgba`
     pub fn with_blue(&self, blue: f32) -> Self {
-        match self {
-            Colors::FHsl(_, _, _) => *self,
-            Colors::FRgba(r, g, _, a) => Self::FRgba(*r, *g, blue, *a),
-        }
+        let [r, g, _, a] = self.rgba();
+        Self::FRgba(r, g, blue, a)
+    }
+
+    /// Blends towards `to` in `space`, `t` goes from 0.0 at `self` to 1.0 at `to`
+    pub fn mix(&self, to: Colors, t: f32, space: ColorSpace) -> Self {
+        space.mix(self.rgba(), to.rgba(), t).into()
+    }
+
+    /// Raises the OKLab lightness by `amount`, chroma and hue stay the same
+    pub fn lighten(&self, amount: f32) -> Self {
+        let [l, a, b, alpha] = ColorSpace::Oklab.from_srgb(self.rgba());
+        ColorSpace::Oklab
+            .to_srgb([(l + amount).clamp(0.0, 1.0), a, b, alpha])
+            .into()
+    }
+
+    /// Lowers the OKLab lightness by `amount`, chroma and hue stay the same
+    pub fn darken(&self, amount: f32) -> Self {
+        self.lighten(-amount)
+    }
+
+    /// Relative luminance as defined by WCAG, alpha is ignored
+    pub fn luminance(&self) -> f32 {
+        let [r, g, b, _] = ColorSpace::LinearSrgb.from_srgb(self.rgba());
+        0.2126 * r + 0.7152 * g + 0.0722 * b
+    }
+
+    /// WCAG contrast ratio from 1.0 to 21.0, normal text needs at least 4.5
+    pub fn contrast_ratio(&self, other: Colors) -> f32 {
+        let (a, b) = (self.luminance(), other.luminance());
+        (a.max(b) + 0.05) / (a.min(b) + 0.05)
+    }
+
+    /// `#rrggbbaa` in sRGB
+    pub fn to_hex(&self) -> String {
+        let [r, g, b, a] = self
+            .rgba()
+            .map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8);
+        format!("#{r:02x}{g:02x}{b:02x}{a:02x}")
+    }
+
+    /// Color named after one of the constants, case insensitive
+    pub fn named(name: &str) -> Option<Self> {
+        let color = match name.to_ascii_lowercase().as_str() {
+            "black" => Self::BLACK,
+            "white" => Self::WHITE,
+            "red" => Self::RED,
+            "green" => Self::GREEN,
+            "blue" => Self::BLUE,
+            "transparent" => Self::TRANSPARENT,
+            "yellow" => Self::YELLOW,
+            "cyan" => Self::CYAN,
+            "magenta" => Self::MAGENTA,
+            "orange" => Self::ORANGE,
+            "purple" => Self::PURPLE,
+            "pink" => Self::PINK,
+            "lime" => Self::LIME,
+            "brown" => Self::BROWN,
+            "skyblue" => Self::SKYBLUE,
+            "gray" | "grey" => Self::GRAY,
+            "silver" => Self::SILVER,
+            "gold" => Self::GOLD,
+            "bronze" => Self::BRONZE,
+            _ => return None,
+        };
+        Some(color)
     }
 }
 
@@ -77,41 +194,381 @@ impl From<(f32, f32, f32, f32)> for Colors {
 
 impl From<Colors> for [f32; 4] {
     fn from(color: Colors) -> Self {
-        match color {
-            Colors::FRgba(r, g, b, a) => [r, g, b, a],
-            Colors::FHsl(h, s, l) => {
-                let rgb = Colors::hsl_to_rgb(h, s, l);
-                [rgb.0, rgb.1, rgb.2, 1.0]
-            }
-        }
+        color.rgba()
     }
 }
 
 impl From<Colors> for (f32, f32, f32, f32) {
     fn from(color: Colors) -> Self {
-        match color {
-            Colors::FRgba(r, g, b, a) => (r, g, b, a),
-            Colors::FHsl(h, s, l) => {
-                let rgb = Colors::hsl_to_rgb(h, s, l);
-                (rgb.0, rgb.1, rgb.2, 1.0)
-            }
-        }
+        let [r, g, b, a] = color.rgba();
+        (r, g, b, a)
     }
 }
 
 impl Colors {
     fn hsl_to_rgb(hue: f32, saturation: f32, lightness: f32) -> (f32, f32, f32) {
-        let c = lightness * saturation / 100.0;
-        let x = c * (1.0 - (hue % 60.0).abs() / 60.0);
-        let m = lightness - c;
-
-        match hue {
-            _ if (hue >= 0.0 && hue < 60.0) => (c, x, 0.0),
-            _ if (hue >= 60.0 && hue < 120.0) => (x, c, 0.0),
-            _ if (hue >= 120.0 && hue < 180.0) => (0.0, c, x),
-            _ if (hue >= 180.0 && hue < 240.0) => (0.0, x, c),
-            _ if (hue >= 240.0 && hue <= 300.0) => (x, 0.0, c),
-            _ => ((c + m), (m - x), (m - x)),
+        let c = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+        Colors::hue_to_rgb(hue, c, lightness - c / 2.0)
+    }
+
+    fn hsv_to_rgb(hue: f32, saturation: f32, value: f32) -> (f32, f32, f32) {
+        let c = value * saturation;
+        Colors::hue_to_rgb(hue, c, value - c)
+    }
+
+    /// Shared by HSL and HSV, which only differ in how the chroma `c` and the
+    /// lowest channel `m` are found
+    fn hue_to_rgb(hue: f32, c: f32, m: f32) -> (f32, f32, f32) {
+        let h = hue.rem_euclid(360.0) / 60.0;
+        let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+        let (r, g, b) = match h as u32 {
+            0 => (c, x, 0.0),
+            1 => (x, c, 0.0),
+            2 => (0.0, c, x),
+            3 => (0.0, x, c),
+            4 => (x, 0.0, c),
+            _ => (c, 0.0, x),
+        };
+        (r + m, g + m, b + m)
+    }
+}
+
+/// Error of parsing `Colors` from a string
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColorParseError {
+    /// Hex colors have 3, 4, 6 or 8 hexadecimal digits
+    InvalidHex,
+    InvalidNumber(String),
+    UnknownName(String),
+    UnknownFunction(String),
+    /// Functions take 3 channels and an optional alpha
+    Arguments {
+        function: String,
+        found: usize,
+    },
+}
+
+impl fmt::Display for ColorParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ColorParseError::InvalidHex => write!(f, "invalid hex color"),
+            ColorParseError::InvalidNumber(n) => write!(f, "invalid number '{n}'"),
+            ColorParseError::UnknownName(name) => write!(f, "unknown color '{name}'"),
+            ColorParseError::UnknownFunction(name) => write!(f, "unknown function '{name}'"),
+            ColorParseError::Arguments { function, found } => {
+                write!(f, "'{function}' takes 3 or 4 arguments, found {found}")
+            }
         }
     }
 }
+
+impl std::error::Error for ColorParseError {}
+
+impl FromStr for Colors {
+    type Err = ColorParseError;
+
+    /// Reads `#rgb`, `#rgba`, `#rrggbb`, `#rrggbbaa`, names of the color constants
+    /// and the CSS functions `rgb`, `rgba`, `hsl`, `hsla`, `oklab` and `oklch`
+    /// as well as `hsv` and `hsva`
+    ///
+    /// Arguments are separated by commas or spaces and alpha may follow a `/`.
+    /// Percentages are accepted everywhere except hues, which can end with `deg`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Some(hex) = s.strip_prefix('#') {
+            return parse_hex(hex);
+        }
+        let Some((function, args)) = s.strip_suffix(')').and_then(|s| s.split_once('(')) else {
+            return Colors::named(s).ok_or_else(|| ColorParseError::UnknownName(s.to_string()));
+        };
+        let function = function.trim().to_ascii_lowercase();
+        let args: Vec<&str> = args
+            .split(|c: char| c == ',' || c == '/' || c.is_whitespace())
+            .filter(|arg| !arg.is_empty())
+            .collect();
+        let alpha = match args.len() {
+            3 => 1.0,
+            4 => channel(args[3], 1.0)?,
+            found => return Err(ColorParseError::Arguments { function, found }),
+        };
+        let color = match function.as_str() {
+            "rgb" | "rgba" => Colors::FRgba(
+                channel(args[0], 255.0)?,
+                channel(args[1], 255.0)?,
+                channel(args[2], 255.0)?,
+                alpha,
+            ),
+            "hsl" | "hsla" => Colors::FHsl(
+                hue(args[0])?,
+                channel(args[1], 100.0)?,
+                channel(args[2], 100.0)?,
+            )
+            .with_alpha(alpha),
+            "hsv" | "hsva" => Colors::FHsva(
+                hue(args[0])?,
+                channel(args[1], 100.0)?,
+                channel(args[2], 100.0)?,
+                alpha,
+            ),
+            "oklab" => Colors::FOklab(
+                channel(args[0], 1.0)?,
+                channel(args[1], 1.0)?,
+                channel(args[2], 1.0)?,
+                alpha,
+            ),
+            "oklch" => Colors::FOklch(
+                channel(args[0], 1.0)?,
+                channel(args[1], 1.0)?,
+                hue(args[2])?,
+                alpha,
+            ),
+            _ => return Err(ColorParseError::UnknownFunction(function)),
+        };
+        Ok(color)
+    }
+}
+
+fn parse_hex(hex: &str) -> Result<Colors, ColorParseError> {
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(ColorParseError::InvalidHex);
+    }
+    let digit = |i: usize| u8::from_str_radix(&hex[i..i + 1], 16).unwrap() as f32;
+    let mut channels = [1.0; 4];
+    match hex.len() {
+        3 | 4 => {
+            for (i, c) in channels.iter_mut().enumerate().take(hex.len()) {
+                *c = digit(i) / 15.0;
+            }
+        }
+        6 | 8 => {
+            for (i, c) in channels.iter_mut().enumerate().take(hex.len() / 2) {
+                *c = (digit(i * 2) * 16.0 + digit(i * 2 + 1)) / 255.0;
+            }
+        }
+        _ => return Err(ColorParseError::InvalidHex),
+    }
+    Ok(channels.into())
+}
+
+/// Number divided by `max`, or a percentage of 1.0
+fn channel(arg: &str, max: f32) -> Result<f32, ColorParseError> {
+    let invalid = || ColorParseError::InvalidNumber(arg.to_string());
+    match arg.strip_suffix('%') {
+        Some(percent) => Ok(percent.parse::<f32>().map_err(|_| invalid())? / 100.0),
+        None => Ok(arg.parse::<f32>().map_err(|_| invalid())? / max),
+    }
+}
+
+/// Degrees, the unit is optional
+fn hue(arg: &str) -> Result<f32, ColorParseError> {
+    arg.trim_end_matches("deg")
+        .parse()
+        .map_err(|_| ColorParseError::InvalidNumber(arg.to_string()))
+}
+
+/// Space in which colors are interpolated
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ColorSpace {
+    /// Channels as they are stored
+    #[default]
+    Srgb,
+    /// Channels with the sRGB transfer function removed
+    LinearSrgb,
+    /// Perceptually uniform space, keeps the lightness and hue of blends steady
+    Oklab,
+    /// Polar form of `Oklab`, blends go around the hue circle the shorter way
+    Oklch,
+}
+
+impl ColorSpace {
+    /// Converts an sRGB color into this space, alpha is kept
+    pub fn from_srgb(&self, color: [f32; 4]) -> [f32; 4] {
+        let [r, g, b, a] = color;
+        match self {
+            Self::Srgb => color,
+            Self::LinearSrgb => [to_linear(r), to_linear(g), to_linear(b), a],
+            Self::Oklab => {
+                let [l, a_, b_] = linear_to_oklab([to_linear(r), to_linear(g), to_linear(b)]);
+                [l, a_, b_, a]
+            }
+            Self::Oklch => {
+                let [l, a_, b_, a] = Self::Oklab.from_srgb(color);
+                let h = b_.atan2(a_).to_degrees().rem_euclid(360.0);
+                [l, a_.hypot(b_), h, a]
+            }
+        }
+    }
+
+    /// Converts a color of this space back into sRGB, alpha is kept
+    ///
+    /// Colors outside of the sRGB gamut are clamped.
+    pub fn to_srgb(&self, color: [f32; 4]) -> [f32; 4] {
+        let [x, y, z, a] = color;
+        let [r, g, b] = match self {
+            Self::Srgb => return color,
+            Self::LinearSrgb => [x, y, z],
+            Self::Oklab => oklab_to_linear([x, y, z]),
+            Self::Oklch => {
+                let (sin, cos) = z.to_radians().sin_cos();
+                oklab_to_linear([x, y * cos, y * sin])
+            }
+        }
+        .map(|c| from_linear(c.clamp(0.0, 1.0)));
+        [r, g, b, a]
+    }
+
+    /// Blends two sRGB colors in this space, `t` goes from 0.0 at `from` to 1.0 at `to`
+    pub fn mix(&self, from: [f32; 4], to: [f32; 4], t: f32) -> [f32; 4] {
+        let (mut from, mut to) = (self.from_srgb(from), self.from_srgb(to));
+        if *self == Self::Oklch {
+            // grays have no hue, they take the hue of the other color
+            if from[1] < 1e-4 {
+                from[2] = to[2];
+            } else if to[1] < 1e-4 {
+                to[2] = from[2];
+            }
+            to[2] = from[2] + (to[2] - from[2] + 180.0).rem_euclid(360.0) - 180.0;
+        }
+        let mixed = std::array::from_fn(|i| from[i] + (to[i] - from[i]) * t);
+        self.to_srgb(mixed)
+    }
+}
+
+fn to_linear(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn from_linear(c: f32) -> f32 {
+    if c <= 0.0031308 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+fn linear_to_oklab([r, g, b]: [f32; 3]) -> [f32; 3] {
+    let l = (0.41222146 * r + 0.53633255 * g + 0.051445995 * b).cbrt();
+    let m = (0.2119035 * r + 0.6806995 * g + 0.10739696 * b).cbrt();
+    let s = (0.08830246 * r + 0.28171885 * g + 0.6299787 * b).cbrt();
+    [
+        0.21045426 * l + 0.7936178 * m - 0.004072047 * s,
+        1.9779985 * l - 2.4285922 * m + 0.4505937 * s,
+        0.025904037 * l + 0.78277177 * m - 0.80867577 * s,
+    ]
+}
+
+fn oklab_to_linear([l, a, b]: [f32; 3]) -> [f32; 3] {
+    let l_ = (l + 0.39633778 * a + 0.21580376 * b).powi(3);
+    let m_ = (l - 0.105561346 * a - 0.06385417 * b).powi(3);
+    let s_ = (l - 0.08948418 * a - 1.2914855 * b).powi(3);
+    [
+        4.0767417 * l_ - 3.3077116 * m_ + 0.23096994 * s_,
+        -1.268438 * l_ + 2.6097574 * m_ - 0.34131938 * s_,
+        -0.0041960864 * l_ - 0.7034186 * m_ + 1.7076147 * s_,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: [f32; 4], b: [f32; 4]) -> bool {
+        a.iter().zip(b).all(|(a, b)| (a - b).abs() < 1e-3)
+    }
+
+    #[test]
+    fn parse() {
+        let orange: Colors = "#ff8800cc".parse().unwrap();
+        assert!(close(orange.rgba(), [1.0, 0.533, 0.0, 0.8]));
+        assert_eq!(
+            "#f80".parse(),
+            Ok(Colors::FRgba(1.0, 0.533_333_36, 0.0, 1.0))
+        );
+        assert_eq!(orange.to_hex(), "#ff8800cc");
+        assert!(close(
+            "rgb(255, 136, 0, 80%)".parse::<Colors>().unwrap().rgba(),
+            orange.rgba()
+        ));
+        assert!(close(
+            "rgb(100% 53.3% 0 / 0.8)".parse::<Colors>().unwrap().rgba(),
+            orange.rgba()
+        ));
+        assert_eq!("Skyblue".parse(), Ok(Colors::SKYBLUE));
+        assert!(close(
+            "hsl(120deg, 100%, 50%)".parse::<Colors>().unwrap().rgba(),
+            [0.0, 1.0, 0.0, 1.0]
+        ));
+        assert!(close(
+            "hsv(240 100 50)".parse::<Colors>().unwrap().rgba(),
+            [0.0, 0.0, 0.5, 1.0]
+        ));
+
+        assert_eq!("#ff88".parse::<Colors>().unwrap().alpha(), 0.53333336);
+        assert_eq!("#ff88f".parse::<Colors>(), Err(ColorParseError::InvalidHex));
+        assert_eq!(
+            "rgb(1, 2)".parse::<Colors>(),
+            Err(ColorParseError::Arguments {
+                function: "rgb".to_string(),
+                found: 2
+            })
+        );
+        assert_eq!(
+            "lab(1, 2, 3)".parse::<Colors>(),
+            Err(ColorParseError::UnknownFunction("lab".to_string()))
+        );
+    }
+
+    #[test]
+    fn conversions() {
+        for color in [
+            Colors::ORANGE,
+            Colors::SKYBLUE,
+            Colors::BRONZE,
+            Colors::WHITE,
+        ] {
+            let rgba = color.rgba();
+            for space in [ColorSpace::LinearSrgb, ColorSpace::Oklab, ColorSpace::Oklch] {
+                assert!(close(space.to_srgb(space.from_srgb(rgba)), rgba));
+            }
+        }
+        assert!(close(
+            Colors::FHsl(0.0, 1.0, 0.25).rgba(),
+            [0.5, 0.0, 0.0, 1.0]
+        ));
+        assert!(close(
+            Colors::FHsva(300.0, 0.5, 1.0, 0.5).rgba(),
+            [1.0, 0.5, 1.0, 0.5]
+        ));
+        assert!(close(Colors::FOklab(1.0, 0.0, 0.0, 1.0).rgba(), [1.0; 4]));
+        assert_eq!(
+            Colors::FHsl(0.0, 1.0, 0.5).with_alpha(0.5),
+            Colors::FRgba(1.0, 0.0, 0.0, 0.5)
+        );
+    }
+
+    #[test]
+    fn manipulation() {
+        assert!((Colors::BLACK.contrast_ratio(Colors::WHITE) - 21.0).abs() < 1e-3);
+        assert_eq!(Colors::GRAY.contrast_ratio(Colors::GRAY), 1.0);
+
+        let lighter = Colors::BLUE.lighten(0.1);
+        assert!(lighter.luminance() > Colors::BLUE.luminance());
+        assert!(Colors::BLUE.darken(0.1).luminance() < Colors::BLUE.luminance());
+
+        // the blend of two saturated colors stays saturated in OKLCH
+        let srgb = Colors::RED.mix(Colors::BLUE, 0.5, ColorSpace::Srgb);
+        let oklch = Colors::RED.mix(Colors::BLUE, 0.5, ColorSpace::Oklch);
+        assert!(oklch.luminance() > srgb.luminance());
+        assert!(close(
+            Colors::WHITE
+                .mix(Colors::RED, 1.0, ColorSpace::Oklch)
+                .rgba(),
+            Colors::RED.rgba()
+        ));
+    }
+}
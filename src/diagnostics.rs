@@ -0,0 +1,472 @@
+//! Checks of the way elements read and write variables
+//!
+//! Elements are laid out in tree order, an element reading a variable set by
+//! a `Value::SetVariable` of a later element sees the value of the previous
+//! update, or 0.0 on the first one. `Gui::check_variables` walks the tree
+//! in the same order and reports such reads together with conflicting writes
+//! and variables that depend on each other. Reads of unset variables and writes
+//! of constants that happen during the layout are collected in `Gui::var_issues`.
+
+use std::fmt;
+
+use crate::{
+    styles::{Style, Value},
+    variables::{VarFault, VarKey, VarKind},
+    ElementKey, Gui, ImageData,
+};
+
+/// Place a variable is read or written at
+#[derive(Debug, Clone, PartialEq)]
+pub struct VarSite {
+    pub element: ElementKey,
+    pub label: Option<String>,
+    /// Style holding the value, `None` for a procedure
+    pub style: Option<Style>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum VarIssue {
+    /// Variable without a value is read, but never written
+    Uninitialized { var: VarKey, reader: VarSite },
+    /// Variable is read before the element that writes it is laid out
+    ReadBeforeWrite {
+        var: VarKey,
+        reader: VarSite,
+        writer: VarSite,
+    },
+    /// Variable is written at more than one place
+    WriteConflict { var: VarKey, writers: Vec<VarSite> },
+    /// Constant variable is written
+    ConstantWrite { var: VarKey, writer: VarSite },
+    /// Variables are computed from each other
+    Cycle {
+        vars: Vec<VarKey>,
+        writers: Vec<VarSite>,
+    },
+}
+
+impl fmt::Display for VarSite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.label {
+            Some(label) => write!(f, "'{label}'")?,
+            None => write!(f, "element {}", self.element.index())?,
+        }
+        match self.style {
+            Some(style) => write!(f, " ({style:?})"),
+            None => write!(f, " (procedure)"),
+        }
+    }
+}
+
+impl fmt::Display for VarIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VarIssue::Uninitialized { var, reader } => {
+                write!(f, "variable {} read by {reader} is never set", var.raw())
+            }
+            VarIssue::ReadBeforeWrite {
+                var,
+                reader,
+                writer,
+            } => write!(
+                f,
+                "variable {} is read by {reader} before it is set by {writer}",
+                var.raw()
+            ),
+            VarIssue::WriteConflict { var, writers } => {
+                write!(f, "variable {} is set by", var.raw())?;
+                for (i, writer) in writers.iter().enumerate() {
+                    write!(f, "{} {writer}", if i == 0 { "" } else { "," })?;
+                }
+                Ok(())
+            }
+            VarIssue::ConstantWrite { var, writer } => {
+                write!(f, "constant variable {} is set by {writer}", var.raw())
+            }
+            VarIssue::Cycle { vars, writers } => {
+                write!(f, "variables")?;
+                for var in vars {
+                    write!(f, " {}", var.raw())?;
+                }
+                write!(f, " depend on each other, set by")?;
+                for (i, writer) in writers.iter().enumerate() {
+                    write!(f, "{} {writer}", if i == 0 { "" } else { "," })?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for VarIssue {}
+
+/// Variables read and written by a single value
+#[derive(Default)]
+struct Access {
+    reads: Vec<VarKey>,
+    /// Written variable and the variables its new value is computed from
+    writes: Vec<(VarKey, Vec<VarKey>)>,
+}
+
+impl Access {
+    fn collect(&mut self, value: &Value) {
+        match value {
+            Value::Variable(key) => self.reads.push(*key),
+            Value::SetVariable(key, inner) => {
+                let mut access = Access::default();
+                access.collect(inner);
+                self.reads.extend(&access.reads);
+                self.writes.extend(access.writes);
+                self.writes.push((*key, access.reads));
+            }
+            _ => value.for_each_child(|v| self.collect(v)),
+        }
+    }
+}
+
+impl<Msg: Clone, Img: Clone + ImageData> Gui<Msg, Img> {
+    /// Checks the variables read and written by the elements under the entry
+    pub fn check_variables(&self) -> Result<(), Vec<VarIssue>> {
+        let mut sites = Vec::new();
+        if let Some(entry) = self.entry {
+            self.collect_access(entry, &mut sites);
+        }
+
+        let mut issues = Vec::new();
+        let mut writers: Vec<(VarKey, Vec<VarSite>)> = Vec::new();
+        for (site, access) in &sites {
+            for (var, _) in &access.writes {
+                match writers.iter_mut().find(|(k, _)| k == var) {
+                    Some((_, sites)) if !sites.contains(site) => sites.push(site.clone()),
+                    Some(_) => (),
+                    None => writers.push((*var, vec![site.clone()])),
+                }
+            }
+        }
+        for (var, sites) in &writers {
+            if sites.len() > 1 {
+                issues.push(VarIssue::WriteConflict {
+                    var: *var,
+                    writers: sites.clone(),
+                });
+            }
+            if self.variables.kind(*var) == Some(VarKind::Constant) {
+                issues.push(VarIssue::ConstantWrite {
+                    var: *var,
+                    writer: sites[0].clone(),
+                });
+            }
+        }
+
+        let mut written: Vec<VarKey> = Vec::new();
+        let mut reported: Vec<VarKey> = Vec::new();
+        for (site, access) in &sites {
+            for var in &access.reads {
+                if written.contains(var) || reported.contains(var) {
+                    continue;
+                }
+                match writers.iter().find(|(k, _)| k == var) {
+                    // computing a variable from itself is reported as a cycle
+                    Some((_, sites)) if sites.contains(site) => continue,
+                    Some((_, sites)) => issues.push(VarIssue::ReadBeforeWrite {
+                        var: *var,
+                        reader: site.clone(),
+                        writer: sites[0].clone(),
+                    }),
+                    None if self.variables.value(*var).is_none() => {
+                        issues.push(VarIssue::Uninitialized {
+                            var: *var,
+                            reader: site.clone(),
+                        })
+                    }
+                    None => continue,
+                }
+                reported.push(*var);
+            }
+            written.extend(access.writes.iter().map(|(k, _)| *k));
+        }
+
+        let edges: Vec<(VarKey, VarKey)> = sites
+            .iter()
+            .flat_map(|(_, a)| &a.writes)
+            .flat_map(|(var, from)| from.iter().map(|f| (*var, *f)))
+            .collect();
+        for cycle in cycles(&edges) {
+            let writers = writers
+                .iter()
+                .filter(|(k, _)| cycle.contains(k))
+                .flat_map(|(_, sites)| sites.iter().cloned())
+                .collect();
+            issues.push(VarIssue::Cycle {
+                vars: cycle,
+                writers,
+            });
+        }
+
+        if issues.is_empty() {
+            Ok(())
+        } else {
+            Err(issues)
+        }
+    }
+
+    /// Failed variable accesses met during the layout
+    ///
+    /// Reads of variables without a value fall back to 0.0 and are reported as
+    /// `VarIssue::Uninitialized`, writes of constants are not stored and are reported
+    /// as `VarIssue::ConstantWrite`. Every issue is listed once.
+    pub fn var_issues(&self) -> &[VarIssue] {
+        &self.var_issues
+    }
+
+    /// Turns the faults of the layout of `key` into issues
+    ///
+    /// Values of the children are computed by flex layouts of their parent,
+    /// they are searched when the element itself does not access the variable.
+    pub(crate) fn report_var_faults(&mut self, key: ElementKey, children: &[ElementKey]) {
+        let faults = std::mem::take(&mut self.variables.faults);
+        let mut sites = Vec::new();
+        self.element_access(key, &mut sites);
+        for child in children {
+            self.element_access(*child, &mut sites);
+        }
+        for fault in faults {
+            let (VarFault::Read(var) | VarFault::Write(var)) = fault;
+            let accessed = |access: &Access| match fault {
+                VarFault::Read(_) => access.reads.contains(&var),
+                VarFault::Write(_) => access.writes.iter().any(|(k, _)| *k == var),
+            };
+            let site = match sites.iter().find(|(_, a)| accessed(a)) {
+                Some((site, _)) => site.clone(),
+                None => VarSite {
+                    element: key,
+                    label: self.elements[key.index()].label.clone(),
+                    style: None,
+                },
+            };
+            let issue = match fault {
+                VarFault::Read(_) => VarIssue::Uninitialized { var, reader: site },
+                VarFault::Write(_) => VarIssue::ConstantWrite { var, writer: site },
+            };
+            if !self.var_issues.contains(&issue) {
+                self.var_issues.push(issue);
+            }
+        }
+    }
+
+    /// Collects the accesses of the subtree in layout order
+    fn collect_access(&self, key: ElementKey, sites: &mut Vec<(VarSite, Access)>) {
+        self.element_access(key, sites);
+        let Some(element) = self.get_element(key) else {
+            return;
+        };
+        for child in element.children.iter().flatten() {
+            self.collect_access(*child, sites);
+        }
+    }
+
+    /// Collects the accesses of the values of the element itself
+    fn element_access(&self, key: ElementKey, sites: &mut Vec<(VarSite, Access)>) {
+        let Some(element) = self.get_element(key) else {
+            return;
+        };
+        let site = |style| VarSite {
+            element: key,
+            label: element.label.clone(),
+            style,
+        };
+        for proc in &element.procedures {
+            let mut access = Access::default();
+            access.collect(proc);
+            sites.push((site(None), access));
+        }
+        element.styles().for_each_value(|style, value| {
+            let mut access = Access::default();
+            access.collect(value);
+            if !access.reads.is_empty() || !access.writes.is_empty() {
+                sites.push((site(Some(style)), access));
+            }
+        });
+    }
+}
+
+/// Cycles of the graph given by edges from a variable to the ones it is computed from
+fn cycles(edges: &[(VarKey, VarKey)]) -> Vec<Vec<VarKey>> {
+    fn visit(
+        var: VarKey,
+        edges: &[(VarKey, VarKey)],
+        path: &mut Vec<VarKey>,
+        done: &mut Vec<VarKey>,
+        found: &mut Vec<Vec<VarKey>>,
+    ) {
+        if let Some(start) = path.iter().position(|v| *v == var) {
+            let mut cycle = path[start..].to_vec();
+            cycle.sort();
+            if !found.contains(&cycle) {
+                found.push(cycle);
+            }
+            return;
+        }
+        if done.contains(&var) {
+            return;
+        }
+        path.push(var);
+        for (_, next) in edges.iter().filter(|(from, _)| *from == var) {
+            visit(*next, edges, path, done, found);
+        }
+        path.pop();
+        done.push(var);
+    }
+
+    let mut found = Vec::new();
+    let mut done = Vec::new();
+    for (var, _) in edges {
+        visit(*var, edges, &mut Vec::new(), &mut done, &mut found);
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZero;
+
+    use super::*;
+    use crate::{variables::Variable, Element};
+
+    #[test]
+    fn variable_issues() {
+        let mut gui: Gui = Gui::new((NonZero::new(800).unwrap(), NonZero::new(600).unwrap()));
+        let late = gui.variables.push(Variable::new_var());
+        let unset = gui.variables.push(Variable::new_var());
+        let a = gui.variables.push(Variable::new_var());
+        let b = gui.variables.push(Variable::new_var());
+
+        let mut reader = Element::default();
+        reader.label = Some("reader".into());
+        reader
+            .styles_mut()
+            .width
+            .set(Value::Variable(late) + Value::Variable(unset));
+        let reader = gui.add_element(reader);
+
+        let mut writer = Element::default();
+        writer.label = Some("writer".into());
+        writer
+            .procedures
+            .push(Value::set_variable(late, Value::Px(10.0)));
+        writer
+            .styles_mut()
+            .height
+            .set(Value::set_variable(a, Value::Variable(b)));
+        let writer = gui.add_element(writer);
+
+        let mut other = Element::default();
+        other
+            .styles_mut()
+            .height
+            .set(Value::set_variable(b, Value::Variable(a)));
+        other
+            .procedures
+            .push(Value::set_variable(late, Value::Px(20.0)));
+        let other = gui.add_element(other);
+
+        let mut root = Element::default();
+        root.children = Some(vec![reader, writer, other]);
+        let root = gui.add_element(root);
+        gui.set_entry(root);
+
+        let issues = gui.check_variables().unwrap_err();
+        let writer_site = VarSite {
+            element: writer,
+            label: Some("writer".into()),
+            style: None,
+        };
+        assert!(issues.contains(&VarIssue::ReadBeforeWrite {
+            var: late,
+            reader: VarSite {
+                element: reader,
+                label: Some("reader".into()),
+                style: Some(Style::Width),
+            },
+            writer: writer_site.clone(),
+        }));
+        assert!(issues
+            .iter()
+            .any(|i| matches!(i, VarIssue::Uninitialized { var, .. } if *var == unset)));
+        assert!(issues.iter().any(|i| matches!(
+            i,
+            VarIssue::WriteConflict { var, writers } if *var == late && writers[0] == writer_site
+        )));
+        assert!(issues
+            .iter()
+            .any(|i| matches!(i, VarIssue::Cycle { vars, .. } if *vars == vec![a, b])));
+        // the cycle also reads `b` before it is set
+        assert!(issues
+            .iter()
+            .any(|i| matches!(i, VarIssue::ReadBeforeWrite { var, .. } if *var == b)));
+        assert_eq!(issues.len(), 5);
+        assert_eq!(
+            issues[0].to_string(),
+            "variable 0 is set by 'writer' (procedure), element 2 (procedure)"
+        );
+    }
+
+    #[test]
+    fn self_reference() {
+        let mut gui: Gui = Gui::new((NonZero::new(800).unwrap(), NonZero::new(600).unwrap()));
+        let counter = gui.variables.push(Variable::new_var());
+        gui.variables.set(counter, 0.0).unwrap();
+
+        let mut element = Element::default();
+        element.styles_mut().width.set(Value::set_variable(
+            counter,
+            Value::Variable(counter) + Value::Px(1.0),
+        ));
+        let element = gui.add_element(element);
+        gui.set_entry(element);
+
+        // the value read is the one of the previous update, not a read before the write
+        let issues = gui.check_variables().unwrap_err();
+        assert_eq!(issues.len(), 1);
+        assert!(matches!(&issues[0], VarIssue::Cycle { vars, .. } if *vars == vec![counter]));
+    }
+
+    #[test]
+    fn layout_issues() {
+        let mut gui: Gui = Gui::new((NonZero::new(800).unwrap(), NonZero::new(600).unwrap()));
+        let unset = gui.variables.push(Variable::new_var());
+        let constant = gui.variables.push(Variable::new_const(5.0));
+
+        let mut panel = Element::default();
+        panel.label = Some("panel".into());
+        let styles = panel.styles_mut();
+        styles.width.set(Value::Variable(unset) + Value::Px(10.0));
+        styles.height.set(Value::set_variable(constant, Value::Px(20.0)));
+        let panel = gui.add_element(panel);
+        gui.set_entry(panel);
+
+        gui.update(0.0);
+        gui.update(1.0);
+        let size = gui.get_element(panel).unwrap().instance().container.size;
+        assert_eq!(size, crate::Vector(10.0, 20.0));
+        assert_eq!(gui.variables.get(constant), Some(5.0));
+        let site = |style| VarSite {
+            element: panel,
+            label: Some("panel".into()),
+            style: Some(style),
+        };
+        assert_eq!(
+            gui.var_issues(),
+            [
+                VarIssue::Uninitialized {
+                    var: unset,
+                    reader: site(Style::Width),
+                },
+                VarIssue::ConstantWrite {
+                    var: constant,
+                    writer: site(Style::Height),
+                },
+            ]
+        );
+    }
+}
@@ -1,23 +1,130 @@
 use std::fmt::Debug;
 
-use crate::{text::DEFAULT_FONT_SIZE, EventListeners, ImageData, Styles, Value, Vector};
-
+use crate::{
+    animation::Transitions,
+    styles::{Corners, Dependencies, Sides},
+    stylesheet::{Cascade, PseudoState, StylePatch},
+    text::DEFAULT_FONT_SIZE,
+    EventListeners, ImageData, Styles, Value, Vector,
+};
+
+/// Node of the element tree
+///
+/// With the `serde` feature the element serializes without its place in the tree,
+/// see `snapshot::ElementTree` for whole subtrees.
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound = "Msg: crate::snapshot::MsgResolver")
+)]
 pub struct Element<Msg: Clone, Img: Clone + ImageData> {
     pub label: Option<String>,
+    /// Classes matched by `stylesheet::Selector`s
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) classes: Vec<String>,
+    /// Styles that win over the stylesheet
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) overrides: StylePatch<Img>,
+    /// Styles applied while the element is in the given state
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) state_styles: Vec<(PseudoState, StylePatch<Img>)>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) states: ElementStates,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) cascade: Cascade<Img>,
     pub events: EventListeners<Msg>,
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub children: Option<Vec<ElementKey>>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) parent: Option<ElementKey>,
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub(crate) instance: ElementInstance,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) shadows: Vec<ShadowInstance>,
     pub(crate) styles: Styles<Img>,
+    #[cfg_attr(feature = "serde", serde(skip, default = "crate::snapshot::dirty"))]
     pub(crate) dirty_styles: bool,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) layout: LayoutState,
     pub procedures: Vec<Value>,
 }
 
+/// Bookkeeping of the incremental layout
+#[derive(Debug, Clone, Default)]
+pub(crate) struct LayoutState {
+    /// Element has to be laid out again
+    pub needs_update: bool,
+    /// Some element in the subtree has to be laid out again
+    pub dirty_descendant: bool,
+    /// Children changed since the last layout
+    pub dirty_tree: bool,
+    /// Children seen by the last `Gui::update`, edits through mutable access are found with it
+    pub children: Vec<ElementKey>,
+    /// Element is queued in `Gui::touched`
+    pub touched: bool,
+    /// Element is listed in `Gui::watched`
+    pub watched: bool,
+    /// Dependencies changed, everything has to be recomputed
+    pub force: bool,
+    pub deps: Dependencies,
+    /// `Variables::stamp` at the time of the last layout
+    pub var_stamp: u64,
+    /// Container passed to the children during the last layout
+    pub children_container: Container,
+    /// Element is placed by the flex layout of its parent
+    pub flex_item: bool,
+    /// Slot assigned by the flex layout of the parent
+    pub slot: Container,
+    /// Intrinsic size measured after the last layout, see `styles::Container::Content`
+    pub content: Vector,
+    /// Smallest size the text fits into without overflowing, its widest word
+    pub min_content: Vector,
+    /// Width resolves from the content, text is measured without wrapping
+    pub fit_width: bool,
+    /// Position of the element before it is rotated around its container
+    pub pos: Vector,
+    pub transitions: Transitions,
+}
+
+/// Interactive states of an element, see `PseudoState`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ElementStates {
+    pub hover: bool,
+    pub active: bool,
+    pub focus: bool,
+    pub disabled: bool,
+}
+
+/// Generational handle to an element stored in a `Gui`
+///
+/// Slots of removed elements are reused, the generation makes sure
+/// that keys of removed elements do not resolve to their successors.
 #[derive(Debug, Hash, PartialEq, Eq, Copy, Clone)]
-pub struct ElementKey(pub(crate) u64);
+pub struct ElementKey {
+    pub(crate) idx: u32,
+    pub(crate) generation: u32,
+}
 
 impl ElementKey {
+    /// Packed representation, generation in the upper 32 bits and
+    /// slot index in the lower 32 bits
     pub fn raw(&self) -> u64 {
-        self.0
+        ((self.generation as u64) << 32) | self.idx as u64
+    }
+
+    /// Index of the slot this key points to
+    ///
+    /// Slot indices are stable for the lifetime of an element and are
+    /// reused after removal, drawing layers can use them to index their
+    /// per-element buffers.
+    #[inline]
+    pub fn index(&self) -> usize {
+        self.idx as usize
+    }
+
+    pub fn generation(&self) -> u32 {
+        self.generation
     }
 }
 
@@ -26,8 +133,7 @@ pub struct ElementInstance {
     pub container: Container,
     pub color: [f32; 4],
     pub flags: u32,
-    pub round: f32,
-    pub shadow: f32,
+    pub round: Corners<f32>,
     pub alpha: f32,
     /// x, y
     pub lin_grad_p1: Vector,
@@ -41,17 +147,53 @@ pub struct ElementInstance {
     pub rad_grad_p2: Vector,
     pub rad_grad_color1: [f32; 4],
     pub rad_grad_color2: [f32; 4],
+    /// x, y
+    pub con_grad_p1: Vector,
+    /// x, y
+    pub con_grad_p2: Vector,
+    pub con_grad_color1: [f32; 4],
+    pub con_grad_color2: [f32; 4],
     pub image_tint: [f32; 4],
     pub image_size: Vector,
+    /// Top left corner of the drawn image relative to the top left corner of the element
+    pub image_offset: Vector,
+    /// Size of the drawn image after `styles::ImageFit` is applied
+    pub image_draw_size: Vector,
+    /// Drawn part of the image as texture coordinates: min x, min y, max x, max y
+    pub image_uv: [f32; 4],
+    /// Nine-slice insets in pixels of the source, used with `Flags::NineSlice`
+    pub image_slice: Sides<f32>,
     pub scroll: Vector,
-    pub padding: f32,
+    pub padding: Sides<f32>,
     pub shadow_alpha: f32,
     pub font: u16,
     pub font_size: f32,
     pub font_color: [f32; 4],
     pub text_wrap: bool,
     pub text_align: f32,
-    pub margin: f32,
+    pub margin: Sides<f32>,
+    pub border: Sides<f32>,
+    pub border_color: [f32; 4],
+    pub border_grad_p1: Vector,
+    pub border_grad_p2: Vector,
+    pub border_grad_color1: [f32; 4],
+    pub border_grad_color2: [f32; 4],
+    /// Length of dashes and gaps, solid border when the length is zero
+    pub border_dash: Vector,
+    pub outline_width: f32,
+    pub outline_offset: f32,
+    pub outline_color: [f32; 4],
+}
+
+/// Shadow of an element resolved to pixels, see `styles::Shadow`
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct ShadowInstance {
+    /// Offset in the rotated frame of the element
+    pub offset: Vector,
+    pub blur: f32,
+    pub spread: f32,
+    pub color: [f32; 4],
+    pub inset: bool,
 }
 
 #[repr(u32)]
@@ -61,6 +203,11 @@ pub enum Flags {
     RadialGradient,
     Image,
     OverflowHidden,
+    Border,
+    BorderGradient,
+    Outline,
+    ConicGradient,
+    NineSlice,
     Count,
 }
 
@@ -90,6 +237,16 @@ impl Flags {
     }
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TreeError {
+    /// One of the keys does not point to a live element
+    NotFound,
+    /// The element would become its own ancestor
+    Cycle,
+    /// The sibling is not attached to any parent
+    NoParent,
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Default)]
 #[repr(C)]
 pub struct Container {
@@ -111,6 +268,11 @@ impl<Msg: Clone, Img: Clone + ImageData> Element<Msg, Img> {
         &self.instance
     }
 
+    /// Shadows resolved by the last layout, in the order they are drawn
+    pub fn shadows(&self) -> &[ShadowInstance] {
+        &self.shadows
+    }
+
     pub fn styles(&self) -> &Styles<Img> {
         &self.styles
     }
@@ -120,6 +282,11 @@ impl<Msg: Clone, Img: Clone + ImageData> Element<Msg, Img> {
         &mut self.styles
     }
 
+    /// Parent of the element, `None` for the entry and detached elements
+    pub fn parent(&self) -> Option<ElementKey> {
+        self.parent
+    }
+
     pub fn child(&self, idx: usize) -> Option<&ElementKey> {
         match &self.children {
             Some(c) => c.get(idx),
@@ -127,7 +294,101 @@ impl<Msg: Clone, Img: Clone + ImageData> Element<Msg, Img> {
         }
     }
 
+    pub fn classes(&self) -> &[String] {
+        &self.classes
+    }
+
+    pub fn has_class(&self, class: &str) -> bool {
+        self.classes.iter().any(|c| c == class)
+    }
+
+    pub fn add_class(&mut self, class: impl Into<String>) {
+        let class = class.into();
+        if !self.has_class(&class) {
+            self.classes.push(class);
+            self.cascade.restyle = true;
+        }
+    }
+
+    /// Returns whether the element had the class
+    pub fn remove_class(&mut self, class: &str) -> bool {
+        let len = self.classes.len();
+        self.classes.retain(|c| c != class);
+        self.cascade.restyle |= len != self.classes.len();
+        len != self.classes.len()
+    }
+
+    pub fn overrides(&self) -> &StylePatch<Img> {
+        &self.overrides
+    }
+
+    /// Styles set here are applied after the stylesheet on the next update
+    pub fn overrides_mut(&mut self) -> &mut StylePatch<Img> {
+        self.cascade.restyle = true;
+        &mut self.overrides
+    }
+
+    pub fn states(&self) -> ElementStates {
+        self.states
+    }
+
+    pub fn is_disabled(&self) -> bool {
+        self.states.disabled
+    }
+
+    /// Disabled elements and their subtrees receive no `ElemEvent`s and can not be selected
+    pub fn set_disabled(&mut self, disabled: bool) {
+        if self.states.disabled == disabled {
+            return;
+        }
+        self.states.disabled = disabled;
+        if disabled {
+            self.states.hover = false;
+            self.states.active = false;
+        }
+        self.cascade.restyle = true;
+        // selectables are collected again
+        self.events.changed = true;
+    }
+
+    pub fn state_style(&self, state: PseudoState) -> Option<&StylePatch<Img>> {
+        self.state_styles
+            .iter()
+            .find(|(s, _)| *s == state)
+            .map(|(_, styles)| styles)
+    }
+
+    /// Styles applied after `Element::overrides` while the element is in `state`
+    pub fn state_style_mut(&mut self, state: PseudoState) -> &mut StylePatch<Img> {
+        self.cascade.restyle = true;
+        let idx = match self.state_styles.iter().position(|(s, _)| *s == state) {
+            Some(idx) => idx,
+            None => {
+                self.state_styles.push((state, StylePatch::default()));
+                self.state_styles.len() - 1
+            }
+        };
+        &mut self.state_styles[idx].1
+    }
+
+    /// Returns whether the state changed
+    pub(crate) fn set_state(&mut self, state: PseudoState, on: bool) -> bool {
+        let flag = match state {
+            PseudoState::Hover => &mut self.states.hover,
+            PseudoState::Active => &mut self.states.active,
+            PseudoState::Focus => &mut self.states.focus,
+            PseudoState::Disabled => &mut self.states.disabled,
+        };
+        if *flag == on {
+            return false;
+        }
+        *flag = on;
+        self.cascade.restyle = true;
+        true
+    }
+
     pub fn add_child(&mut self, key: ElementKey) {
+        self.layout.dirty_tree = true;
         match &mut self.children {
             Some(children) => {
                 children.push(key);
@@ -143,12 +404,31 @@ impl<Msg: Clone, Img: Clone + ImageData> Default for Element<Msg, Img> {
     fn default() -> Self {
         Self {
             label: None,
+            classes: Vec::new(),
+            overrides: StylePatch::default(),
+            state_styles: Vec::new(),
+            states: ElementStates::default(),
+            cascade: Cascade::default(),
             events: EventListeners::new(),
             children: None,
+            parent: None,
             instance: ElementInstance::default(),
+            shadows: Vec::new(),
             styles: Styles::default(),
             procedures: Vec::new(),
             dirty_styles: true,
+            layout: LayoutState::default(),
+        }
+    }
+}
+
+impl Container {
+    /// Container shrunk by `sides`, measured in its rotated frame
+    pub fn inset(&self, sides: &Sides<f32>) -> Container {
+        Container {
+            pos: self.pos + sides.offset().rotate_around_origin(self.rotation),
+            size: (self.size - sides.size()).max(0.0),
+            rotation: self.rotation,
         }
     }
 }
@@ -191,6 +471,18 @@ impl ContainerWrapper {
         self.container.rotation = v;
     }
 
+    /// Replaces the container, marking only the parts that differ as dirty
+    pub fn update(&mut self, c: &Container) {
+        self.dirty_pos |= self.container.pos != c.pos;
+        self.dirty_size |= self.container.size != c.size;
+        self.dirty_rotation |= self.container.rotation != c.rotation;
+        self.container = *c;
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.dirty_pos || self.dirty_size || self.dirty_rotation
+    }
+
     pub fn clean(&mut self) {
         self.dirty_pos = false;
         self.dirty_size = false;
@@ -255,8 +547,7 @@ impl Default for ElementInstance {
             container: Container::default(),
             color: [0.0; 4],
             flags: 0,
-            round: 0.0,
-            shadow: 0.0,
+            round: Corners::default(),
             alpha: 1.0,
             lin_grad_p1: Vector::default(),
             lin_grad_p2: Vector::default(),
@@ -266,22 +557,58 @@ impl Default for ElementInstance {
             rad_grad_p2: Vector::default(),
             rad_grad_color1: [0.0; 4],
             rad_grad_color2: [0.0; 4],
+            con_grad_p1: Vector::default(),
+            con_grad_p2: Vector::default(),
+            con_grad_color1: [0.0; 4],
+            con_grad_color2: [0.0; 4],
             image_size: Vector::ZERO,
+            image_offset: Vector::ZERO,
+            image_draw_size: Vector::ZERO,
+            image_uv: [0.0, 0.0, 1.0, 1.0],
+            image_slice: Sides::default(),
             image_tint: [1.0; 4],
             scroll: Vector::ZERO,
-            padding: 0.0,
+            padding: Sides::default(),
             shadow_alpha: 1.0,
             font: 0,
             font_size: DEFAULT_FONT_SIZE,
             font_color: [1.0, 1.0, 1.0, 1.0],
             text_wrap: true,
             text_align: 0.0,
-            margin: 0.0,
+            margin: Sides::default(),
+            border: Sides::default(),
+            border_color: [0.0; 4],
+            border_grad_p1: Vector::ZERO,
+            border_grad_p2: Vector::ZERO,
+            border_grad_color1: [0.0; 4],
+            border_grad_color2: [0.0; 4],
+            border_dash: Vector::ZERO,
+            outline_width: 0.0,
+            outline_offset: 0.0,
+            outline_color: [0.0; 4],
+        }
+    }
+}
+
+impl ElementStates {
+    pub fn contains(&self, state: PseudoState) -> bool {
+        match state {
+            PseudoState::Hover => self.hover,
+            PseudoState::Active => self.active,
+            PseudoState::Focus => self.focus,
+            PseudoState::Disabled => self.disabled,
         }
     }
 }
 
 impl ElementInstance {
+    /// Color of the `shadow` style, it glows in the color of the element
+    pub(crate) fn legacy_shadow_color(&self) -> [f32; 4] {
+        let mut color = self.color;
+        color[3] *= self.shadow_alpha;
+        color
+    }
+
     pub fn set_flag(&mut self, flag: Flags) {
         self.flags |= u32::from(flag);
     }
@@ -289,4 +616,27 @@ impl ElementInstance {
     pub fn remove_flag(&mut self, flag: Flags) {
         self.flags &= !u32::from(flag);
     }
+
+    /// Area inside the border, children are clipped to it when overflow is hidden
+    pub fn clip_container(&self) -> Container {
+        self.container.inset(&self.border)
+    }
+
+    /// Whether `point` lies inside the rounded shape of the element,
+    /// also returns the point relative to its center
+    pub fn hit(&self, point: Vector) -> (bool, Vector) {
+        let (inside, pos) = point.container_colision_with_pos(&self.container);
+        let inside = inside && self.round.contains(pos, self.container.size * 0.5);
+        (inside, pos)
+    }
+
+    /// Whether `point` lies inside the rounded area of `clip_container`
+    pub fn clip_hit(&self, point: Vector) -> bool {
+        let clip = self.clip_container();
+        let widest = self.border.iter().fold(0.0f32, |a, b| a.max(*b));
+        let round = self.round.map(|r| (r - widest).max(0.0));
+        point
+            .container_colision(&clip)
+            .is_some_and(|pos| round.contains(pos, clip.size * 0.5))
+    }
 }
@@ -1,6 +1,6 @@
 use std::path::PathBuf;
 
-use crate::{ElementKey, Vector};
+use crate::{styles::Style, timeline::TimelineKey, ElementKey, Vector};
 
 #[derive(Debug, Clone)]
 pub enum EnvEvents {
@@ -45,6 +45,11 @@ pub enum EnvEventCategories {
 }
 
 #[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound = "Msg: crate::snapshot::MsgResolver")
+)]
 pub struct EventListeners<Msg: Clone> {
     pub(crate) mouse_move: Vec<EventListener<Msg>>,
     pub(crate) click: Vec<EventListener<Msg>>,
@@ -55,6 +60,11 @@ pub struct EventListeners<Msg: Clone> {
     pub(crate) mouse_enter: Vec<EventListener<Msg>>,
     pub(crate) mouse_leave: Vec<EventListener<Msg>>,
     pub(crate) selection: Vec<EventListener<Msg>>,
+    pub(crate) transition_end: Vec<EventListener<Msg>>,
+    pub(crate) timeline_marker: Vec<EventListener<Msg>>,
+    /// Listeners were added since the last `Gui::update`
+    #[cfg_attr(feature = "serde", serde(skip, default = "crate::snapshot::dirty"))]
+    pub(crate) changed: bool,
 }
 
 impl<Msg: Clone> EventListeners<Msg> {
@@ -69,10 +79,14 @@ impl<Msg: Clone> EventListeners<Msg> {
             mouse_enter: Vec::with_capacity(0),
             mouse_leave: Vec::with_capacity(0),
             selection: Vec::with_capacity(0),
+            transition_end: Vec::with_capacity(0),
+            timeline_marker: Vec::with_capacity(0),
+            changed: true,
         }
     }
 
     pub fn add(&mut self, listener: EventListener<Msg>) {
+        self.changed = true;
         match listener.event {
             ElemEventTypes::MouseMove => self.mouse_move.push(listener),
             ElemEventTypes::Click => self.click.push(listener),
@@ -83,13 +97,21 @@ impl<Msg: Clone> EventListeners<Msg> {
             ElemEventTypes::MouseEnter => self.mouse_enter.push(listener),
             ElemEventTypes::MouseLeave => self.mouse_leave.push(listener),
             ElemEventTypes::Selection => self.selection.push(listener),
+            ElemEventTypes::TransitionEnd => self.transition_end.push(listener),
+            ElemEventTypes::TimelineMarker => self.timeline_marker.push(listener),
         }
     }
 }
 
 #[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound = "Msg: crate::snapshot::MsgResolver")
+)]
 pub struct EventListener<Msg: Clone> {
     pub event: ElemEventTypes,
+    #[cfg_attr(feature = "serde", serde(with = "crate::snapshot::msg"))]
     pub msg: Option<Msg>,
     pub kind: ListenerTypes,
 }
@@ -190,6 +212,15 @@ pub enum ElemEvents {
     TextCopy {
         text: String,
     },
+    /// Transition of the style finished, see `StyleComponent::set_transition`
+    TransitionEnd {
+        style: Style,
+    },
+    /// Playhead of a timeline crossed a marker placed on the element
+    TimelineMarker {
+        timeline: TimelineKey,
+        name: String,
+    },
 }
 
 impl ElemEvents {
@@ -209,6 +240,7 @@ impl ElemEvents {
 }
 
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ListenerTypes {
     Listen,
     Peek,
@@ -216,6 +248,7 @@ pub enum ListenerTypes {
 }
 
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ElemEventTypes {
     MouseMove,
     Click,
@@ -226,6 +259,8 @@ pub enum ElemEventTypes {
     TextInput,
     KeyPress,
     Selection,
+    TransitionEnd,
+    TimelineMarker,
 }
 
 #[derive(Debug, Copy, Clone)]
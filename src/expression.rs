@@ -0,0 +1,475 @@
+//! Text syntax of `Value`
+//!
+//! ```text
+//! 50%c.w - 20px
+//! clamp(50%vp.w, 200px, 600px)
+//! if(100%this.w > 100%this.h, 10px, 20vh)
+//! ```
+//!
+//! Numbers without a unit are pixels. Units are `px`, `deg` (converted to
+//! radians), `vw`, `vh` and percents of a container dimension written as
+//! `%<container>.<dimension>`. Containers are `c`, `vp`, `this`, `img` and
+//! `content`, dimensions `w`, `h`, `d`, `min`, `max` and `avg`.
+//!
+//! Operators are `+`, `-`, `*`, `/` and `%`. Functions match the `Value`
+//! variants: `min`, `max`, `clamp`, `lerp`, `abs`, `floor`, `round`, `pow`,
+//! `sqrt`, `atan2`, `sin`, `cos`, `debug` and `if(a <cmp> b, then, else)`.
+//! `time` reads the update time and `$name` a variable.
+
+use std::{fmt, ops::Range, str::FromStr};
+
+use crate::{
+    styles::{Compare, Condition, Container, Portion, Value, Values},
+    variables::VarKey,
+};
+
+/// Error of `Value::parse`, `span` is the byte range of the source it points to
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+    pub span: Range<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseErrorKind {
+    UnexpectedChar(char),
+    UnexpectedEnd,
+    InvalidNumber,
+    UnknownUnit(String),
+    UnknownContainer(String),
+    UnknownDimension(String),
+    UnknownFunction(String),
+    UnknownVariable(String),
+    /// Function was called with the wrong number of arguments
+    Arguments {
+        function: String,
+        expected: usize,
+        found: usize,
+    },
+    /// `if` needs a comparison as its first argument
+    ExpectedComparison,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            ParseErrorKind::UnexpectedChar(c) => write!(f, "unexpected '{c}'")?,
+            ParseErrorKind::UnexpectedEnd => write!(f, "unexpected end of expression")?,
+            ParseErrorKind::InvalidNumber => write!(f, "invalid number")?,
+            ParseErrorKind::UnknownUnit(u) => write!(f, "unknown unit '{u}'")?,
+            ParseErrorKind::UnknownContainer(c) => write!(f, "unknown container '{c}'")?,
+            ParseErrorKind::UnknownDimension(d) => write!(f, "unknown dimension '{d}'")?,
+            ParseErrorKind::UnknownFunction(name) => write!(f, "unknown function '{name}'")?,
+            ParseErrorKind::UnknownVariable(name) => write!(f, "unknown variable '${name}'")?,
+            ParseErrorKind::Arguments {
+                function,
+                expected,
+                found,
+            } => write!(f, "'{function}' takes {expected} arguments, found {found}")?,
+            ParseErrorKind::ExpectedComparison => write!(f, "expected a comparison")?,
+        }
+        write!(f, " at {}..{}", self.span.start, self.span.end)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl FromStr for Value {
+    type Err = ParseError;
+
+    /// Parses an expression without variables, see `Value::parse`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Value::parse(s, |_| None)
+    }
+}
+
+impl Value {
+    /// Parses an expression, `variables` looks up the keys of `$name` references
+    pub fn parse(
+        src: &str,
+        variables: impl Fn(&str) -> Option<VarKey>,
+    ) -> Result<Self, ParseError> {
+        let mut parser = Parser {
+            src,
+            pos: 0,
+            variables: &variables,
+        };
+        let value = parser.expr()?;
+        parser.skip_ws();
+        match parser.peek() {
+            None => Ok(value),
+            Some(c) => Err(parser.error_char(c)),
+        }
+    }
+}
+
+struct Parser<'a> {
+    src: &'a str,
+    pos: usize,
+    variables: &'a dyn Fn(&str) -> Option<VarKey>,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<char> {
+        self.src[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn skip_ws(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.bump();
+        }
+    }
+
+    fn error(&self, kind: ParseErrorKind, span: Range<usize>) -> ParseError {
+        ParseError { kind, span }
+    }
+
+    fn error_char(&self, c: char) -> ParseError {
+        self.error(
+            ParseErrorKind::UnexpectedChar(c),
+            self.pos..self.pos + c.len_utf8(),
+        )
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), ParseError> {
+        self.skip_ws();
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.bump();
+                Ok(())
+            }
+            Some(c) => Err(self.error_char(c)),
+            None => Err(self.error(ParseErrorKind::UnexpectedEnd, self.pos..self.pos)),
+        }
+    }
+
+    fn ident(&mut self) -> &'a str {
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            self.bump();
+        }
+        &self.src[start..self.pos]
+    }
+
+    fn expr(&mut self) -> Result<Value, ParseError> {
+        let mut left = self.term()?;
+        loop {
+            self.skip_ws();
+            left = match self.peek() {
+                Some('+') => {
+                    self.bump();
+                    left + self.term()?
+                }
+                Some('-') => {
+                    self.bump();
+                    left - self.term()?
+                }
+                _ => return Ok(left),
+            };
+        }
+    }
+
+    fn term(&mut self) -> Result<Value, ParseError> {
+        let mut left = self.unary()?;
+        loop {
+            self.skip_ws();
+            left = match self.peek() {
+                Some('*') => {
+                    self.bump();
+                    left * self.unary()?
+                }
+                Some('/') => {
+                    self.bump();
+                    left / self.unary()?
+                }
+                Some('%') => {
+                    self.bump();
+                    left % self.unary()?
+                }
+                _ => return Ok(left),
+            };
+        }
+    }
+
+    fn unary(&mut self) -> Result<Value, ParseError> {
+        self.skip_ws();
+        if self.peek() == Some('-') {
+            self.bump();
+            return Ok(-self.unary()?);
+        }
+        self.atom()
+    }
+
+    fn atom(&mut self) -> Result<Value, ParseError> {
+        self.skip_ws();
+        let start = self.pos;
+        match self.peek() {
+            None => Err(self.error(ParseErrorKind::UnexpectedEnd, start..start)),
+            Some('(') => {
+                self.bump();
+                let value = self.expr()?;
+                self.expect(')')?;
+                Ok(value)
+            }
+            Some('$') => {
+                self.bump();
+                let name = self.ident();
+                match (self.variables)(name) {
+                    Some(key) => Ok(Value::Variable(key)),
+                    None => Err(self.error(
+                        ParseErrorKind::UnknownVariable(name.to_string()),
+                        start..self.pos,
+                    )),
+                }
+            }
+            Some(c) if c.is_ascii_digit() || c == '.' => self.number(),
+            Some(c) if c.is_ascii_alphabetic() => {
+                let name = self.ident().to_string();
+                match name.as_str() {
+                    "time" => return Ok(Value::Time),
+                    "zero" => return Ok(Value::Zero),
+                    _ => (),
+                }
+                self.skip_ws();
+                if self.peek() != Some('(') {
+                    return Err(self.error(ParseErrorKind::UnknownFunction(name), start..self.pos));
+                }
+                self.call(name, start)
+            }
+            Some(c) => Err(self.error_char(c)),
+        }
+    }
+
+    fn number(&mut self) -> Result<Value, ParseError> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit() || c == '.') {
+            self.bump();
+        }
+        let number: f32 = self.src[start..self.pos]
+            .parse()
+            .map_err(|_| self.error(ParseErrorKind::InvalidNumber, start..self.pos))?;
+
+        let unit_start = self.pos;
+        let rest = &self.src[self.pos..];
+        if rest.starts_with('%') && rest[1..].starts_with(|c: char| c.is_ascii_alphabetic()) {
+            self.bump();
+            let container = match self.ident() {
+                "c" | "container" => Container::Container,
+                "vp" | "viewport" => Container::ViewPort,
+                "this" => Container::This,
+                "img" | "image" => Container::Image,
+                "content" => Container::Content,
+                other => {
+                    return Err(self.error(
+                        ParseErrorKind::UnknownContainer(other.to_string()),
+                        unit_start + 1..self.pos,
+                    ))
+                }
+            };
+            self.expect('.')?;
+            let dim_start = self.pos;
+            let dimension = match self.ident() {
+                "w" | "width" => Values::Width,
+                "h" | "height" => Values::Height,
+                "d" | "diameter" => Values::Diameter,
+                "min" => Values::Min,
+                "max" => Values::Max,
+                "avg" => Values::Avg,
+                other => {
+                    return Err(self.error(
+                        ParseErrorKind::UnknownDimension(other.to_string()),
+                        dim_start..self.pos,
+                    ))
+                }
+            };
+            return Ok(Value::Value(container, dimension, Portion::Percent(number)));
+        }
+        if !rest.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            return Ok(Value::Px(number));
+        }
+        Ok(match self.ident() {
+            "px" => Value::Px(number),
+            "deg" => Value::Px(number.to_radians()),
+            "vw" => Value::vp_width(Portion::Percent(number)),
+            "vh" => Value::vp_height(Portion::Percent(number)),
+            other => {
+                return Err(self.error(
+                    ParseErrorKind::UnknownUnit(other.to_string()),
+                    unit_start..self.pos,
+                ))
+            }
+        })
+    }
+
+    fn call(&mut self, name: String, start: usize) -> Result<Value, ParseError> {
+        self.expect('(')?;
+        let condition = if name == "if" {
+            let cond = self.condition()?;
+            self.expect(',')?;
+            Some(cond)
+        } else {
+            None
+        };
+        let mut args = Vec::new();
+        self.skip_ws();
+        if self.peek() != Some(')') {
+            loop {
+                args.push(self.expr()?);
+                self.skip_ws();
+                if self.peek() == Some(',') {
+                    self.bump();
+                } else {
+                    break;
+                }
+            }
+        }
+        self.expect(')')?;
+        let span = start..self.pos;
+
+        let expected = match name.as_str() {
+            "abs" | "floor" | "round" | "sqrt" | "sin" | "cos" | "debug" => 1,
+            "min" | "max" | "pow" | "atan2" | "if" => 2,
+            "clamp" | "lerp" => 3,
+            _ => return Err(self.error(ParseErrorKind::UnknownFunction(name), span)),
+        };
+        if args.len() != expected {
+            return Err(self.error(
+                ParseErrorKind::Arguments {
+                    found: args.len() + condition.is_some() as usize,
+                    expected: expected + condition.is_some() as usize,
+                    function: name,
+                },
+                span,
+            ));
+        }
+        let mut args = args.into_iter();
+        let mut arg = || args.next().unwrap();
+        Ok(match name.as_str() {
+            "abs" => Value::abs(arg()),
+            "floor" => Value::floor(arg()),
+            "round" => Value::round(arg()),
+            "sqrt" => Value::sqrt(arg()),
+            "sin" => Value::sin(arg()),
+            "cos" => Value::cos(arg()),
+            "debug" => Value::debug(arg()),
+            "min" => Value::min(arg(), arg()),
+            "max" => Value::max(arg(), arg()),
+            "pow" => Value::pow(arg(), arg()),
+            "atan2" => Value::atan2(arg(), arg()),
+            "clamp" => Value::clamp(arg(), arg(), arg()),
+            "lerp" => Value::lerp(arg(), arg(), arg()),
+            _ => Value::if_else(condition.unwrap(), arg(), arg()),
+        })
+    }
+
+    fn condition(&mut self) -> Result<Condition, ParseError> {
+        let left = self.expr()?;
+        self.skip_ws();
+        let start = self.pos;
+        let rest = &self.src[self.pos..];
+        let (cmp, len) = if rest.starts_with("<=") {
+            (Compare::LessEq, 2)
+        } else if rest.starts_with(">=") {
+            (Compare::GreaterEq, 2)
+        } else if rest.starts_with("==") {
+            (Compare::Equal, 2)
+        } else if rest.starts_with("!=") {
+            (Compare::NotEqual, 2)
+        } else if rest.starts_with('<') {
+            (Compare::Less, 1)
+        } else if rest.starts_with('>') {
+            (Compare::Greater, 1)
+        } else {
+            return Err(self.error(ParseErrorKind::ExpectedComparison, start..start));
+        };
+        self.pos += len;
+        let right = self.expr()?;
+        Ok(Condition::new(left, cmp, right))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        element::Container as Cont,
+        styles::Containers,
+        variables::{Variable, Variables},
+        Vector,
+    };
+
+    #[test]
+    fn parse_and_eval() {
+        let container = Cont {
+            pos: Vector::ZERO,
+            size: Vector(800.0, 300.0),
+            rotation: 0.0,
+        };
+        let vp = Cont {
+            pos: Vector::ZERO,
+            size: Vector(1000.0, 500.0),
+            rotation: 0.0,
+        };
+        let containers = Containers {
+            container: &container,
+            vp: &vp,
+            this: &container,
+            image: &Vector::ZERO,
+            content: &Vector::ZERO,
+            time: 2.0,
+        };
+        let mut variables = Variables::default();
+        let gap = variables.push(Variable::new_const(6.0));
+        let mut eval = |src: &str| {
+            Value::parse(src, |name| (name == "gap").then_some(gap))
+                .unwrap()
+                .calc(&containers, &mut variables)
+        };
+
+        assert_eq!(eval("50%c.w - 20px"), 380.0);
+        assert_eq!(eval("10vw + 10vh"), 150.0);
+        assert_eq!(eval("2 + 3 * 4"), 14.0);
+        assert_eq!(eval("(2 + 3) * -4"), -20.0);
+        assert_eq!(eval("7 % 4 + $gap"), 9.0);
+        assert_eq!(eval("clamp(50%vp.w, 200px, 400)"), 400.0);
+        assert_eq!(eval("max(100%this.h, 100%c.min)"), 300.0);
+        assert_eq!(eval("time * 2"), 4.0);
+        assert_eq!(eval("if(100%c.w >= 100%c.h, 1, 2)"), 1.0);
+        assert_eq!(eval("lerp(0, 10, 0.5)"), 5.0);
+        assert!((eval("180deg") - std::f32::consts::PI).abs() < 1e-6);
+
+        let err = "50%c.w - 20pt".parse::<Value>().unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::UnknownUnit("pt".into()));
+        assert_eq!(err.span, 11..13);
+        let err = "min(1)".parse::<Value>().unwrap_err();
+        assert!(matches!(
+            err.kind,
+            ParseErrorKind::Arguments {
+                expected: 2,
+                found: 1,
+                ..
+            }
+        ));
+        assert_eq!(err.span, 0..6);
+        let err = "1 + $gap".parse::<Value>().unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::UnknownVariable("gap".into()));
+        assert_eq!(err.span, 4..8);
+        let err = "1 +".parse::<Value>().unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::UnexpectedEnd);
+        let err = "1 2".parse::<Value>().unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::UnexpectedChar('2'));
+        assert_eq!(err.span, 2..3);
+        let err = "if(1 > 0 5, 6)".parse::<Value>().unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::UnexpectedChar('5'));
+        assert_eq!(err.span, 9..10);
+    }
+}
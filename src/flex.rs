@@ -0,0 +1,200 @@
+use crate::{element::Container, FlexAlign, FlexDirection, FlexJustify, Vector};
+
+/// Child of a flex container as measured before the distribution
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct FlexItem {
+    /// Size resolved from `width` and `height` styles of the child, including its padding
+    pub base: Vector,
+    /// Size the item does not shrink below, the min-content of the child
+    pub min: Vector,
+    pub grow: f32,
+    pub shrink: f32,
+}
+
+/// Resolved parameters of `Flex`
+pub(crate) struct FlexParams {
+    pub direction: FlexDirection,
+    pub gap: f32,
+    pub justify: FlexJustify,
+    pub align: FlexAlign,
+    pub wrap: bool,
+}
+
+/// Computes the slot of every item inside of `container`
+///
+/// Slots are rotated together with the container, `out` is cleared first.
+pub(crate) fn slots(
+    params: &FlexParams,
+    container: &Container,
+    items: &[FlexItem],
+    out: &mut Vec<Container>,
+) {
+    out.clear();
+    let row = params.direction == FlexDirection::Row;
+    let main = |v: Vector| if row { v.0 } else { v.1 };
+    let cross = |v: Vector| if row { v.1 } else { v.0 };
+    let main_size = main(container.size);
+    let cross_size = cross(container.size);
+    let gap = params.gap;
+
+    // (start, end) item indices of every line
+    let mut lines = Vec::new();
+    let mut start = 0;
+    let mut used = 0.0;
+    for (i, item) in items.iter().enumerate() {
+        let size = main(item.base);
+        if params.wrap && i > start && used + gap + size > main_size {
+            lines.push((start, i));
+            start = i;
+            used = 0.0;
+        }
+        if i > start {
+            used += gap;
+        }
+        used += size;
+    }
+    if start < items.len() {
+        lines.push((start, items.len()));
+    }
+
+    let line_cross = |line: &[FlexItem]| {
+        if lines.len() == 1 {
+            cross_size
+        } else {
+            line.iter().map(|i| cross(i.base)).fold(0.0, f32::max)
+        }
+    };
+
+    let mut cross_pos = 0.0;
+    for &(start, end) in &lines {
+        let line = &items[start..end];
+        let line_cross = line_cross(line);
+
+        let base: f32 = line.iter().map(|i| main(i.base)).sum();
+        let mut free = main_size - base - gap * (line.len() - 1) as f32;
+        let mut sizes: Vec<f32> = line.iter().map(|i| main(i.base)).collect();
+        let grow: f32 = line.iter().map(|i| i.grow).sum();
+        if free > 0.0 && grow > 0.0 {
+            for (size, item) in sizes.iter_mut().zip(line) {
+                *size += free * item.grow / grow;
+            }
+            free = 0.0;
+        } else if free < 0.0 {
+            // items that reach their minimum are frozen, the others shrink in their place
+            let mut frozen = vec![false; line.len()];
+            loop {
+                let shrinking = line.iter().zip(&frozen).filter(|(_, f)| !**f);
+                let shrink: f32 = shrinking.map(|(i, _)| i.shrink * main(i.base)).sum();
+                if shrink <= 0.0 {
+                    break;
+                }
+                let used: f32 = sizes
+                    .iter()
+                    .zip(line)
+                    .zip(&frozen)
+                    .map(|((s, i), f)| if *f { *s } else { main(i.base) })
+                    .sum();
+                let free = main_size - used - gap * (line.len() - 1) as f32;
+                let mut clamped = false;
+                for ((size, item), frozen) in sizes.iter_mut().zip(line).zip(&mut frozen) {
+                    if *frozen {
+                        continue;
+                    }
+                    *size = main(item.base) + free * item.shrink * main(item.base) / shrink;
+                    if *size < main(item.min) {
+                        *size = main(item.min);
+                        *frozen = true;
+                        clamped = true;
+                    }
+                }
+                if !clamped {
+                    break;
+                }
+            }
+            free = main_size - sizes.iter().sum::<f32>() - gap * (line.len() - 1) as f32;
+        }
+        let free = free.max(0.0);
+
+        let n = line.len() as f32;
+        let (mut main_pos, spacing) = match params.justify {
+            FlexJustify::Start => (0.0, 0.0),
+            FlexJustify::Center => (free * 0.5, 0.0),
+            FlexJustify::End => (free, 0.0),
+            FlexJustify::SpaceBetween if line.len() > 1 => (0.0, free / (n - 1.0)),
+            FlexJustify::SpaceBetween => (0.0, 0.0),
+            FlexJustify::SpaceAround => (free / n * 0.5, free / n),
+            FlexJustify::SpaceEvenly => (free / (n + 1.0), free / (n + 1.0)),
+        };
+
+        for (size, item) in sizes.iter().zip(line) {
+            let (item_cross, offset) = match params.align {
+                FlexAlign::Stretch => (line_cross, 0.0),
+                FlexAlign::Start => (cross(item.base), 0.0),
+                FlexAlign::Center => (cross(item.base), (line_cross - cross(item.base)) * 0.5),
+                FlexAlign::End => (cross(item.base), line_cross - cross(item.base)),
+            };
+            let (pos, slot_size) = if row {
+                (
+                    Vector(main_pos + size * 0.5, cross_pos + offset + item_cross * 0.5),
+                    Vector(*size, item_cross),
+                )
+            } else {
+                (
+                    Vector(cross_pos + offset + item_cross * 0.5, main_pos + size * 0.5),
+                    Vector(item_cross, *size),
+                )
+            };
+            let local = pos - container.size * 0.5;
+            out.push(Container {
+                pos: container.pos + local.rotate_around_origin(container.rotation),
+                size: slot_size,
+                rotation: container.rotation,
+            });
+            main_pos += size + gap + spacing;
+        }
+        cross_pos += line_cross + gap;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(w: f32, h: f32, grow: f32) -> FlexItem {
+        FlexItem {
+            base: Vector(w, h),
+            min: Vector::ZERO,
+            grow,
+            shrink: 1.0,
+        }
+    }
+
+    #[test]
+    fn row_grow_and_wrap() {
+        let container = Container {
+            pos: Vector(50.0, 50.0),
+            size: Vector(100.0, 100.0),
+            rotation: 0.0,
+        };
+        let mut params = FlexParams {
+            direction: FlexDirection::Row,
+            gap: 10.0,
+            justify: FlexJustify::Start,
+            align: FlexAlign::Stretch,
+            wrap: false,
+        };
+        let mut out = Vec::new();
+
+        slots(&params, &container, &[item(20.0, 10.0, 0.0), item(20.0, 10.0, 1.0)], &mut out);
+        assert_eq!(out[0].size, Vector(20.0, 100.0));
+        assert_eq!(out[0].pos, Vector(10.0, 50.0));
+        assert_eq!(out[1].size, Vector(70.0, 100.0));
+        assert_eq!(out[1].pos, Vector(65.0, 50.0));
+
+        params.wrap = true;
+        params.align = FlexAlign::Start;
+        slots(&params, &container, &[item(60.0, 10.0, 0.0), item(60.0, 20.0, 0.0)], &mut out);
+        assert_eq!(out[0].pos, Vector(30.0, 5.0));
+        assert_eq!(out[1].pos, Vector(30.0, 30.0));
+    }
+}
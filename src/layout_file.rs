@@ -0,0 +1,709 @@
+//! Element trees described in RON or JSON documents
+//!
+//! A document is a single `LayoutNode`. Styles are written by their field
+//! names in `Styles`, listener messages by name and resolved by the caller.
+//! Values of sizes and procedures can also be written as expressions, see
+//! `expression`, as `Expr("50%c.w - 20px")` or in JSON as a plain string.
+//!
+//! ```ron
+//! (
+//!     label: Some("menu"),
+//!     styles: (color: FRgba(0.1, 0.1, 0.1, 1.0), layout: Flex((direction: Column, gap: Px(8.0), justify: Center, align: Center, wrap: false))),
+//!     children: [
+//!         (name: Some("start"), styles: (width: Px(200.0), height: Px(40.0), text: Label("Start")), events: [(event: Click, msg: Some("start"))]),
+//!     ],
+//! )
+//! ```
+
+use std::{
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    builder::{BuiltTree, ElementBuilder},
+    stylesheet::Cascade,
+    text::TextRepr,
+    Border, Colors, CornerShape, Corners, Dash, ElemEventTypes, Element, ElementKey, EventListener,
+    EventListeners, FontIdx, Gradient, Gui, ImageData, Layout, LineHeight, ListenerTypes, Outline,
+    Overflow, Position, Rotation, Shadow, Sides, Styles, TextAlign, TextWrap, Value,
+};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LayoutNode {
+    pub label: Option<String>,
+    /// Name of the element in `BuiltTree::handles`
+    pub name: Option<String>,
+    /// See `Element::add_class`
+    pub classes: Vec<String>,
+    pub styles: StylesDesc,
+    pub events: Vec<ListenerDesc>,
+    #[serde(deserialize_with = "expr::values")]
+    pub procedures: Vec<Value>,
+    pub children: Vec<LayoutNode>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ListenerDesc {
+    pub event: ElemEventTypes,
+    /// Name of the message, resolved when the document is applied
+    #[serde(default)]
+    pub msg: Option<String>,
+    #[serde(default = "ListenerDesc::default_kind")]
+    pub kind: ListenerTypes,
+}
+
+/// Values of the sides of an element, `all` is used for the sides that are not given
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SidesDesc {
+    #[serde(deserialize_with = "expr::value")]
+    pub all: Option<Value>,
+    #[serde(deserialize_with = "expr::value")]
+    pub top: Option<Value>,
+    #[serde(deserialize_with = "expr::value")]
+    pub right: Option<Value>,
+    #[serde(deserialize_with = "expr::value")]
+    pub bottom: Option<Value>,
+    #[serde(deserialize_with = "expr::value")]
+    pub left: Option<Value>,
+}
+
+/// Radii of the corners of an element, `all` is used for the corners that are not given
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CornersDesc {
+    #[serde(deserialize_with = "expr::value")]
+    pub all: Option<Value>,
+    #[serde(deserialize_with = "expr::value")]
+    pub top_left: Option<Value>,
+    #[serde(deserialize_with = "expr::value")]
+    pub top_right: Option<Value>,
+    #[serde(deserialize_with = "expr::value")]
+    pub bottom_right: Option<Value>,
+    #[serde(deserialize_with = "expr::value")]
+    pub bottom_left: Option<Value>,
+    pub shape: Option<CornerShape>,
+}
+
+/// Border of an element, `width` is given like `padding`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BorderDesc {
+    pub width: SidesDesc,
+    pub color: Option<Colors>,
+    pub gradient: Option<Gradient>,
+    pub dash: Option<Dash>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum TextDesc {
+    Label(String),
+    Paragraph(String),
+    Editor(String),
+}
+
+#[derive(Debug)]
+pub enum LayoutError {
+    Io(std::io::Error),
+    Ron(ron::error::SpannedError),
+    Json(serde_json::Error),
+    /// File extension is neither `ron` nor `json`
+    UnknownFormat,
+    /// Message name was not resolved
+    UnknownMessage(String),
+    /// Root key of the reloaded subtree does not point to a live element
+    NotFound,
+}
+
+macro_rules! styles_desc {
+    (
+        values { $($field: ident: $ty: ty),* $(,)? }
+        exprs { $($expr_field: ident),* $(,)? }
+        options { $($opt_field: ident: $opt_ty: ty),* $(,)? }
+        opt_exprs { $($opt_expr_field: ident),* $(,)? }
+        sides { $($sides_field: ident),* $(,)? }
+        corners { $($corners_field: ident),* $(,)? }
+        borders { $($border_field: ident),* $(,)? }
+    ) => {
+        /// Styles set by a `LayoutNode`, missing fields keep their defaults
+        #[derive(Debug, Clone, Default, Serialize, Deserialize)]
+        #[serde(default, deny_unknown_fields)]
+        pub struct StylesDesc {
+            $(pub $field: Option<$ty>,)*
+            $(#[serde(deserialize_with = "expr::value")]
+            pub $expr_field: Option<Value>,)*
+            $(pub $opt_field: Option<$opt_ty>,)*
+            $(#[serde(deserialize_with = "expr::value")]
+            pub $opt_expr_field: Option<Value>,)*
+            $(pub $sides_field: Option<SidesDesc>,)*
+            $(pub $corners_field: Option<CornersDesc>,)*
+            $(pub $border_field: Option<BorderDesc>,)*
+            pub text: Option<TextDesc>,
+        }
+
+        impl StylesDesc {
+            pub fn apply<Img: Clone + ImageData>(&self, styles: &mut Styles<Img>) {
+                $(if let Some(v) = &self.$field {
+                    styles.$field.set(v.clone());
+                })*
+                $(if let Some(v) = &self.$expr_field {
+                    styles.$expr_field.set(v.clone());
+                })*
+                $(if let Some(v) = &self.$opt_field {
+                    styles.$opt_field.set(Some(v.clone()));
+                })*
+                $(if let Some(v) = &self.$opt_expr_field {
+                    styles.$opt_expr_field.set(Some(v.clone()));
+                })*
+                $(if let Some(v) = &self.$sides_field {
+                    styles.$sides_field.set(v.to_sides());
+                })*
+                $(if let Some(v) = &self.$corners_field {
+                    styles.$corners_field.set(v.to_corners());
+                })*
+                $(if let Some(v) = &self.$border_field {
+                    styles.$border_field.set(v.to_border());
+                })*
+                if let Some(text) = &self.text {
+                    styles.text.set(Some(text.to_repr()));
+                }
+            }
+        }
+    };
+}
+
+styles_desc! {
+    values {
+        color: Colors,
+        rotation: Rotation,
+        shadow_alpha: f32,
+        shadows: Vec<Shadow>,
+        alpha: f32,
+        position: Position,
+        origin: Position,
+        image_tint: Colors,
+        overflow: Overflow,
+        font: FontIdx,
+        text_wrap: TextWrap,
+        line_height: LineHeight,
+        font_color: Colors,
+        text_align: TextAlign,
+        layout: Layout,
+        flex_grow: f32,
+        flex_shrink: f32,
+    }
+    exprs {
+        width,
+        height,
+        scroll_y,
+        scroll_x,
+        font_size,
+    }
+    options {
+        grad_linear: Gradient,
+        grad_radial: Gradient,
+        grad_conic: Gradient,
+        outline: Outline,
+    }
+    opt_exprs {
+        max_width,
+        min_width,
+        max_height,
+        min_height,
+        shadow,
+        text_box_width,
+        text_box_height,
+    }
+    sides {
+        padding,
+        margin,
+    }
+    corners {
+        round,
+    }
+    borders {
+        border,
+    }
+}
+
+impl ListenerDesc {
+    fn default_kind() -> ListenerTypes {
+        ListenerTypes::Listen
+    }
+
+    fn to_listener<Msg: Clone>(
+        &self,
+        msgs: &impl Fn(&str) -> Option<Msg>,
+    ) -> Result<EventListener<Msg>, LayoutError> {
+        let msg = match &self.msg {
+            Some(name) => {
+                Some(msgs(name).ok_or_else(|| LayoutError::UnknownMessage(name.clone()))?)
+            }
+            None => None,
+        };
+        Ok(EventListener {
+            event: self.event,
+            msg,
+            kind: self.kind,
+        })
+    }
+}
+
+impl SidesDesc {
+    fn to_sides(&self) -> Sides {
+        let side = |v: &Option<Value>| {
+            v.as_ref()
+                .or(self.all.as_ref())
+                .cloned()
+                .unwrap_or(Value::Zero)
+        };
+        Sides::new(
+            side(&self.top),
+            side(&self.right),
+            side(&self.bottom),
+            side(&self.left),
+        )
+    }
+}
+
+impl CornersDesc {
+    fn to_corners(&self) -> Corners {
+        let corner = |v: &Option<Value>| {
+            v.as_ref()
+                .or(self.all.as_ref())
+                .cloned()
+                .unwrap_or(Value::Zero)
+        };
+        Corners::new(
+            corner(&self.top_left),
+            corner(&self.top_right),
+            corner(&self.bottom_right),
+            corner(&self.bottom_left),
+        )
+        .with_shape(self.shape.unwrap_or_default())
+    }
+}
+
+impl BorderDesc {
+    fn to_border(&self) -> Border {
+        let mut border = Border::new(self.width.to_sides(), self.color.unwrap_or(Colors::BLACK));
+        border.gradient = self.gradient.clone();
+        border.dash = self.dash.clone();
+        border
+    }
+}
+
+impl TextDesc {
+    fn to_repr(&self) -> TextRepr {
+        match self {
+            TextDesc::Label(t) => TextRepr::new_label(t),
+            TextDesc::Paragraph(t) => TextRepr::new_paragraph(t),
+            TextDesc::Editor(t) => TextRepr::new_editor(t),
+        }
+    }
+}
+
+impl LayoutNode {
+    pub fn from_ron(src: &str) -> Result<Self, LayoutError> {
+        ron::from_str(src).map_err(LayoutError::Ron)
+    }
+
+    pub fn from_json(src: &str) -> Result<Self, LayoutError> {
+        serde_json::from_str(src).map_err(LayoutError::Json)
+    }
+
+    /// Reads a document, the format is picked by the file extension
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, LayoutError> {
+        let path = path.as_ref();
+        let src = std::fs::read_to_string(path).map_err(LayoutError::Io)?;
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("ron") => Self::from_ron(&src),
+            Some("json") => Self::from_json(&src),
+            _ => Err(LayoutError::UnknownFormat),
+        }
+    }
+
+    pub fn to_builder<Msg: Clone, Img: Clone + ImageData>(
+        &self,
+        msgs: &impl Fn(&str) -> Option<Msg>,
+    ) -> Result<ElementBuilder<Msg, Img>, LayoutError> {
+        let mut builder = ElementBuilder::from_element(self.to_element(msgs)?);
+        if let Some(name) = &self.name {
+            builder = builder.name(name);
+        }
+        for child in &self.children {
+            builder = builder.child(child.to_builder(msgs)?);
+        }
+        Ok(builder)
+    }
+
+    fn to_element<Msg: Clone, Img: Clone + ImageData>(
+        &self,
+        msgs: &impl Fn(&str) -> Option<Msg>,
+    ) -> Result<Element<Msg, Img>, LayoutError> {
+        let mut element = Element {
+            label: self.label.clone(),
+            classes: self.classes.clone(),
+            procedures: self.procedures.clone(),
+            ..Default::default()
+        };
+        self.styles.apply(element.styles_mut());
+        for listener in &self.events {
+            element.events.add(listener.to_listener(msgs)?);
+        }
+        Ok(element)
+    }
+
+    /// Checks that all messages of the subtree can be resolved
+    fn validate<Msg: Clone>(&self, msgs: &impl Fn(&str) -> Option<Msg>) -> Result<(), LayoutError> {
+        for listener in &self.events {
+            listener.to_listener(msgs)?;
+        }
+        self.children.iter().try_for_each(|c| c.validate(msgs))
+    }
+}
+
+impl<Msg: Clone, Img: Clone + ImageData> Gui<Msg, Img> {
+    /// Adds the elements of a document, the root is not attached to any parent
+    pub fn load_subtree(
+        &mut self,
+        node: &LayoutNode,
+        msgs: impl Fn(&str) -> Option<Msg>,
+    ) -> Result<BuiltTree, LayoutError> {
+        Ok(node.to_builder(&msgs)?.build(self))
+    }
+
+    /// Re-applies a changed document to a subtree created from it
+    ///
+    /// The root keeps its key. Children are matched to the nodes by their labels,
+    /// matched elements keep their keys and the state of their text editors,
+    /// the rest is removed or created anew. Nothing is changed if the document fails
+    /// to resolve.
+    pub fn reload_subtree(
+        &mut self,
+        root: ElementKey,
+        node: &LayoutNode,
+        msgs: impl Fn(&str) -> Option<Msg>,
+    ) -> Result<BuiltTree, LayoutError> {
+        if !self.contains(root) {
+            return Err(LayoutError::NotFound);
+        }
+        node.validate(&msgs)?;
+        let mut tree = BuiltTree {
+            root,
+            handles: Default::default(),
+        };
+        self.reload_element(root, node, &msgs, &mut tree)?;
+        Ok(tree)
+    }
+
+    fn reload_element(
+        &mut self,
+        key: ElementKey,
+        node: &LayoutNode,
+        msgs: &impl Fn(&str) -> Option<Msg>,
+        tree: &mut BuiltTree,
+    ) -> Result<(), LayoutError> {
+        self.touch(key);
+        let element = &mut self.elements[key.index()];
+        let mut styles = Styles::default();
+        node.styles.apply(&mut styles);
+        let old_text = element.styles.text.get();
+        if let (Some(TextDesc::Editor(_)), Some(old)) = (&node.styles.text, old_text) {
+            if old.variant.editor().is_some() {
+                styles.text.set(Some(old.clone()));
+            }
+        }
+        *element.styles_mut() = styles;
+        element.label = node.label.clone();
+        element.classes = node.classes.clone();
+        element.cascade = Cascade::default();
+        element.events = EventListeners::new();
+        for listener in &node.events {
+            element.events.add(listener.to_listener(msgs)?);
+        }
+        element.procedures = node.procedures.clone();
+        if let Some(name) = &node.name {
+            tree.handles.insert(name.clone(), key);
+        }
+
+        let mut old_children = element.children.take().unwrap_or_default();
+        let mut children = Vec::with_capacity(node.children.len());
+        for child in &node.children {
+            let matched = child.label.as_ref().and_then(|label| {
+                old_children.iter().position(|c| {
+                    self.get_element(*c)
+                        .is_some_and(|e| e.label.as_ref() == Some(label))
+                })
+            });
+            let child_key = match matched {
+                Some(i) => {
+                    let child_key = old_children.remove(i);
+                    self.reload_element(child_key, child, msgs, tree)?;
+                    child_key
+                }
+                None => {
+                    let built = child.to_builder(msgs)?.build(self);
+                    tree.handles.extend(built.handles);
+                    built.root
+                }
+            };
+            self.elements[child_key.index()].parent = Some(key);
+            children.push(child_key);
+        }
+        // still linked to `key`, removing them does not search the whole tree
+        for old in old_children {
+            self.remove_subtree(old);
+        }
+
+        let element = &mut self.elements[key.index()];
+        element.children = Some(children);
+        element.layout.dirty_tree = true;
+        self.tree_dirty = true;
+        Ok(())
+    }
+}
+
+/// Deserialization of values written either as `Value` or as an expression string
+mod expr {
+    use std::fmt;
+
+    use serde::{
+        de::{self, value::EnumAccessDeserializer, EnumAccess, IntoDeserializer, VariantAccess},
+        Deserialize, Deserializer,
+    };
+
+    use crate::Value;
+
+    pub fn value<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Value>, D::Error> {
+        Ok(Option::<ValueOrExpr>::deserialize(deserializer)?.map(|v| v.0))
+    }
+
+    pub fn values<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Value>, D::Error> {
+        Ok(Vec::<ValueOrExpr>::deserialize(deserializer)?
+            .into_iter()
+            .map(|v| v.0)
+            .collect())
+    }
+
+    struct ValueOrExpr(Value);
+
+    /// Variant name, JSON also reads plain strings as names of unit variants
+    struct Name(String);
+
+    impl<'de> Deserialize<'de> for Name {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            struct Visitor;
+            impl de::Visitor<'_> for Visitor {
+                type Value = Name;
+
+                fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    f.write_str("a variant name or an expression")
+                }
+
+                fn visit_str<E: de::Error>(self, v: &str) -> Result<Name, E> {
+                    Ok(Name(v.to_string()))
+                }
+            }
+            deserializer.deserialize_identifier(Visitor)
+        }
+    }
+
+    /// Hands the already read variant name to the derived `Value` deserializer
+    struct Replay<V> {
+        name: String,
+        variant: V,
+    }
+
+    impl<'de, V: VariantAccess<'de>> EnumAccess<'de> for Replay<V> {
+        type Error = V::Error;
+        type Variant = V;
+
+        fn variant_seed<S: de::DeserializeSeed<'de>>(
+            self,
+            seed: S,
+        ) -> Result<(S::Value, V), V::Error> {
+            Ok((seed.deserialize(self.name.into_deserializer())?, self.variant))
+        }
+    }
+
+    impl<'de> Deserialize<'de> for ValueOrExpr {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            struct Visitor;
+            impl<'de> de::Visitor<'de> for Visitor {
+                type Value = ValueOrExpr;
+
+                fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    f.write_str("a Value or an expression string")
+                }
+
+                fn visit_enum<A: EnumAccess<'de>>(self, data: A) -> Result<ValueOrExpr, A::Error> {
+                    let (Name(name), variant) = data.variant()?;
+                    if name == "Expr" {
+                        let src: String = variant.newtype_variant()?;
+                        return src.parse().map(ValueOrExpr).map_err(de::Error::custom);
+                    }
+                    // variants are capitalized, expressions never start with a capital
+                    if name.starts_with(|c: char| c.is_ascii_uppercase()) {
+                        let replay = EnumAccessDeserializer::new(Replay { name, variant });
+                        return Ok(ValueOrExpr(Value::deserialize(replay)?));
+                    }
+                    variant.unit_variant()?;
+                    name.parse().map(ValueOrExpr).map_err(de::Error::custom)
+                }
+            }
+            deserializer.deserialize_enum("Value", &[], Visitor)
+        }
+    }
+}
+
+/// Watches a document for changes by its modification time
+pub struct LayoutWatcher {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+}
+
+impl LayoutWatcher {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            modified: None,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Loads the document if it changed since the last successful poll
+    ///
+    /// The first poll always loads it.
+    pub fn poll(&mut self) -> Option<Result<LayoutNode, LayoutError>> {
+        let modified = match std::fs::metadata(&self.path).and_then(|m| m.modified()) {
+            Ok(m) => m,
+            Err(e) => return Some(Err(LayoutError::Io(e))),
+        };
+        if self.modified == Some(modified) {
+            return None;
+        }
+        let node = LayoutNode::load(&self.path);
+        if node.is_ok() {
+            self.modified = Some(modified);
+        }
+        Some(node)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZero;
+
+    use crate::{CornerShape, Gui, Value};
+
+    use super::{LayoutError, LayoutNode};
+
+    #[test]
+    fn reload_keeps_matching_keys() {
+        let mut gui: Gui<u32> = Gui::new((NonZero::new(800).unwrap(), NonZero::new(800).unwrap()));
+        let msgs = |name: &str| match name {
+            "start" => Some(1),
+            _ => None,
+        };
+        let doc = LayoutNode::from_ron(
+            r#"(children: [
+                (label: Some("input"), styles: (text: Some(Editor("hi")))),
+                (label: Some("old"), name: Some("old")),
+            ])"#,
+        )
+        .unwrap();
+        let tree = gui.load_subtree(&doc, msgs).unwrap();
+        let input = gui
+            .get_element(tree.root)
+            .unwrap()
+            .children
+            .as_ref()
+            .unwrap()[0];
+        let text = gui
+            .get_element_mut(input)
+            .unwrap()
+            .styles_mut()
+            .text
+            .get_mut();
+        text.as_mut().unwrap().insert_str("!");
+
+        let doc = LayoutNode::from_json(
+            r#"{"children": [
+                {"label": "input", "styles": {"text": {"Editor": "hi"}}},
+                {"name": "new", "events": [{"event": "Click", "msg": "start"}]}
+            ]}"#,
+        )
+        .unwrap();
+        let reloaded = gui.reload_subtree(tree.root, &doc, msgs).unwrap();
+        let children = gui
+            .get_element(tree.root)
+            .unwrap()
+            .children
+            .clone()
+            .unwrap();
+        assert_eq!(children[0], input);
+        assert_eq!(children[1], reloaded["new"]);
+        assert!(gui.get_element(tree["old"]).is_none());
+        let text = gui
+            .get_element(input)
+            .unwrap()
+            .styles()
+            .text
+            .get()
+            .as_ref()
+            .unwrap();
+        assert_eq!(text.text.text.to_string(), "!hi");
+
+        let bad = LayoutNode::from_json(r#"{"events": [{"event": "Click", "msg": "nope"}]}"#);
+        assert!(gui.reload_subtree(tree.root, &bad.unwrap(), msgs).is_err());
+    }
+
+    #[test]
+    fn expression_values() {
+        let doc = LayoutNode::from_ron(
+            r#"(styles: (width: Some(Expr("50%c.w - 20px")), height: Some(Px(40.0)), round: Some((all: Some(Expr("min(8, 10%this.h)")), top_left: Some(Px(0.0)), shape: Some(Squircle)))))"#,
+        )
+        .unwrap();
+        assert!(matches!(doc.styles.width, Some(Value::Sub(_))));
+        assert!(matches!(doc.styles.height, Some(Value::Px(h)) if h == 40.0));
+        let round = doc.styles.round.unwrap().to_corners();
+        assert!(matches!(round.bottom_right, Value::Min(_)));
+        assert!(matches!(round.top_left, Value::Px(r) if r == 0.0));
+        assert_eq!(round.shape, CornerShape::Squircle);
+
+        let doc = LayoutNode::from_ron(r#"(styles: (padding: Some((all: Some(Px(4.0)), left: Some(Expr("10%c.w"))))))"#)
+            .unwrap();
+        let padding = doc.styles.padding.unwrap().to_sides();
+        assert!(matches!(padding.top, Value::Px(p) if p == 4.0));
+        assert!(matches!(padding.left, Value::Value(..)));
+
+        let doc = LayoutNode::from_json(
+            r#"{"styles": {"border": {"width": {"all": "2", "top": "5%c.h"}, "color": {"FRgba": [1.0, 0.0, 0.0, 1.0]}}}}"#,
+        )
+        .unwrap();
+        let border = doc.styles.border.unwrap().to_border();
+        assert!(matches!(border.width.bottom, Value::Px(w) if w == 2.0));
+        assert!(matches!(border.width.top, Value::Value(..)));
+        assert!(border.dash.is_none());
+
+        let doc = LayoutNode::from_json(
+            r#"{"styles": {"width": "clamp(50%vp.w, 200, 600)", "height": {"Px": 40.0}}, "procedures": ["time", "Zero", {"Expr": "1 + 2"}]}"#,
+        )
+        .unwrap();
+        assert!(matches!(doc.styles.width, Some(Value::Clamp(_))));
+        assert!(matches!(doc.styles.height, Some(Value::Px(_))));
+        assert!(matches!(doc.procedures[..], [Value::Time, Value::Zero, Value::Add(_)]));
+
+        let err = LayoutNode::from_json(r#"{"styles": {"width": "50%c.q"}}"#).unwrap_err();
+        assert!(matches!(err, LayoutError::Json(_)));
+    }
+}
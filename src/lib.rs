@@ -1,25 +1,40 @@
-use std::{fmt::Debug, num::NonZero, path::PathBuf};
+use std::{collections::HashMap, fmt::Debug, num::NonZero, path::PathBuf};
 
+use animation::{BlendColor, GradientInstance};
 use colors::*;
 use element::{Container, *};
 use events::*;
 use math::*;
 use styles::*;
+use stylesheet::PseudoState;
 use text::{FontIdx, Rect, TextProccesor, TextSelection};
-use variables::Variables;
+use variables::{VarKey, Variables};
 
+pub mod animation;
+pub mod builder;
 pub mod colors;
+pub mod diagnostics;
 pub mod element;
 pub mod events;
+pub mod expression;
+mod flex;
+#[cfg(feature = "layout-file")]
+pub mod layout_file;
 pub mod math;
+#[cfg(feature = "serde")]
+pub mod snapshot;
 pub mod styles;
+pub mod stylesheet;
 pub mod text;
+pub mod timeline;
 pub mod variables;
 pub mod widgets;
 pub mod rich_text;
 
 pub struct Gui<Msg: Clone = (), Img: Clone + ImageData = ()> {
     elements: Vec<Element<Msg, Img>>,
+    slots: Vec<ElementSlot>,
+    free_slots: Vec<u32>,
     pub variables: Variables,
     viewport: ContainerWrapper,
     size: (u32, u32),
@@ -30,6 +45,47 @@ pub struct Gui<Msg: Clone = (), Img: Clone + ImageData = ()> {
     file_drop_hover: Option<PathBuf>,
     pub text_ctx: TextProccesor,
     pub update_time: f32,
+    /// `Gui::update` ran before, `update_time` holds the time of the last one
+    updated: bool,
+    /// Tree structure or event listeners changed since the last update
+    tree_dirty: bool,
+    dirty_buffer: Vec<ElementKey>,
+    /// Elements edited since the last `Gui::mark_dirty`, see `Gui::touch`
+    touched: Vec<ElementKey>,
+    /// Elements whose dependencies have to be checked on every update
+    watched: Vec<ElementKey>,
+    /// Elements whose styles read or are bound to the variable
+    subscribers: HashMap<VarKey, Vec<ElementKey>>,
+    /// `Variables::stamp` when the subscribers were last notified
+    notified: u64,
+    /// Content of an element that fits its content changed during the last pass
+    relayout: bool,
+    stylesheet: stylesheet::StyleSheet<Img>,
+    /// Stylesheet changed since the last update
+    restyle: bool,
+    /// Elements in the `PseudoState::Active` state
+    pressed: Vec<ElementKey>,
+    /// Element in the `PseudoState::Focus` state
+    focused: Option<ElementKey>,
+    timelines: Vec<Option<timeline::Timeline<Img>>>,
+    /// Failed variable accesses met during the layout, see `Gui::var_issues`
+    var_issues: Vec<diagnostics::VarIssue>,
+}
+
+/// Upper bound of layout passes in a single `Gui::update`,
+/// procedures may change variables that other elements depend on
+/// and content sizes are only known after the children are laid out
+const MAX_LAYOUT_PASSES: usize = 3;
+
+/// Shared inputs of the transform prologs of `Gui::layout_element`
+struct PrologContext<'a> {
+    container: &'a ContainerWrapper,
+    variables: &'a mut Variables,
+    vp: &'a Container,
+    time: f32,
+    image: &'a Vector,
+    /// Recalculate even if no style is dirty, dependencies changed
+    force: bool,
 }
 
 impl<Msg: Clone, Img: Clone + ImageData> Gui<Msg, Img> {
@@ -37,6 +93,8 @@ impl<Msg: Clone, Img: Clone + ImageData> Gui<Msg, Img> {
         let size = (size.0.get(), size.1.get());
         Self {
             elements: Vec::new(),
+            slots: Vec::new(),
+            free_slots: Vec::new(),
             variables: Variables::default(),
             viewport: ContainerWrapper::new_dirty(&Container {
                 pos: Vector::ZERO,
@@ -51,6 +109,20 @@ impl<Msg: Clone, Img: Clone + ImageData> Gui<Msg, Img> {
             file_drop_hover: None,
             text_ctx: TextProccesor::new(),
             update_time: 0.0,
+            updated: false,
+            tree_dirty: true,
+            dirty_buffer: Vec::new(),
+            touched: Vec::new(),
+            watched: Vec::new(),
+            subscribers: HashMap::new(),
+            notified: 0,
+            relayout: false,
+            stylesheet: stylesheet::StyleSheet::new(),
+            restyle: false,
+            pressed: Vec::new(),
+            focused: None,
+            timelines: Vec::new(),
+            var_issues: Vec::new(),
         }
     }
 
@@ -68,31 +140,59 @@ impl<Msg: Clone, Img: Clone + ImageData> Gui<Msg, Img> {
             None => return,
         };
 
-        let vp_copy = self.viewport;
-        let container = &vp_copy;
-        let vp = vp_copy.get();
+        // the first update has no earlier time to measure from
+        let delta = if self.updated {
+            (time - self.update_time).max(0.0)
+        } else {
+            0.0
+        };
+        self.advance_timelines(delta);
+        self.variables.step_springs(delta);
+        self.sync_focus();
+        self.link_children();
+        self.apply_stylesheet();
+
+        let mut vp_copy = self.viewport;
+        let mut time_changed = time != self.update_time;
+
+        for _ in 0..MAX_LAYOUT_PASSES {
+            let stamp = self.variables.stamp();
+            self.relayout = false;
+            self.mark_dirty(time_changed, vp_copy.is_dirty());
+            let vp = *vp_copy.get();
+            self.update_element(entry, &vp_copy, &vp, time);
+            if self.variables.stamp() == stamp && !self.relayout {
+                break;
+            }
+            time_changed = false;
+            vp_copy.clean();
+        }
 
-        self.selection.selectables.clear();
-        self.variables.prepare();
-        self.update_element(entry, container, vp, time);
+        if self.tree_dirty {
+            self.tree_dirty = false;
+            self.selection.selectables.clear();
+            self.collect_selectables(entry);
+        }
         self.selection.post_update();
 
         self.viewport.clean();
         self.update_time = time;
+        self.updated = true;
     }
 
     fn resize_prolog(
         element: &mut Element<Msg, Img>,
         element_container: &mut ContainerWrapper,
-        container: &ContainerWrapper,
-        container_transforms: &Container,
-        variables: &mut Variables,
-        vp: &Container,
-        time: f32,
-        image: &Vector,
+        ctx: &mut PrologContext,
     ) -> bool {
+        let container = ctx.container;
+        let variables = &mut *ctx.variables;
+        let (vp, time, image, force) = (ctx.vp, ctx.time, ctx.image, ctx.force);
+        let container_transforms = container.get();
+        let content = element.layout.content;
         let styles = &mut element.styles;
         let mut transform_update = false;
+        let mut sized = (false, false);
         /*println!("styles.width.is_dirty()
             || container.dirty_size()
             || styles.max_width.is_dirty()
@@ -109,8 +209,9 @@ impl<Msg: Clone, Img: Clone + ImageData> Gui<Msg, Img> {
             || styles.max_width.is_dirty()
             || styles.min_width.is_dirty()
             || styles.padding.is_dirty()
-            || true // FIXME
+            || force
         {
+            let started = styles.width.take_started();
             let width = styles.width.fix_dirty_force();
             let max = styles.max_width.fix_dirty_force();
             let min = styles.min_width.fix_dirty_force();
@@ -119,21 +220,28 @@ impl<Msg: Clone, Img: Clone + ImageData> Gui<Msg, Img> {
                 vp,
                 this: element_container.get(),
                 image,
+                content: &content,
                 time,
             };
 
-            let mut width = width.calc(containers, variables);
-            if let Some(max) = max {
-                width = width.min(max.calc(containers, variables));
-            }
-            if let Some(min) = min {
-                width = width.max(min.calc(containers, variables));
-            }
+            let width = if element.layout.flex_item {
+                // the slot is already sized by the parent
+                container_transforms.size.0
+            } else {
+                element.layout.transitions.resolve(
+                    Style::Width,
+                    started,
+                    element_container.get().size.0 + element.instance.padding.size().0,
+                    clamp_size(width, max, min, containers, variables),
+                    time,
+                )
+            };
 
             if element_container.get().size.0 != width {
                 element_container.size_mut().0 = width;
                 transform_update |= true;
             }
+            sized.0 = true;
         }
 
         if styles.height.is_dirty()
@@ -141,43 +249,68 @@ impl<Msg: Clone, Img: Clone + ImageData> Gui<Msg, Img> {
             || styles.max_height.is_dirty()
             || styles.min_height.is_dirty()
             || styles.padding.is_dirty()
-            || true // FIXME
+            || force
         {
             let containers = &Containers {
                 container: container_transforms,
                 vp,
                 this: element_container.get(),
                 image,
+                content: &content,
                 time,
             };
+            let started = styles.height.take_started();
             let style = styles.height.fix_dirty_force();
             let max = styles.max_height.fix_dirty_force();
             let min = styles.min_height.fix_dirty_force();
 
-            let mut height = style.calc(containers, variables);
-            if let Some(max) = max {
-                height = height.min(max.calc(containers, variables));
-            }
-            if let Some(min) = min {
-                height = height.max(min.calc(containers, variables));
-            }
+            let height = if element.layout.flex_item {
+                container_transforms.size.1
+            } else {
+                element.layout.transitions.resolve(
+                    Style::Height,
+                    started,
+                    element_container.get().size.1 + element.instance.padding.size().1,
+                    clamp_size(style, max, min, containers, variables),
+                    time,
+                )
+            };
 
             if element_container.get().size.1 != height {
                 element_container.size_mut().1 = height;
                 transform_update |= true;
             }
+            sized.1 = true;
         }
-        if element_container.dirty_size() || styles.padding.is_dirty() {
-            let size = element_container.get().size;
+        if element_container.dirty_size() || styles.padding.is_dirty() || force {
+            let mut size = element_container.get().size;
+            // sides that were not sized again still have the old padding subtracted
+            let old = element.instance.padding.size();
+            if !sized.0 {
+                size.0 += old.0;
+            }
+            if !sized.1 {
+                size.1 += old.1;
+            }
             let containers = &Containers {
                 container: container_transforms,
                 vp,
                 this: element_container.get(),
                 image,
+                content: &content,
                 time,
             };
+            let started = styles.padding.take_started();
             let padding = styles.padding.fix_dirty_force().calc(containers, variables);
-            element_container.set_size((size - padding).max(0.0));
+            let padding = element.layout.transitions.resolve(
+                Style::Padding,
+                started,
+                element.instance.padding,
+                padding,
+                time,
+            );
+            element_container.set_size((size - padding.size()).max(0.0));
+            transform_update |= element.instance.padding.offset() != padding.offset();
             element.instance.padding = padding;
         }
         transform_update
@@ -185,21 +318,25 @@ impl<Msg: Clone, Img: Clone + ImageData> Gui<Msg, Img> {
     fn position_prolog(
         element: &mut Element<Msg, Img>,
         element_container: &mut ContainerWrapper,
-        container: &ContainerWrapper,
-        variables: &mut Variables,
-        vp: &Container,
-        time: f32,
-        image: &Vector,
+        ctx: &mut PrologContext,
         mut transform_update: bool,
     ) -> bool {
+        let container = ctx.container;
+        let variables = &mut *ctx.variables;
+        let (vp, time, image, force) = (ctx.vp, ctx.time, ctx.image, ctx.force);
+        let content = element.layout.content;
         let styles = &mut element.styles;
         let container_transforms = container.get();
 
         if container.dirty_pos()
             || container.dirty_rotation()
             || container.dirty_size()
+            || element_container.dirty_size()
+            || transform_update
             || styles.origin.is_dirty()
             || styles.position.is_dirty()
+            || styles.rotation.is_dirty()
+            || force
         {
             element_container.set_pos(container_transforms.pos);
             let containers = &Containers {
@@ -207,13 +344,23 @@ impl<Msg: Clone, Img: Clone + ImageData> Gui<Msg, Img> {
                 vp,
                 this: element_container.get(),
                 image,
+                content: &content,
                 time,
             };
 
             let center = styles.position.get().calc(containers, variables);
             let align = styles.origin.get().calc_relative(containers, variables);
 
-            let result = center - align;
+            let started = styles.position.take_started().or(styles.origin.take_started());
+            // the displayed position is rotated around the container, start from the unrotated one
+            let result = element.layout.transitions.resolve(
+                Style::Center,
+                started,
+                element.layout.pos,
+                center - align + element.instance.padding.offset(),
+                time,
+            );
+            element.layout.pos = result;
             transform_update = element_container.get().pos != result;
             element_container.set_pos(result);
         }
@@ -223,18 +370,18 @@ impl<Msg: Clone, Img: Clone + ImageData> Gui<Msg, Img> {
     fn rotation_prolog(
         element: &mut Element<Msg, Img>,
         element_container: &mut ContainerWrapper,
-        container: &ContainerWrapper,
-        variables: &mut Variables,
-        vp: &Container,
-        time: f32,
-        image: &Vector,
+        ctx: &mut PrologContext,
         transform_update: bool,
     ) -> bool {
+        let container = ctx.container;
+        let variables = &mut *ctx.variables;
+        let (vp, time, image, force) = (ctx.vp, ctx.time, ctx.image, ctx.force);
         let mut rotated = false;
+        let content = element.layout.content;
         let styles = &mut element.styles;
         let container_transforms = container.get();
 
-        if transform_update || container.dirty_rotation() {
+        if transform_update || container.dirty_rotation() || styles.rotation.is_dirty() || force {
             let elem = element_container.get();
             if container_transforms.rotation != 0.0 && container_transforms.pos != elem.pos {
                 let pos = elem
@@ -242,15 +389,22 @@ impl<Msg: Clone, Img: Clone + ImageData> Gui<Msg, Img> {
                     .rotate_around_point(&container_transforms.pos, container_transforms.rotation);
                 element_container.set_pos(pos);
             };
-            if styles.rotation.is_dirty() || container.dirty_rotation() {
+            if styles.rotation.is_dirty() || container.dirty_rotation() || force {
                 let containers = &Containers {
                     container: container_transforms,
                     vp,
                     this: element_container.get(),
                     image,
+                    content: &content,
                     time,
                 };
-                let rot = styles.rotation.get().calc(containers, variables);
+                let rot = element.layout.transitions.resolve(
+                    Style::Rotation,
+                    styles.rotation.take_started(),
+                    element_container.get().rotation,
+                    styles.rotation.get().calc(containers, variables),
+                    time,
+                );
                 rotated = element_container.get().rotation != rot;
                 element_container.set_rotation(rot);
             }
@@ -258,6 +412,195 @@ impl<Msg: Clone, Img: Clone + ImageData> Gui<Msg, Img> {
         rotated
     }
 
+    /// Marks elements whose styles or dependencies changed and their ancestors
+    ///
+    /// Only the touched and the watched elements and the subscribers of changed variables
+    /// are checked.
+    fn mark_dirty(&mut self, time_changed: bool, vp_changed: bool) {
+        self.notify_subscribers();
+        let mut marked = std::mem::take(&mut self.dirty_buffer);
+        let mut touched = std::mem::take(&mut self.touched);
+        for key in touched.drain(..) {
+            if !self.contains(key) {
+                continue;
+            }
+            self.elements[key.index()].layout.touched = false;
+            if self.check_element(key, time_changed, vp_changed) {
+                marked.push(key);
+            }
+        }
+        self.touched = touched;
+
+        let mut watched = std::mem::take(&mut self.watched);
+        watched.retain(|k| self.contains(*k) && self.elements[k.index()].layout.watched);
+        for key in &watched {
+            if self.check_element(*key, time_changed, vp_changed) {
+                marked.push(*key);
+            }
+        }
+        watched.append(&mut self.watched);
+        self.watched = watched;
+
+        for key in marked.drain(..) {
+            let mut parent = self.elements[key.index()].parent;
+            while let Some(p) = parent {
+                if !self.contains(p) {
+                    break;
+                }
+                let element = &mut self.elements[p.index()];
+                if element.layout.dirty_descendant {
+                    break;
+                }
+                element.layout.dirty_descendant = true;
+                parent = element.parent;
+            }
+        }
+        self.dirty_buffer = marked;
+    }
+
+    /// Touches the elements that depend on variables changed since the last call
+    fn notify_subscribers(&mut self) {
+        if self.variables.stamp() == self.notified {
+            return;
+        }
+        let subscribers = std::mem::take(&mut self.subscribers);
+        for (var, keys) in &subscribers {
+            if !self.variables.changed_since(*var, self.notified) {
+                continue;
+            }
+            for key in keys {
+                if self.contains(*key) {
+                    self.touch(*key);
+                }
+            }
+        }
+        self.subscribers = subscribers;
+        self.notified = self.variables.stamp();
+    }
+
+    /// Updates the dependencies of the element, returns whether it has to be laid out again
+    fn check_element(&mut self, key: ElementKey, time_changed: bool, vp_changed: bool) -> bool {
+        let element = &mut self.elements[key.index()];
+        if element.styles.apply_bindings(&self.variables) {
+            element.dirty_styles = true;
+        }
+        let layout = &mut element.layout;
+        if element.dirty_styles {
+            let old = std::mem::take(&mut layout.deps);
+            element.styles.dependencies(&mut layout.deps);
+            for proc in &element.procedures {
+                proc.dependencies(&mut layout.deps);
+            }
+            for var in &old.variables {
+                if let Some(keys) = self.subscribers.get_mut(var) {
+                    keys.retain(|k| *k != key);
+                }
+            }
+            for var in &layout.deps.variables {
+                self.subscribers.entry(*var).or_default().push(key);
+            }
+            layout.fit_width = element.styles.fits_content_width();
+            layout.needs_update = true;
+            let watch = layout.deps.polled();
+            if watch && !layout.watched {
+                self.watched.push(key);
+            }
+            layout.watched = watch;
+        }
+        if layout
+            .deps
+            .changed(time_changed, vp_changed, &self.variables, layout.var_stamp)
+        {
+            layout.needs_update = true;
+            layout.force = true;
+        }
+        if layout.transitions.is_running() {
+            layout.needs_update = true;
+            layout.force = true;
+        }
+        if layout.dirty_tree {
+            layout.dirty_descendant = true;
+            self.tree_dirty = true;
+        }
+        if element.events.changed {
+            element.events.changed = false;
+            self.tree_dirty = true;
+        }
+        layout.needs_update || layout.dirty_descendant
+    }
+
+    /// Queues the element for the next `Gui::mark_dirty`
+    ///
+    /// Has to be called by everything that edits an element outside of the layout.
+    pub(crate) fn touch(&mut self, key: ElementKey) {
+        let layout = &mut self.elements[key.index()].layout;
+        if !layout.touched {
+            layout.touched = true;
+            self.touched.push(key);
+        }
+    }
+
+    /// Links the children edited through mutable access since the last update
+    ///
+    /// The children of touched elements are compared with the ones seen by the last call,
+    /// added children get their parent link, removed ones lose it.
+    fn link_children(&mut self) {
+        // linking touches the children, they are checked as well
+        let mut i = 0;
+        while i < self.touched.len() {
+            let key = self.touched[i];
+            i += 1;
+            if !self.contains(key) {
+                continue;
+            }
+            let element = &mut self.elements[key.index()];
+            let children = element.children.as_deref().unwrap_or(&[]);
+            if children == element.layout.children.as_slice() {
+                continue;
+            }
+            let children = children.to_vec();
+            let old = std::mem::replace(&mut element.layout.children, children.clone());
+            element.layout.dirty_tree = true;
+            for child in old {
+                if self.contains(child)
+                    && !children.contains(&child)
+                    && self.elements[child.index()].parent == Some(key)
+                {
+                    let child_e = &mut self.elements[child.index()];
+                    child_e.parent = None;
+                    child_e.cascade.restyle = true;
+                    self.touch(child);
+                }
+            }
+            for child in children {
+                if !self.contains(child) || self.elements[child.index()].parent == Some(key) {
+                    continue;
+                }
+                let child_e = &mut self.elements[child.index()];
+                child_e.parent = Some(key);
+                child_e.cascade.restyle = true;
+                child_e.layout.needs_update = true;
+                child_e.layout.force = true;
+                self.touch(child);
+            }
+        }
+    }
+
+    fn collect_selectables(&mut self, key: ElementKey) {
+        let element = &self.elements[key.index()];
+        if element.states.disabled {
+            return;
+        }
+        if !element.events.selection.is_empty() {
+            self.selection.selectables.push(key);
+        }
+        if let Some(children) = &element.children {
+            for child in children.clone() {
+                self.collect_selectables(child);
+            }
+        }
+    }
+
     fn update_element(
         &mut self,
         key: ElementKey,
@@ -265,18 +608,234 @@ impl<Msg: Clone, Img: Clone + ImageData> Gui<Msg, Img> {
         vp: &Container,
         time: f32,
     ) {
-        let variables = &mut self.variables;
-        let element = &mut self.elements[key.0 as usize];
-        let styles = &mut element.styles;
+        let element = &mut self.elements[key.index()];
+        let layout = &mut element.layout;
+        if layout.deps.changed(false, false, &self.variables, layout.var_stamp) {
+            layout.needs_update = true;
+            layout.force = true;
+        }
+        let laid_out = layout.needs_update || container.is_dirty();
+        let children_container = if laid_out {
+            let children_container = self.layout_element(key, container, vp, time);
+            if !self.variables.faults.is_empty() {
+                self.report_var_faults(key, &[]);
+            }
+            if self.elements[key.index()].layout.transitions.is_running() {
+                self.touch(key);
+            }
+            children_container
+        } else {
+            ContainerWrapper::new(&layout.children_container)
+        };
 
-        if element.events.selection.len() > 0 {
-            self.selection.selectables.push(key);
+        let element = &mut self.elements[key.index()];
+        let layout = &mut element.layout;
+        let descend = children_container.is_dirty() || layout.dirty_descendant;
+        layout.needs_update = false;
+        layout.force = false;
+        layout.dirty_descendant = false;
+        layout.dirty_tree = false;
+        if !descend {
+            if laid_out {
+                self.measure_content(key);
+            }
+            return;
+        }
+
+        if let Some(children) = element.children.take() {
+            let slots = match element.styles.layout.get() {
+                Layout::Flex(flex) => {
+                    let flex = flex.clone();
+                    Some(self.flex_slots(&flex, &children, children_container.get(), vp, time))
+                }
+                Layout::Free => None,
+            };
+            if !self.variables.faults.is_empty() {
+                self.report_var_faults(key, &children);
+            }
+            for (i, child) in children.iter().enumerate() {
+                let child_e = &mut self.elements[child.index()];
+                child_e.parent = Some(key);
+                let layout = &mut child_e.layout;
+                if layout.flex_item != slots.is_some() {
+                    layout.flex_item = slots.is_some();
+                    layout.needs_update = true;
+                    layout.force = true;
+                }
+                match &slots {
+                    Some(slots) => {
+                        let mut slot = ContainerWrapper::new(&layout.slot);
+                        slot.update(&slots[i]);
+                        layout.slot = slots[i];
+                        self.update_element(*child, &slot, vp, time);
+                    }
+                    None => self.update_element(*child, &children_container, vp, time),
+                }
+            }
+            self.elements[key.index()].children = Some(children);
+        }
+        self.measure_content(key);
+    }
+
+    /// Measures the extent of the text and children, see `styles::Container::Content`
+    ///
+    /// Elements that depend on their content are laid out again in the next pass
+    /// when it changes.
+    fn measure_content(&mut self, key: ElementKey) {
+        let element = &self.elements[key.index()];
+        let mut content = Vector::ZERO;
+        // the content is placed inside the padding, children also inside the border and margin
+        let inset = element.instance.padding.size();
+        let children_inset =
+            inset + (element.instance.border + element.instance.margin).size();
+        let mut min_content = Vector::ZERO;
+        if let Some(text) = element.styles.text.get() {
+            let size = text.text.size() + inset;
+            content = Vector(content.0.max(size.0), content.1.max(size.1));
+            // text fitting the width is not wrapped, it can not get any narrower
+            let min_width = if element.layout.fit_width {
+                size.0
+            } else {
+                text.text.min_width() + inset.0
+            };
+            min_content = Vector(min_width, size.1);
         }
+        if let Some(text) = element.styles.rich_text.get() {
+            text.with_shape(None, |shape, _, _| {
+                for line in &shape.lines {
+                    let b = &line.bounds;
+                    content.0 = content.0.max(b.left + b.width - shape.bounds.left + inset.0);
+                    content.1 = content.1.max(b.top + b.height - shape.bounds.top + inset.1);
+                }
+            });
+        }
+        if let Some(children) = &element.children {
+            let frame = &element.layout.children_container;
+            let mut bounds: Option<(Vector, Vector)> = None;
+            for child in children {
+                let instance = &self.elements[child.index()].instance;
+                let c = &instance.container;
+                // the padding of the child belongs to it
+                let pos = (c.pos - frame.pos).rotate_around_origin(-frame.rotation)
+                    - instance.padding.offset();
+                let size = c.size + instance.padding.size();
+                let (min, max) = (pos - size * 0.5, pos + size * 0.5);
+                bounds = Some(match bounds {
+                    Some((bmin, bmax)) => (
+                        Vector(bmin.0.min(min.0), bmin.1.min(min.1)),
+                        Vector(bmax.0.max(max.0), bmax.1.max(max.1)),
+                    ),
+                    None => (min, max),
+                });
+            }
+            if let Some((min, max)) = bounds {
+                let size = max - min + children_inset;
+                content = Vector(content.0.max(size.0), content.1.max(size.1));
+            }
+        }
+
+        let layout = &mut self.elements[key.index()].layout;
+        // flex items are measured by the parent before they are laid out
+        let resized = layout.flex_item && layout.min_content != min_content;
+        layout.min_content = min_content;
+        if layout.content != content || resized {
+            let depends = layout.deps.content || resized;
+            layout.content = content;
+            if depends {
+                layout.needs_update = true;
+                layout.force = true;
+                self.relayout = true;
+                self.touch(key);
+            }
+        }
+    }
+
+    /// Measures children of a flex container and assigns them their slots
+    fn flex_slots(
+        &mut self,
+        flex: &Flex,
+        children: &[ElementKey],
+        container: &Container,
+        vp: &Container,
+        time: f32,
+    ) -> Vec<Container> {
+        let variables = &mut self.variables;
+        let gap = flex.gap.calc(
+            &Containers {
+                container,
+                vp,
+                this: container,
+                image: &Vector::ZERO,
+                content: &Vector::ZERO,
+                time,
+            },
+            variables,
+        );
+        let items: Vec<_> = children
+            .iter()
+            .map(|child| {
+                let element = &self.elements[child.index()];
+                let styles = &element.styles;
+                let containers = &Containers {
+                    container,
+                    vp,
+                    this: &element.instance.container,
+                    image: &element.instance.image_size,
+                    content: &element.layout.content,
+                    time,
+                };
+                let width = clamp_size(
+                    styles.width.get(),
+                    styles.max_width.get(),
+                    styles.min_width.get(),
+                    containers,
+                    variables,
+                );
+                let height = clamp_size(
+                    styles.height.get(),
+                    styles.max_height.get(),
+                    styles.min_height.get(),
+                    containers,
+                    variables,
+                );
+                flex::FlexItem {
+                    base: Vector(width, height),
+                    min: element.layout.min_content,
+                    grow: *styles.flex_grow.get(),
+                    shrink: *styles.flex_shrink.get(),
+                }
+            })
+            .collect();
+        let params = flex::FlexParams {
+            direction: flex.direction,
+            gap,
+            justify: flex.justify,
+            align: flex.align,
+            wrap: flex.wrap,
+        };
+        let mut slots = Vec::with_capacity(items.len());
+        flex::slots(&params, container, &items, &mut slots);
+        slots
+    }
+
+    /// Lays out a single element, returns the container for its children
+    fn layout_element(
+        &mut self,
+        key: ElementKey,
+        container: &ContainerWrapper,
+        vp: &Container,
+        time: f32,
+    ) -> ContainerWrapper {
+        let variables = &mut self.variables;
+        let element = &mut self.elements[key.index()];
+        let force = element.layout.force;
+        let styles = &mut element.styles;
 
         let mut element_container = ContainerWrapper::new(&element.instance.container);
         let container_transforms = container.get();
 
         // --- CONTENT-CONTAINERS ---
+        let image_update = styles.image.is_dirty();
         if let Some(image_opt) = styles.image.fix_dirty() {
             match image_opt {
                 Some(image) => {
@@ -291,6 +850,7 @@ impl<Msg: Clone, Img: Clone + ImageData> Gui<Msg, Img> {
             }
         }
         let image = &element.instance.image_size.into();
+        let content = element.layout.content;
         // --- CONTENT-CONTAINERS ---
 
         let containers = &Containers {
@@ -298,6 +858,7 @@ impl<Msg: Clone, Img: Clone + ImageData> Gui<Msg, Img> {
             vp,
             this: element_container.get(),
             image,
+            content: &content,
             time,
         };
         for proc in &element.procedures {
@@ -308,45 +869,27 @@ impl<Msg: Clone, Img: Clone + ImageData> Gui<Msg, Img> {
         //
         // SIZE
         //
-        let mut transform_update = Self::resize_prolog(
-            element,
-            &mut element_container,
+        let ctx = &mut PrologContext {
             container,
-            container_transforms,
             variables,
             vp,
             time,
             image,
-        );
+            force,
+        };
+        let mut transform_update = Self::resize_prolog(element, &mut element_container, ctx);
 
         //
         // POSITION
         // - dependent on size
         //
-        transform_update |= Self::position_prolog(
-            element,
-            &mut element_container,
-            container,
-            variables,
-            vp,
-            time,
-            image,
-            transform_update,
-        );
+        transform_update |=
+            Self::position_prolog(element, &mut element_container, ctx, transform_update);
 
         //
         // ROTATION
         // - dependent on position
-        let rotated = Self::rotation_prolog(
-            element,
-            &mut element_container,
-            container,
-            variables,
-            vp,
-            time,
-            image,
-            transform_update,
-        );
+        let rotated = Self::rotation_prolog(element, &mut element_container, ctx, transform_update);
         //
         // --- TRANSFORMS ---
 
@@ -361,6 +904,7 @@ impl<Msg: Clone, Img: Clone + ImageData> Gui<Msg, Img> {
                     vp,
                     this: element_container_c,
                     image,
+                    content: &content,
                     time,
                 }
             };
@@ -368,8 +912,24 @@ impl<Msg: Clone, Img: Clone + ImageData> Gui<Msg, Img> {
         let containers = make_containers!();
 
         // --- TRANSFORM-DEPENDENT ---
+        if transform_update || image_update || force {
+            if let Some(image) = styles.image.get() {
+                let instance = &mut element.instance;
+                let (offset, size) = image.fit_into(instance.container.size);
+                instance.image_offset = offset;
+                instance.image_draw_size = size;
+                instance.image_uv = image.uv();
+                match image.slice {
+                    Some(slice) => {
+                        instance.image_slice = slice;
+                        instance.set_flag(Flags::NineSlice);
+                    }
+                    None => instance.remove_flag(Flags::NineSlice),
+                }
+            }
+        }
         let mut text_update = false;
-        if transform_update || styles.rich_text.is_dirty() {
+        if transform_update || styles.rich_text.is_dirty() || force {
             if let Some(text) = styles.rich_text.fix_dirty_force_mut() {
                 text.instance_data.align = text.styles.align.fix_dirty_force().calc();
                 text.instance_data.line_offset = text.styles.line_offset.fix_dirty_force_mut().calc();
@@ -390,44 +950,194 @@ impl<Msg: Clone, Img: Clone + ImageData> Gui<Msg, Img> {
                 text_update = true;
             }
         }
-        if transform_update || styles.round.is_dirty() {
-            if let Some(rnd) = styles.round.get() {
-                let size = rnd.calc(containers, variables);
-                element.instance.round = size;
+        if transform_update || styles.round.is_dirty() || force {
+            let started = styles.round.take_started();
+            let round = styles.round.fix_dirty_force().calc(containers, variables);
+            element.instance.round = element.layout.transitions.resolve(
+                Style::Round,
+                started,
+                element.instance.round,
+                round,
+                time,
+            );
+        }
+        if transform_update || styles.shadow.is_dirty() || styles.shadows.is_dirty() || force {
+            element.shadows.clear();
+            if let Some(size) = styles.shadow.fix_dirty_force() {
+                let size = size.calc(containers, variables).max(0.0);
+                element.shadows.push(ShadowInstance {
+                    offset: Vector::ZERO,
+                    blur: size,
+                    spread: size * 0.5,
+                    color: element.instance.legacy_shadow_color(),
+                    inset: false,
+                });
             }
+            let shadows = styles.shadows.fix_dirty_force();
+            element
+                .shadows
+                .extend(shadows.iter().map(|s| s.calc(containers, variables)));
         }
-        if transform_update || styles.shadow.is_dirty() {
-            if let Some(rnd) = styles.shadow.get() {
-                let size = rnd.calc(containers, variables);
-                element.instance.shadow = size;
+        if transform_update || styles.border.is_dirty() || rotated || force {
+            let started = styles.border.take_started();
+            let border = styles.border.fix_dirty_force();
+            let instance = &mut element.instance;
+            instance.border = border.width.calc(containers, variables);
+            instance.border_color = element
+                .layout
+                .transitions
+                .resolve(
+                    Style::Border,
+                    started,
+                    BlendColor(instance.border_color, border.color.space()),
+                    border.color.into(),
+                    time,
+                )
+                .0;
+            instance.border_dash = match &border.dash {
+                Some(dash) => Vector(
+                    dash.length.calc(containers, variables),
+                    dash.gap.calc(containers, variables),
+                ),
+                None => Vector::ZERO,
+            };
+            if let Some(grad) = &border.gradient {
+                instance.border_grad_p1 = grad.p1.0.calc_rot(containers, variables);
+                instance.border_grad_p2 = grad.p2.0.calc_rot(containers, variables);
+                instance.border_grad_color1 = grad.p1.1.into();
+                instance.border_grad_color2 = grad.p2.1.into();
+                instance.set_flag(Flags::BorderGradient);
+            } else {
+                instance.remove_flag(Flags::BorderGradient);
+            }
+            if instance.border.iter().any(|w| *w > 0.0) {
+                instance.set_flag(Flags::Border);
+            } else {
+                instance.remove_flag(Flags::Border);
+            }
+        }
+        if transform_update || styles.outline.is_dirty() || force {
+            if let Some(outline) = styles.outline.fix_dirty_force() {
+                let instance = &mut element.instance;
+                instance.outline_width = outline.width.calc(containers, variables);
+                instance.outline_offset = outline.offset.calc(containers, variables);
+                instance.outline_color = outline.color.into();
+                instance.set_flag(Flags::Outline);
+            } else {
+                element.instance.remove_flag(Flags::Outline);
             }
         }
-        if transform_update || styles.grad_linear.is_dirty() || rotated {
+        if transform_update || styles.grad_linear.is_dirty() || rotated || force {
+            let started = styles
+                .grad_linear
+                .take_started()
+                .filter(|_| Flags::LinearGradient.contained_in(element.instance.flags));
             if let Some(grad) = styles.grad_linear.fix_dirty_force() {
-                let p1 = grad.p1.0.calc_rot(containers, variables);
-                let p2 = grad.p2.0.calc_rot(containers, variables);
-                element.instance.lin_grad_p1 = p1;
-                element.instance.lin_grad_p2 = p2;
-                element.instance.lin_grad_color1 = grad.p1.1.into();
-                element.instance.lin_grad_color2 = grad.p2.1.into();
-                element.instance.set_flag(Flags::LinearGradient);
+                let instance = &mut element.instance;
+                let current = GradientInstance {
+                    p1: instance.lin_grad_p1,
+                    p2: instance.lin_grad_p2,
+                    color1: instance.lin_grad_color1,
+                    color2: instance.lin_grad_color2,
+                    space: grad.space,
+                };
+                let target = GradientInstance {
+                    p1: grad.p1.0.calc_rot(containers, variables),
+                    p2: grad.p2.0.calc_rot(containers, variables),
+                    color1: grad.p1.1.into(),
+                    color2: grad.p2.1.into(),
+                    space: grad.space,
+                };
+                let grad = element.layout.transitions.resolve(
+                    Style::GradLinear,
+                    started,
+                    current,
+                    target,
+                    time,
+                );
+                instance.lin_grad_p1 = grad.p1;
+                instance.lin_grad_p2 = grad.p2;
+                instance.lin_grad_color1 = grad.color1;
+                instance.lin_grad_color2 = grad.color2;
+                instance.set_flag(Flags::LinearGradient);
             } else {
                 element.instance.remove_flag(Flags::LinearGradient);
             }
         }
-        if transform_update || styles.grad_radial.is_dirty() || rotated {
+        if transform_update || styles.grad_radial.is_dirty() || rotated || force {
+            let started = styles
+                .grad_radial
+                .take_started()
+                .filter(|_| Flags::RadialGradient.contained_in(element.instance.flags));
             if let Some(grad) = styles.grad_radial.fix_dirty_force() {
-                let p1 = grad.p1.0.calc_rot(containers, variables);
-                let p2 = grad.p2.0.calc_rot(containers, variables);
-                element.instance.rad_grad_p1 = p1;
-                element.instance.rad_grad_p2 = p2;
-                element.instance.rad_grad_color1 = grad.p1.1.into();
-                element.instance.rad_grad_color2 = grad.p2.1.into();
-                element.instance.set_flag(Flags::RadialGradient);
+                let instance = &mut element.instance;
+                let current = GradientInstance {
+                    p1: instance.rad_grad_p1,
+                    p2: instance.rad_grad_p2,
+                    color1: instance.rad_grad_color1,
+                    color2: instance.rad_grad_color2,
+                    space: grad.space,
+                };
+                let target = GradientInstance {
+                    p1: grad.p1.0.calc_rot(containers, variables),
+                    p2: grad.p2.0.calc_rot(containers, variables),
+                    color1: grad.p1.1.into(),
+                    color2: grad.p2.1.into(),
+                    space: grad.space,
+                };
+                let grad = element.layout.transitions.resolve(
+                    Style::GradRadial,
+                    started,
+                    current,
+                    target,
+                    time,
+                );
+                instance.rad_grad_p1 = grad.p1;
+                instance.rad_grad_p2 = grad.p2;
+                instance.rad_grad_color1 = grad.color1;
+                instance.rad_grad_color2 = grad.color2;
+                instance.set_flag(Flags::RadialGradient);
             } else {
                 element.instance.remove_flag(Flags::RadialGradient);
             }
         }
+        if transform_update || styles.grad_conic.is_dirty() || rotated || force {
+            let started = styles
+                .grad_conic
+                .take_started()
+                .filter(|_| Flags::ConicGradient.contained_in(element.instance.flags));
+            if let Some(grad) = styles.grad_conic.fix_dirty_force() {
+                let instance = &mut element.instance;
+                let current = GradientInstance {
+                    p1: instance.con_grad_p1,
+                    p2: instance.con_grad_p2,
+                    color1: instance.con_grad_color1,
+                    color2: instance.con_grad_color2,
+                    space: grad.space,
+                };
+                let target = GradientInstance {
+                    p1: grad.p1.0.calc_rot(containers, variables),
+                    p2: grad.p2.0.calc_rot(containers, variables),
+                    color1: grad.p1.1.into(),
+                    color2: grad.p2.1.into(),
+                    space: grad.space,
+                };
+                let grad = element.layout.transitions.resolve(
+                    Style::GradConic,
+                    started,
+                    current,
+                    target,
+                    time,
+                );
+                instance.con_grad_p1 = grad.p1;
+                instance.con_grad_p2 = grad.p2;
+                instance.con_grad_color1 = grad.color1;
+                instance.con_grad_color2 = grad.color2;
+                instance.set_flag(Flags::ConicGradient);
+            } else {
+                element.instance.remove_flag(Flags::ConicGradient);
+            }
+        }
         //          --- TEXT-THINGS ---
         /*let mut text_update = false;
         if styles.text.get().is_some() {
@@ -461,18 +1171,47 @@ impl<Msg: Clone, Img: Clone + ImageData> Gui<Msg, Img> {
         // --- TRANSFORM-DEPENDENT ---
 
         // --- TRANSFORM-INDEPENDENT ---
-        if element.dirty_styles {
-            if let Some(tint) = styles.image_tint.fix_dirty() {
-                element.instance.image_tint = (*tint).into();
+        let animating = element.layout.transitions.is_running();
+        if element.dirty_styles || animating {
+            let transitions = &mut element.layout.transitions;
+            let instance = &mut element.instance;
+            if styles.image_tint.is_dirty() || animating {
+                let started = styles.image_tint.take_started();
+                let tint = *styles.image_tint.fix_dirty_force();
+                let current = BlendColor(instance.image_tint, tint.space());
+                instance.image_tint = transitions
+                    .resolve(Style::ImageTint, started, current, tint.into(), time)
+                    .0;
+            }
+            if styles.shadow_alpha.is_dirty() || animating {
+                let started = styles.shadow_alpha.take_started();
+                let alpha = *styles.shadow_alpha.fix_dirty_force();
+                instance.shadow_alpha = transitions.resolve(
+                    Style::ShadowAlpha,
+                    started,
+                    instance.shadow_alpha,
+                    alpha,
+                    time,
+                );
             }
-            if let Some(alpha) = styles.shadow_alpha.fix_dirty() {
-                element.instance.shadow_alpha = *alpha;
+            if styles.color.is_dirty() || animating {
+                let started = styles.color.take_started();
+                let color = *styles.color.fix_dirty_force();
+                let current = BlendColor(instance.color, color.space());
+                instance.color = transitions
+                    .resolve(Style::Color, started, current, color.into(), time)
+                    .0;
             }
-            if let Some(c) = styles.color.fix_dirty() {
-                element.instance.color = (*c).into()
+            if styles.shadow.get().is_some() {
+                if let Some(shadow) = element.shadows.first_mut() {
+                    shadow.color = instance.legacy_shadow_color();
+                }
             }
-            if let Some(a) = styles.alpha.fix_dirty() {
-                element.instance.alpha = *a
+            if styles.alpha.is_dirty() || animating {
+                let started = styles.alpha.take_started();
+                let alpha = *styles.alpha.fix_dirty_force();
+                instance.alpha =
+                    transitions.resolve(Style::Alpha, started, instance.alpha, alpha, time);
             }
             match styles.overflow.fix_dirty() {
                 Some(Overflow::Hidden) => element.instance.set_flag(Flags::OverflowHidden),
@@ -498,8 +1237,12 @@ impl<Msg: Clone, Img: Clone + ImageData> Gui<Msg, Img> {
         // --- EVENTS ---
 
         // --- PREPARE-NEXT-ELEMENTS ---
+        if transform_update || styles.margin.is_dirty() || force {
+            let margin = styles.margin.fix_dirty_force().calc(containers, variables);
+            element.instance.margin = margin;
+        }
         let mut dirty_scroll = false;
-        if transform_update || styles.scroll_y.is_dirty() {
+        if transform_update || styles.scroll_y.is_dirty() || force {
             let scroll = styles
                 .scroll_y
                 .fix_dirty_force()
@@ -507,7 +1250,7 @@ impl<Msg: Clone, Img: Clone + ImageData> Gui<Msg, Img> {
             dirty_scroll = element.instance.scroll.1 != scroll;
             element.instance.scroll.1 = scroll;
         }
-        if transform_update || styles.scroll_x.is_dirty() {
+        if transform_update || styles.scroll_x.is_dirty() || force {
             let containers = make_containers!();
             let scroll = styles
                 .scroll_x
@@ -523,31 +1266,47 @@ impl<Msg: Clone, Img: Clone + ImageData> Gui<Msg, Img> {
             || text_update
             || dirty_scroll
             || styles.text.is_dirty()
+            || styles.text_box_width.is_dirty()
+            || styles.text_box_height.is_dirty()
         {
+            let containers = make_containers!();
+            let box_width = styles
+                .text_box_width
+                .fix_dirty_force()
+                .as_ref()
+                .map(|w| w.calc(containers, variables));
+            let box_height = styles
+                .text_box_height
+                .fix_dirty_force()
+                .as_ref()
+                .map(|h| h.calc(containers, variables));
+            let text_box = Vector(
+                box_width.unwrap_or(element_container_c.size.0),
+                box_height.unwrap_or(element_container_c.size.1),
+            );
+            // text of elements that fit their content defines the width,
+            // it can not wrap at it
+            let wrap =
+                element.instance.text_wrap && (box_width.is_some() || !element.layout.fit_width);
             if let Some(text) = styles.text.fix_dirty_force_mut() {
                 let bounds = Rect::new(
-                    -element_container_c.size.0 * 0.5,
-                    -element_container_c.size.1 * 0.5,
-                    element_container_c.size.0,
-                    element_container_c.size.1,
+                    -text_box.0 * 0.5,
+                    -text_box.1 * 0.5,
+                    text_box.0,
+                    text_box.1,
                 );
                 self.text_ctx.procces(
                     FontIdx(element.instance.font),
                     &mut text.text,
                     element.instance.font_size,
                     bounds,
-                    element.instance.text_wrap,
+                    wrap,
                     element.instance.text_align,
                     element.instance.scroll,
                 );
             }
             if let Some(text) = styles.rich_text.fix_dirty_force_mut() {
-                let bounds = Rect::new(
-                    0.0,
-                    0.0,
-                    element_container_c.size.0,
-                    element_container_c.size.1,
-                );
+                let bounds = Rect::new(0.0, 0.0, text_box.0, text_box.1);
                 text.procces(&mut self.text_ctx, None, bounds);
             }
 
@@ -563,22 +1322,64 @@ impl<Msg: Clone, Img: Clone + ImageData> Gui<Msg, Img> {
         }
         // --- PREPARE-NEXT-ELEMENTS ---
 
-        assert!(styles.text_box_width.get().is_none());
-        assert!(styles.text_box_height.get().is_none());
-
-        if let Some(children) = element.children.take() {
-            for child in &children {
-                self.update_element(*child, &element_container, vp, time);
+        let transitions = &mut element.layout.transitions;
+        transitions.ready = true;
+        for style in transitions.finished.drain(..) {
+            for listener in &element.events.transition_end {
+                self.events.push(ElemEvent {
+                    kind: ElemEvents::TransitionEnd { style },
+                    element_key: key,
+                    msg: listener.msg.clone(),
+                });
             }
-            self.elements[key.0 as usize].children = Some(children);
         }
+
+        element.layout.var_stamp = variables.stamp();
+        let mut children_container = ContainerWrapper::new(&element.layout.children_container);
+        let inset = element.instance.border + element.instance.margin;
+        children_container.update(&element_container.get().inset(&inset));
+        element.layout.children_container = *children_container.get();
+        children_container
     }
 
     pub fn env_event(&mut self, event: EnvEvents) -> EnvEventStates {
+        if let EnvEvents::MouseButton { press: false, .. } = event {
+            for key in std::mem::take(&mut self.pressed) {
+                if let Some(element) = self.get_element_mut(key) {
+                    element.set_state(PseudoState::Active, false);
+                }
+            }
+        }
+        let state = self.handle_env_event(event);
+        self.sync_focus();
+        state
+    }
+
+    /// Moves `PseudoState::Focus` to `Selection::current`, disabled elements lose the focus
+    fn sync_focus(&mut self) {
+        let current = self
+            .selection
+            .current
+            .filter(|k| self.get_element(*k).is_some_and(|e| !e.states.disabled));
+        self.selection.current = current;
+        if current == self.focused {
+            return;
+        }
+        if let Some(element) = self.focused.and_then(|k| self.get_element_mut(k)) {
+            element.set_state(PseudoState::Focus, false);
+        }
+        if let Some(element) = current.and_then(|k| self.get_element_mut(k)) {
+            element.set_state(PseudoState::Focus, true);
+        }
+        self.focused = current;
+    }
+
+    fn handle_env_event(&mut self, event: EnvEvents) -> EnvEventStates {
         match &event {
             EnvEvents::Input { text } => {
                 if let Some(key) = self.selection.current {
-                    if let Some(e) = self.elements.get(key.raw() as usize) {
+                    if self.contains(key) {
+                        let e = &self.elements[key.index()];
                         for e in &e.events.text_input {
                             self.events.push(ElemEvent {
                                 kind: ElemEvents::TextInput { text: text.clone() },
@@ -594,7 +1395,7 @@ impl<Msg: Clone, Img: Clone + ImageData> Gui<Msg, Img> {
                 self.cursor.down = *press;
                 /*match (self.selection.current, *press) {
                     (Some(key), true) => {
-                        let elem = &mut self.elements[key.raw() as usize];
+                        let elem = &mut self.elements[key.index()];
                         if let Some(TextRepr {
                             paragraph: Some(paragraph),
                             text,
@@ -624,7 +1425,7 @@ impl<Msg: Clone, Img: Clone + ImageData> Gui<Msg, Img> {
                 self.cursor.current = *pos;
                 match (self.selection.current, self.cursor.down) {
                     (Some(key), true) => {
-                        let elem = &mut self.elements[key.raw() as usize];
+                        let elem = &mut self.elements[key.index()];
                         if let Some(text) = elem.styles.text.get_mut() {
                             if let Some(Some(selection)) = text.variant.selection_mut() {
                                 if let (true, pos) = self
@@ -664,7 +1465,8 @@ impl<Msg: Clone, Img: Clone + ImageData> Gui<Msg, Img> {
                             return EnvEventStates::Free;
                         }
                         if let Some(key) = self.selection.current {
-                            let element = &mut self.elements[key.raw() as usize];
+                            self.touch(key);
+                            let element = &mut self.elements[key.index()];
                             if let Some(text) = element.styles_mut().text.get_mut() {
                                 if let Some(selection) = text.variant.selection_mut() {
                                     *selection = None;
@@ -681,7 +1483,7 @@ impl<Msg: Clone, Img: Clone + ImageData> Gui<Msg, Img> {
                             }
                         }
                         if let Some(key) = self.selection.next() {
-                            let element = &self.elements[key.raw() as usize];
+                            let element = &self.elements[key.index()];
                             for listener in &element.events.selection {
                                 self.events.push(ElemEvent {
                                     kind: ElemEvents::Selection {
@@ -698,7 +1500,8 @@ impl<Msg: Clone, Img: Clone + ImageData> Gui<Msg, Img> {
                             return EnvEventStates::Free;
                         }
                         if let Some(key) = self.selection.current {
-                            let element = &mut self.elements[key.raw() as usize];
+                            self.touch(key);
+                            let element = &mut self.elements[key.index()];
                             if let Some(text) = element.styles_mut().text.get_mut() {
                                 if let Some(selection) = text.variant.selection_mut() {
                                     *selection = None;
@@ -715,7 +1518,7 @@ impl<Msg: Clone, Img: Clone + ImageData> Gui<Msg, Img> {
                             }
                         }
                         if let Some(key) = self.selection.prev() {
-                            let element = &self.elements[key.raw() as usize];
+                            let element = &self.elements[key.index()];
                             for listener in &element.events.selection {
                                 self.events.push(ElemEvent {
                                     kind: ElemEvents::Selection {
@@ -729,7 +1532,7 @@ impl<Msg: Clone, Img: Clone + ImageData> Gui<Msg, Img> {
                     }
                     SelectOpts::Confirm => {
                         if let Some(key) = self.selection.current {
-                            let element = &self.elements[key.raw() as usize];
+                            let element = &self.elements[key.index()];
                             for listener in &element.events.selection {
                                 self.events.push(ElemEvent {
                                     kind: ElemEvents::Selection {
@@ -750,7 +1553,7 @@ impl<Msg: Clone, Img: Clone + ImageData> Gui<Msg, Img> {
                             self.selection.select_element(*key)
                         };
                         if let Some(element_key) = selected_key {
-                            let element = &self.elements[element_key.raw() as usize];
+                            let element = &self.elements[element_key.index()];
                             for listener in &element.events.selection {
                                 self.events.push(ElemEvent {
                                     kind: ElemEvents::Selection {
@@ -762,7 +1565,8 @@ impl<Msg: Clone, Img: Clone + ImageData> Gui<Msg, Img> {
                             }
                         }
                         if let Some(element_key) = prev_key {
-                            let element = &mut self.elements[element_key.raw() as usize];
+                            self.touch(element_key);
+                            let element = &mut self.elements[element_key.index()];
                             if let Some(text) = element.styles_mut().text.get_mut() {
                                 if let Some(selection) = text.variant.selection_mut() {
                                     *selection = None;
@@ -781,7 +1585,8 @@ impl<Msg: Clone, Img: Clone + ImageData> Gui<Msg, Img> {
                     }
                     SelectOpts::NoFocus => {
                         if let Some(element_key) = self.selection.current {
-                            let element = &mut self.elements[element_key.raw() as usize];
+                            self.touch(element_key);
+                            let element = &mut self.elements[element_key.index()];
                             if let Some(text) = element.styles_mut().text.get_mut() {
                                 if let Some(selection) = text.variant.selection_mut() {
                                     *selection = None;
@@ -804,7 +1609,7 @@ impl<Msg: Clone, Img: Clone + ImageData> Gui<Msg, Img> {
             }
             EnvEvents::Copy => {
                 if let Some(key) = &self.selection.current {
-                    let elem = &self.elements[key.raw() as usize];
+                    let elem = &self.elements[key.index()];
                     if let Some(text) = elem.styles().text.get() {
                         if let Some(Some(selection)) = text.variant.selection() {
                             match text
@@ -840,42 +1645,27 @@ impl<Msg: Clone, Img: Clone + ImageData> Gui<Msg, Img> {
         state: &mut EnvEventStates,
     ) -> EventCache {
         let mut cache = EventCache::new();
-        let elem = &mut self.elements[key.0 as usize];
+        let elem = &mut self.elements[key.index()];
+        if elem.states.disabled {
+            return cache;
+        }
 
         if *elem.styles.overflow.get() == Overflow::Hidden {
             match &event {
                 EnvEvents::MouseButton { .. } => {
-                    if self
-                        .cursor
-                        .current
-                        .container_colision(&elem.instance.container)
-                        .is_none()
-                    {
+                    if !elem.instance.clip_hit(self.cursor.current) {
                         return cache;
                     }
                 }
                 EnvEvents::CursorMove { .. } => {
-                    if self
-                        .cursor
-                        .current
-                        .container_colision(&elem.instance.container)
-                        .is_none()
-                        && self
-                            .cursor
-                            .last
-                            .container_colision(&elem.instance.container)
-                            .is_none()
+                    if !elem.instance.clip_hit(self.cursor.current)
+                        && !elem.instance.clip_hit(self.cursor.last)
                     {
                         return cache;
                     }
                 }
                 EnvEvents::Scroll { .. } => {
-                    if self
-                        .cursor
-                        .current
-                        .container_colision(&elem.instance.container)
-                        .is_none()
-                    {
+                    if !elem.instance.clip_hit(self.cursor.current) {
                         return cache;
                     }
                 }
@@ -887,11 +1677,11 @@ impl<Msg: Clone, Img: Clone + ImageData> Gui<Msg, Img> {
             for key in children.iter().rev() {
                 cache.merge(&self.elem_env_event(*key, event, state));
             }
-            let elem = &mut self.elements[key.0 as usize];
+            let elem = &mut self.elements[key.index()];
             elem.children = Some(children);
         }
 
-        let elem = &self.elements[key.0 as usize];
+        let elem = &self.elements[key.index()];
 
         macro_rules! listener_fit {
             ($listener: expr) => {
@@ -906,13 +1696,15 @@ impl<Msg: Clone, Img: Clone + ImageData> Gui<Msg, Img> {
 
         match event {
             EnvEvents::MouseButton { button, press } => {
-                let (col, pos) = self
-                    .cursor
-                    .current
-                    .container_colision_with_pos(&elem.instance.container);
+                let (col, pos) = elem.instance.hit(self.cursor.current);
                 cache.current_over |= col;
                 if cache.current_over {
                     if *press {
+                        self.pressed.push(key);
+                        if self.elements[key.index()].set_state(PseudoState::Active, true) {
+                            self.touch(key);
+                        }
+                        let elem = &self.elements[key.index()];
                         if let Some(text) = elem.styles.text.get() {
                             if text.variant.selection().is_some() {
                                 self.env_event(EnvEvents::Select {
@@ -920,7 +1712,7 @@ impl<Msg: Clone, Img: Clone + ImageData> Gui<Msg, Img> {
                                 });
                             }
                         }
-                        let elem = &mut self.elements[key.0 as usize];
+                        let elem = &mut self.elements[key.index()];
                         if let Some(text) = elem.styles.text.get_mut() {
                             if let Some(selection) = text.variant.selection_mut() {
                                 if let (true, pos) = self
@@ -943,7 +1735,7 @@ impl<Msg: Clone, Img: Clone + ImageData> Gui<Msg, Img> {
                             }
                         }
                     }
-                    let elem = &self.elements[key.0 as usize];
+                    let elem = &self.elements[key.index()];
                     for listener in &elem.events.click {
                         listener_fit!(listener);
                         self.events.push(ElemEvent {
@@ -960,10 +1752,7 @@ impl<Msg: Clone, Img: Clone + ImageData> Gui<Msg, Img> {
                 }
             }
             EnvEvents::Scroll { delta } => {
-                let (col, pos) = self
-                    .cursor
-                    .current
-                    .container_colision_with_pos(&elem.instance.container);
+                let (col, pos) = elem.instance.hit(self.cursor.current);
                 cache.current_over |= col;
                 if cache.current_over {
                     for listener in &elem.events.scroll {
@@ -981,10 +1770,7 @@ impl<Msg: Clone, Img: Clone + ImageData> Gui<Msg, Img> {
                 if *opt != FileDropOpts::Drop {
                     return cache;
                 }
-                let (col, pos) = self
-                    .cursor
-                    .current
-                    .container_colision_with_pos(&elem.instance.container);
+                let (col, pos) = elem.instance.hit(self.cursor.current);
                 cache.current_over |= col;
                 let path = match path {
                     Some(path) => path,
@@ -1006,15 +1792,9 @@ impl<Msg: Clone, Img: Clone + ImageData> Gui<Msg, Img> {
                 }
             }
             EnvEvents::CursorMove { pos: _ } => {
-                let (col, pos) = self
-                    .cursor
-                    .current
-                    .container_colision_with_pos(&elem.instance.container);
+                let (col, pos) = elem.instance.hit(self.cursor.current);
                 cache.current_over |= col;
-                let (col, prev_pos) = self
-                    .cursor
-                    .last
-                    .container_colision_with_pos(&elem.instance.container);
+                let (col, prev_pos) = elem.instance.hit(self.cursor.last);
                 cache.last_over |= col;
                 match (cache.current_over, cache.last_over) {
                     (true, true) => {
@@ -1078,6 +1858,9 @@ impl<Msg: Clone, Img: Clone + ImageData> Gui<Msg, Img> {
                     }
                     _ => (),
                 }
+                if self.elements[key.index()].set_state(PseudoState::Hover, cache.current_over) {
+                    self.touch(key);
+                }
             }
             EnvEvents::KeyPress {
                 key: key_key,
@@ -1105,7 +1888,7 @@ impl<Msg: Clone, Img: Clone + ImageData> Gui<Msg, Img> {
 
     pub fn copy_selection_text(&self) -> Option<String> {
         if let Some(key) = &self.selection.current {
-            let elem = &self.elements[key.raw() as usize];
+            let elem = &self.elements[key.index()];
             if let Some(text) = elem.styles().text.get() {
                 if let Some(Some(selection)) = text.variant.selection() {
                     text.text
@@ -1142,7 +1925,8 @@ impl<Msg: Clone, Img: Clone + ImageData> Gui<Msg, Img> {
                 None => return,
             },
         };
-        let e = &mut self.elements[k.raw() as usize];
+        self.touch(k);
+        let e = &mut self.elements[k.index()];
         cb(e, k, depth);
         let children = match e.children.take() {
             Some(children) => children,
@@ -1151,7 +1935,7 @@ impl<Msg: Clone, Img: Clone + ImageData> Gui<Msg, Img> {
         for child in &children {
             self.foreach_element_mut(cb, Some(*child), depth + 1);
         }
-        self.get_element_mut(k).expect("Unexpected :)").children = Some(children);
+        self.elements[k.index()].children = Some(children);
     }
 
     pub fn foreach_element_mut_two_sided(
@@ -1168,7 +1952,8 @@ impl<Msg: Clone, Img: Clone + ImageData> Gui<Msg, Img> {
                 None => return,
             },
         };
-        let e = &mut self.elements[k.raw() as usize];
+        self.touch(k);
+        let e = &mut self.elements[k.index()];
         left(e, k, depth, e.children.is_some());
         let children = match e.children.take() {
             Some(children) => children,
@@ -1177,8 +1962,8 @@ impl<Msg: Clone, Img: Clone + ImageData> Gui<Msg, Img> {
         for child in &children {
             self.foreach_element_mut_two_sided(left, right, Some(*child), depth + 1);
         }
-        self.get_element_mut(k).expect("Unexpected :)").children = Some(children);
-        let e = &mut self.elements[k.raw() as usize];
+        self.elements[k.index()].children = Some(children);
+        let e = &mut self.elements[k.index()];
         right(e, k, depth);
     }
 
@@ -1195,7 +1980,7 @@ impl<Msg: Clone, Img: Clone + ImageData> Gui<Msg, Img> {
                 None => return,
             },
         };
-        let e = &self.elements[k.raw() as usize];
+        let e = &self.elements[k.index()];
         cb(e, k, depth);
         let children = match e.children.clone() {
             Some(children) => children,
@@ -1219,7 +2004,7 @@ impl<Msg: Clone, Img: Clone + ImageData> Gui<Msg, Img> {
             },
         };
 
-        let elem = &self.elements[root.0 as usize];
+        let elem = &self.elements[root.index()];
 
         match &elem.children {
             Some(c) => {
@@ -1249,22 +2034,275 @@ impl<Msg: Clone, Img: Clone + ImageData> Gui<Msg, Img> {
     }
 
     pub fn add_element(&mut self, element: Element<Msg, Img>) -> ElementKey {
-        let key = ElementKey(self.elements.len() as u64);
-        self.elements.push(element);
+        self.tree_dirty = true;
+        let key = self.insert_element(element);
+        self.elements[key.index()].layout.dirty_tree = true;
+        self.touch(key);
+        if let Some(children) = self.elements[key.index()].children.clone() {
+            for child in children {
+                if let Some(child) = self.get_element_mut(child) {
+                    child.parent = Some(key);
+                }
+            }
+        }
         key
     }
 
+    fn insert_element(&mut self, element: Element<Msg, Img>) -> ElementKey {
+        match self.free_slots.pop() {
+            Some(idx) => {
+                let slot = &mut self.slots[idx as usize];
+                slot.occupied = true;
+                self.elements[idx as usize] = element;
+                ElementKey {
+                    idx,
+                    generation: slot.generation,
+                }
+            }
+            None => {
+                let idx = self.elements.len() as u32;
+                self.elements.push(element);
+                self.slots.push(ElementSlot {
+                    generation: 0,
+                    occupied: true,
+                });
+                ElementKey { idx, generation: 0 }
+            }
+        }
+    }
+
+    /// Removes a single element from the `Gui` and returns it
+    ///
+    /// Children of the removed element are kept alive, they are only
+    /// detached from it. Use `Gui::remove_subtree` to drop them as well.
+    ///
+    /// Returns `None` if the key is stale.
+    pub fn remove_element(&mut self, key: ElementKey) -> Option<Element<Msg, Img>> {
+        if !self.contains(key) {
+            return None;
+        }
+        self.tree_dirty = true;
+        let slot = &mut self.slots[key.index()];
+        slot.occupied = false;
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free_slots.push(key.idx);
+
+        match self.elements[key.index()].parent {
+            Some(parent) if self.contains(parent) => {
+                let parent_e = &mut self.elements[parent.index()];
+                parent_e.layout.dirty_tree = true;
+                if let Some(children) = &mut parent_e.children {
+                    children.retain(|c| *c != key);
+                }
+                self.touch(parent);
+            }
+            // parent link may be missing if `children` were edited by hand
+            _ => {
+                for idx in 0..self.elements.len() {
+                    if !self.slots[idx].occupied {
+                        continue;
+                    }
+                    let element = &mut self.elements[idx];
+                    if let Some(children) = &mut element.children {
+                        let len = children.len();
+                        children.retain(|c| *c != key);
+                        if children.len() != len {
+                            element.layout.dirty_tree = true;
+                            self.touch(self.slot_key(idx));
+                        }
+                    }
+                }
+            }
+        }
+        if self.entry == Some(key) {
+            self.entry = None;
+        }
+        self.selection.forget(key);
+
+        let element = std::mem::take(&mut self.elements[key.index()]);
+        for var in &element.layout.deps.variables {
+            if let Some(keys) = self.subscribers.get_mut(var) {
+                keys.retain(|k| *k != key);
+            }
+        }
+        if let Some(children) = &element.children {
+            for child in children {
+                if let Some(child) = self.get_element_mut(*child) {
+                    child.parent = None;
+                    child.cascade.restyle = true;
+                }
+            }
+        }
+        Some(element)
+    }
+
+    /// Removes an element together with all of its descendants
+    ///
+    /// Returns the number of removed elements.
+    pub fn remove_subtree(&mut self, key: ElementKey) -> usize {
+        let element = match self.remove_element(key) {
+            Some(e) => e,
+            None => return 0,
+        };
+        let mut removed = 1;
+        if let Some(children) = element.children {
+            for child in children {
+                removed += self.remove_subtree(child);
+            }
+        }
+        removed
+    }
+
+    /// Returns the parent of an element
+    ///
+    /// Parent links are maintained by `Gui::add_element` and the tree editing methods.
+    /// Children added by editing `Element::children` directly are linked on the next `Gui::update`.
+    pub fn parent_of(&self, key: ElementKey) -> Option<ElementKey> {
+        self.get_element(key)
+            .and_then(|e| e.parent)
+            .filter(|p| self.contains(*p))
+    }
+
+    /// Iterates over all ancestors of an element, starting with its parent
+    pub fn ancestors(&self, key: ElementKey) -> impl Iterator<Item = ElementKey> + '_ {
+        std::iter::successors(self.parent_of(key), |k| self.parent_of(*k))
+    }
+
+    /// Removes the element from the children of its parent
+    ///
+    /// Returns the previous parent.
+    pub fn detach(&mut self, key: ElementKey) -> Option<ElementKey> {
+        let parent = self.parent_of(key)?;
+        let parent_e = &mut self.elements[parent.index()];
+        parent_e.layout.dirty_tree = true;
+        if let Some(children) = &mut parent_e.children {
+            children.retain(|c| *c != key);
+        }
+        self.touch(parent);
+        let element = &mut self.elements[key.index()];
+        element.parent = None;
+        // selectors may have matched the old ancestors
+        element.cascade.restyle = true;
+        self.touch(key);
+        self.tree_dirty = true;
+        Some(parent)
+    }
+
+    /// Moves the element to the end of children of `parent`
+    pub fn set_parent(&mut self, key: ElementKey, parent: ElementKey) -> Result<(), TreeError> {
+        let len = match self.get_element(parent) {
+            Some(p) => p.children.as_ref().map_or(0, |c| c.len()),
+            None => return Err(TreeError::NotFound),
+        };
+        let len = if self.parent_of(key) == Some(parent) {
+            len - 1
+        } else {
+            len
+        };
+        self.insert_child_at(parent, len, key)
+    }
+
+    /// Moves the element into children of `parent` at the given index
+    ///
+    /// The index is clamped to the number of children.
+    pub fn insert_child_at(
+        &mut self,
+        parent: ElementKey,
+        idx: usize,
+        key: ElementKey,
+    ) -> Result<(), TreeError> {
+        if !self.contains(key) || !self.contains(parent) {
+            return Err(TreeError::NotFound);
+        }
+        if key == parent || self.ancestors(parent).any(|k| k == key) {
+            return Err(TreeError::Cycle);
+        }
+        self.detach(key);
+        let parent_e = &mut self.elements[parent.index()];
+        parent_e.layout.dirty_tree = true;
+        let children = parent_e.children.get_or_insert_with(Vec::new);
+        children.insert(idx.min(children.len()), key);
+        let element = &mut self.elements[key.index()];
+        element.parent = Some(parent);
+        element.cascade.restyle = true;
+        // the new parent container is unrelated to the old one
+        element.layout.needs_update = true;
+        element.layout.force = true;
+        self.touch(parent);
+        self.touch(key);
+        self.tree_dirty = true;
+        Ok(())
+    }
+
+    /// Moves the element right before `sibling` in the children of its parent
+    pub fn move_before(&mut self, key: ElementKey, sibling: ElementKey) -> Result<(), TreeError> {
+        self.move_next_to(key, sibling, 0)
+    }
+
+    /// Moves the element right after `sibling` in the children of its parent
+    pub fn move_after(&mut self, key: ElementKey, sibling: ElementKey) -> Result<(), TreeError> {
+        self.move_next_to(key, sibling, 1)
+    }
+
+    fn move_next_to(
+        &mut self,
+        key: ElementKey,
+        sibling: ElementKey,
+        offset: usize,
+    ) -> Result<(), TreeError> {
+        if !self.contains(key) || !self.contains(sibling) {
+            return Err(TreeError::NotFound);
+        }
+        if key == sibling {
+            return Ok(());
+        }
+        let parent = self.parent_of(sibling).ok_or(TreeError::NoParent)?;
+        if self.ancestors(parent).chain([parent]).any(|k| k == key) {
+            return Err(TreeError::Cycle);
+        }
+        let children = self.elements[parent.index()].children.as_deref();
+        let mut idx = children
+            .and_then(|c| c.iter().position(|c| *c == sibling))
+            .ok_or(TreeError::NoParent)?;
+        // detaching a sibling that comes first shifts the index
+        if children.is_some_and(|c| c[..idx].contains(&key)) {
+            idx -= 1;
+        }
+        self.insert_child_at(parent, idx + offset, key)
+    }
+
+    /// Key of the element currently stored in the slot
+    pub(crate) fn slot_key(&self, idx: usize) -> ElementKey {
+        ElementKey {
+            idx: idx as u32,
+            generation: self.slots[idx].generation,
+        }
+    }
+
+    /// Checks whether the key points to a live element
+    pub fn contains(&self, k: ElementKey) -> bool {
+        match self.slots.get(k.index()) {
+            Some(slot) => slot.occupied && slot.generation == k.generation,
+            None => false,
+        }
+    }
+
     pub fn get_element(&self, k: ElementKey) -> Option<&Element<Msg, Img>> {
-        if (k.0 as usize) < self.elements.len() {
-            Some(&self.elements[k.0 as usize])
+        if self.contains(k) {
+            Some(&self.elements[k.index()])
         } else {
             None
         }
     }
 
+    /// Queues the element to be checked by the next `Gui::update`
+    ///
+    /// Changes to `Element::children` are picked up by the next update, which
+    /// links the parents of the added and removed children.
     pub fn get_element_mut(&mut self, k: ElementKey) -> Option<&mut Element<Msg, Img>> {
-        if (k.0 as usize) < self.elements.len() {
-            Some(&mut self.elements[k.0 as usize])
+        if self.contains(k) {
+            self.touch(k);
+            Some(&mut self.elements[k.index()])
         } else {
             None
         }
@@ -1272,22 +2310,24 @@ impl<Msg: Clone, Img: Clone + ImageData> Gui<Msg, Img> {
 
     /// # Panic
     ///
-    /// May panic if the element does not exist. This is generally safe, since if an element
-    /// does not exist, there is no key for it.
+    /// May panic if the element does not exist. The generation of the key is not
+    /// checked, a key of a removed element may resolve to the element that reused its slot.
     pub fn get_element_unchecked(&self, k: ElementKey) -> &Element<Msg, Img> {
-        &self.elements[k.0 as usize]
+        &self.elements[k.index()]
     }
 
     /// # Panic
     ///
-    /// May panic if the element does not exist. This is generally safe, since if an element
-    /// does not exist, there is no key for it.
+    /// May panic if the element does not exist. The generation of the key is not
+    /// checked, a key of a removed element may resolve to the element that reused its slot.
     pub fn get_element_mut_unchecked(&mut self, k: ElementKey) -> &mut Element<Msg, Img> {
-        &mut self.elements[k.0 as usize]
+        self.touch(k);
+        &mut self.elements[k.index()]
     }
 
     pub fn set_entry(&mut self, key: ElementKey) {
         self.entry = Some(key);
+        self.tree_dirty = true;
         self.selection.current = None;
         self.viewport.size_mut();
         self.viewport.pos_mut();
@@ -1301,9 +2341,41 @@ impl<Msg: Clone, Img: Clone + ImageData> Gui<Msg, Img> {
         self.size
     }
 
+    /// Number of element slots, including the vacant ones
+    ///
+    /// Every `ElementKey::index` is smaller than this number.
     pub fn elements(&self) -> usize {
         self.elements.len()
     }
+
+    /// Number of live elements
+    pub fn element_count(&self) -> usize {
+        self.elements.len() - self.free_slots.len()
+    }
+}
+
+/// Resolves a size style limited by its `max_*` and `min_*` counterparts
+fn clamp_size(
+    value: &Value,
+    max: &Option<Value>,
+    min: &Option<Value>,
+    containers: &Containers,
+    variables: &mut Variables,
+) -> f32 {
+    let mut size = value.calc(containers, variables);
+    if let Some(max) = max {
+        size = size.min(max.calc(containers, variables));
+    }
+    if let Some(min) = min {
+        size = size.max(min.calc(containers, variables));
+    }
+    size
+}
+
+#[derive(Debug, Copy, Clone)]
+struct ElementSlot {
+    generation: u32,
+    occupied: bool,
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -1390,6 +2462,12 @@ impl Selection {
         self.current = None;
         self.selectables.clear();
     }
+    fn forget(&mut self, key: ElementKey) {
+        if self.current == Some(key) {
+            self.current = None;
+        }
+        self.selectables.retain(|k| *k != key);
+    }
     pub fn select_element(&mut self, key: ElementKey) -> (Option<ElementKey>, Option<ElementKey>) {
         let last = self.current;
         if self.selectables.contains(&key) {
@@ -1420,8 +2498,13 @@ mod tests {
     };
 
     use crate::{
+        animation::Transition,
+        stylesheet::PseudoState,
         text::{Font, TextRepr},
-        Element, Gui, Vector,
+        variables::{Spring, Variable},
+        Border, Colors, CornerShape, Corners, ElemEventTypes, ElemEvents, Element, EnvEvents,
+        EventListener, Flags, Flex, Gui, Layout, MouseButtons, Outline, Portion, Position, Rotation,
+        Rotations, SelectOpts, Shadow, Sides, Style, Styles, Value, Vector,
     };
 
     #[test]
@@ -1511,6 +2594,566 @@ mod tests {
         panic!("danda")
     }
 
+    #[test]
+    pub fn element_removal() {
+        let mut gui: Gui = Gui::new((NonZero::new(800).unwrap(), NonZero::new(800).unwrap()));
+
+        let child = gui.add_element(Element::default());
+        let mut parent = Element::default();
+        parent.add_child(child);
+        let parent = gui.add_element(parent);
+        gui.set_entry(parent);
+
+        assert!(gui.remove_element(child).is_some());
+        assert!(gui.get_element(child).is_none());
+        assert!(gui.remove_element(child).is_none());
+        assert!(gui.get_element(parent).unwrap().children.as_ref().unwrap().is_empty());
+
+        let reused = gui.add_element(Element::default());
+        assert_eq!(reused.index(), child.index());
+        assert_ne!(reused, child);
+        assert!(gui.get_element(child).is_none());
+        assert!(gui.get_element(reused).is_some());
+
+        gui.get_element_mut(parent).unwrap().add_child(reused);
+        assert_eq!(gui.remove_subtree(parent), 2);
+        assert_eq!(gui.element_count(), 0);
+        assert_eq!(gui.get_entry(), None);
+    }
+
+    #[test]
+    pub fn tree_editing() {
+        let mut gui: Gui = Gui::new((NonZero::new(800).unwrap(), NonZero::new(800).unwrap()));
+
+        let a = gui.add_element(Element::default());
+        let b = gui.add_element(Element::default());
+        let c = gui.add_element(Element::default());
+        let mut root = Element::default();
+        root.children = Some(vec![a, b]);
+        let root = gui.add_element(root);
+
+        assert_eq!(gui.parent_of(a), Some(root));
+        assert_eq!(gui.parent_of(c), None);
+
+        gui.set_parent(c, root).unwrap();
+        gui.move_before(c, a).unwrap();
+        assert_eq!(gui.get_element(root).unwrap().children, Some(vec![c, a, b]));
+        gui.move_after(c, b).unwrap();
+        assert_eq!(gui.get_element(root).unwrap().children, Some(vec![a, b, c]));
+
+        gui.insert_child_at(a, 0, b).unwrap();
+        assert_eq!(gui.ancestors(b).collect::<Vec<_>>(), vec![a, root]);
+        assert_eq!(gui.set_parent(root, b), Err(crate::TreeError::Cycle));
+
+        assert_eq!(gui.detach(b), Some(a));
+        assert_eq!(gui.parent_of(b), None);
+        assert_eq!(gui.move_after(a, b), Err(crate::TreeError::NoParent));
+
+        gui.move_after(a, c).unwrap();
+        assert_eq!(gui.get_element(root).unwrap().children, Some(vec![c, a]));
+        // a failed move leaves the element where it was
+        gui.set_parent(b, a).unwrap();
+        gui.get_element_mut(a).unwrap().children = Some(Vec::new());
+        assert_eq!(gui.move_after(c, b), Err(crate::TreeError::NoParent));
+        assert_eq!(gui.parent_of(c), Some(root));
+        assert_eq!(gui.get_element(root).unwrap().children, Some(vec![c, a]));
+    }
+
+    #[test]
+    pub fn incremental_layout() {
+        let mut gui: Gui = Gui::new((NonZero::new(800).unwrap(), NonZero::new(800).unwrap()));
+        let var = gui.variables.push(Variable::new_var());
+        gui.variables.set(var, 100.0).unwrap();
+
+        let mut dynamic = Element::default();
+        dynamic.styles_mut().width.set(Value::Variable(var));
+        let dynamic = gui.add_element(dynamic);
+        let fixed = gui.add_element(Element::default());
+        let mut root = Element::default();
+        root.children = Some(vec![dynamic, fixed]);
+        let root = gui.add_element(root);
+        gui.set_entry(root);
+
+        gui.update(0.0);
+        let stamp = gui.get_element(fixed).unwrap().layout.var_stamp;
+        assert_eq!(gui.get_element(dynamic).unwrap().instance().container.size.0, 100.0);
+
+        gui.variables.set(var, 50.0).unwrap();
+        gui.update(0.0);
+        assert_eq!(gui.get_element(dynamic).unwrap().instance().container.size.0, 50.0);
+        assert_eq!(gui.get_element(fixed).unwrap().layout.var_stamp, stamp);
+        // variable readers are indexed by the variable instead of being polled
+        assert!(gui.watched.is_empty());
+        assert_eq!(gui.subscribers[&var], vec![dynamic]);
+
+        // mutable access queues the element without touching the tree
+        gui.get_element_mut(fixed).unwrap();
+        assert!(!gui.get_element(fixed).unwrap().layout.dirty_tree);
+        assert_eq!(gui.touched, vec![fixed]);
+        gui.update(0.0);
+        assert!(gui.touched.is_empty());
+        assert!(!gui.tree_dirty);
+
+        gui.resize((NonZero::new(400).unwrap(), NonZero::new(400).unwrap()));
+        gui.update(0.0);
+        assert_eq!(gui.get_element(fixed).unwrap().instance().container.size.0, 400.0);
+    }
+
+    #[test]
+    pub fn children_edited_through_mutable_access() {
+        let mut gui: Gui<u32> = Gui::new((NonZero::new(800).unwrap(), NonZero::new(800).unwrap()));
+        let old = gui.add_element(Element::default());
+        let mut root = Element::default();
+        root.children = Some(vec![old]);
+        let root = gui.add_element(root);
+        gui.set_entry(root);
+        gui.update(0.0);
+
+        let mut child = Element::default();
+        child.styles_mut().width.set(Value::Px(42.0));
+        child.events.add(EventListener::new(ElemEventTypes::Selection).with_msg(1));
+        let child = gui.add_element(child);
+        gui.get_element_mut(root).unwrap().children = Some(vec![child]);
+        gui.update(0.0);
+
+        assert_eq!(gui.get_element(child).unwrap().instance().container.size.0, 42.0);
+        assert_eq!(gui.parent_of(child), Some(root));
+        assert_eq!(gui.parent_of(old), None);
+        assert_eq!(gui.selection.selectables, vec![child]);
+    }
+
+    #[test]
+    pub fn variable_bindings() {
+        let mut gui: Gui = Gui::new((NonZero::new(800).unwrap(), NonZero::new(800).unwrap()));
+        let health = gui
+            .variables
+            .push_named("health", Variable::new_value(Colors::GREEN));
+        let width = gui.variables.push_named("width", Variable::new_value(120.0));
+        let mut sub = gui.variables.subscribe(health);
+
+        let mut bar = Element::default();
+        bar.styles_mut().color.bind(health);
+        bar.styles_mut().width.bind(width);
+        let bar = gui.add_element(bar);
+        let fixed = gui.add_element(Element::default());
+        let mut root = Element::default();
+        root.children = Some(vec![bar, fixed]);
+        let root = gui.add_element(root);
+        gui.set_entry(root);
+
+        gui.update(0.0);
+        let stamp = gui.get_element(fixed).unwrap().layout.var_stamp;
+        let instance = gui.get_element(bar).unwrap().instance();
+        assert_eq!(instance.color, <[f32; 4]>::from(Colors::GREEN));
+        assert_eq!(instance.container.size.0, 120.0);
+        assert!(sub.poll(&gui.variables).is_none());
+
+        let key = gui.variables.key("health").unwrap();
+        assert!(gui.variables.set_value(key, 3.0).is_err());
+        gui.variables.set_value(key, Colors::RED).unwrap();
+        assert_eq!(sub.poll(&gui.variables).and_then(|v| v.as_color()), Some(Colors::RED));
+        assert!(sub.poll(&gui.variables).is_none());
+        gui.update(0.0);
+        assert_eq!(gui.get_element(bar).unwrap().instance().color, <[f32; 4]>::from(Colors::RED));
+        assert_eq!(gui.get_element(fixed).unwrap().layout.var_stamp, stamp);
+
+        let value = gui.variables.parse("$width * 2").unwrap();
+        gui.get_element_mut(fixed).unwrap().styles_mut().width.set(value);
+        gui.update(0.0);
+        assert_eq!(gui.get_element(fixed).unwrap().instance().container.size.0, 240.0);
+
+        // a bound border takes its width from the variable and keeps its color
+        let styles = gui.get_element_mut(fixed).unwrap().styles_mut();
+        styles.border.set(Border::new(Sides::all(Value::Px(1.0)), Colors::RED));
+        styles.border.bind(width);
+        gui.update(0.0);
+        let instance = gui.get_element(fixed).unwrap().instance();
+        assert_eq!(instance.border, Sides::all(120.0));
+        assert_eq!(instance.border_color, <[f32; 4]>::from(Colors::RED));
+    }
+
+    #[test]
+    pub fn fit_content() {
+        let mut gui: Gui = Gui::new((NonZero::new(800).unwrap(), NonZero::new(800).unwrap()));
+        gui.text_ctx.add_font(
+            Font::from_bytes(
+                include_bytes!("../examples/game/src/NotoSans-Medium.ttf"),
+                0,
+            )
+            .unwrap(),
+        );
+
+        let mut children = Vec::new();
+        for (w, h) in [(40.0, 10.0), (20.0, 30.0)] {
+            let mut child = Element::default();
+            child.styles_mut().width.set(Value::Px(w));
+            child.styles_mut().height.set(Value::Px(h));
+            children.push(gui.add_element(child));
+        }
+        let mut label = Element::default();
+        label.styles_mut().width.set(Value::content_width(Portion::Full));
+        label.styles_mut().text.set(Some(TextRepr::new_label("Hello world")));
+        children.push(gui.add_element(label));
+        let label = *children.last().unwrap();
+
+        let mut panel = Element::default();
+        let styles = panel.styles_mut();
+        styles.width.set(Value::content_width(Portion::Full));
+        styles.height.set(Value::content_height(Portion::Full));
+        panel.children = Some(children);
+        let panel = gui.add_element(panel);
+        let mut root = Element::default();
+        root.children = Some(vec![panel]);
+        let root = gui.add_element(root);
+        gui.set_entry(root);
+
+        gui.update(0.0);
+        let label_size = gui.get_element(label).unwrap().instance().container.size;
+        assert!(label_size.0 > 40.0);
+        let text = gui.get_element(label).unwrap().styles().text.get().as_ref().unwrap();
+        assert_eq!(text.text.size().0, label_size.0);
+
+        let panel_size = gui.get_element(panel).unwrap().instance().container.size;
+        assert_eq!(panel_size, Vector(label_size.0, 30.0));
+    }
+
+    #[test]
+    pub fn flex_layout() {
+        let mut gui: Gui = Gui::new((NonZero::new(800).unwrap(), NonZero::new(800).unwrap()));
+        gui.text_ctx.add_font(
+            Font::from_bytes(
+                include_bytes!("../examples/game/src/NotoSans-Medium.ttf"),
+                0,
+            )
+            .unwrap(),
+        );
+
+        let mut boxed = Element::default();
+        let styles = boxed.styles_mut();
+        styles.width.set(Value::Px(300.0));
+        styles.padding.set(Sides::all(Value::Px(10.0)));
+        let boxed = gui.add_element(boxed);
+        let mut label = Element::default();
+        let styles = label.styles_mut();
+        styles.width.set(Value::Px(100.0));
+        styles.text.set(Some(TextRepr::new_paragraph("Hello wonderful world")));
+        let label = gui.add_element(label);
+
+        let mut panel = Element::default();
+        let styles = panel.styles_mut();
+        styles.width.set(Value::Px(200.0));
+        styles.layout.set(Layout::Flex(Flex::row()));
+        panel.children = Some(vec![boxed, label]);
+        let panel = gui.add_element(panel);
+        let mut root = Element::default();
+        root.children = Some(vec![panel]);
+        let root = gui.add_element(root);
+        gui.set_entry(root);
+
+        gui.update(0.0);
+        let text = gui.get_element(label).unwrap().styles().text.get().as_ref().unwrap();
+        let min_width = text.text.min_width();
+        assert!(min_width > 200.0 / 4.0);
+        // proportional shrinking would leave the label a quarter of the panel
+        let label_c = gui.get_element(label).unwrap().instance().container;
+        assert!(label_c.size.0 >= min_width);
+        assert_eq!(label_c.pos.0, 100.0 - label_c.size.0 * 0.5);
+
+        // padding is the gap around the box inside of its slot
+        let boxed_c = gui.get_element(boxed).unwrap().instance().container;
+        assert_eq!(boxed_c.size.0, 200.0 - label_c.size.0 - 20.0);
+        assert_eq!(boxed_c.pos.0, -100.0 + 10.0 + boxed_c.size.0 * 0.5);
+    }
+
+    #[test]
+    pub fn side_insets() {
+        let mut gui: Gui = Gui::new((NonZero::new(800).unwrap(), NonZero::new(800).unwrap()));
+        let child = gui.add_element(Element::default());
+        let mut panel = Element::default();
+        let styles = panel.styles_mut();
+        styles.padding.set(Sides::new(
+            Value::Px(10.0),
+            Value::Px(20.0),
+            Value::Px(30.0),
+            Value::Px(40.0),
+        ));
+        styles.border.set(Border::new(Value::Px(5.0), Colors::WHITE));
+        styles.margin.set(Sides::symmetric(Value::Px(5.0), Value::Zero));
+        panel.children = Some(vec![child]);
+        let panel = gui.add_element(panel);
+        gui.set_entry(panel);
+
+        gui.update(0.0);
+        let instance = gui.get_element(panel).unwrap().instance();
+        assert_eq!(instance.container.size, Vector(740.0, 760.0));
+        assert_eq!(instance.container.pos, Vector(10.0, -10.0));
+        assert_eq!(instance.clip_container().size, Vector(730.0, 750.0));
+        let child = gui.get_element(child).unwrap().instance().container;
+        assert_eq!(child.size, Vector(730.0, 740.0));
+        assert_eq!(child.pos, Vector(10.0, -10.0));
+    }
+
+    #[test]
+    pub fn borders_and_outlines() {
+        let mut gui: Gui = Gui::new((NonZero::new(800).unwrap(), NonZero::new(800).unwrap()));
+        let mut panel = Element::default();
+        let styles = panel.styles_mut();
+        styles.border.set(
+            Border::new(Sides::symmetric(Value::Px(2.0), Value::Px(4.0)), Colors::RED)
+                .with_dash(Value::Px(6.0), Value::Px(3.0)),
+        );
+        styles.outline.set(Some(Outline::new(Value::Px(1.0), Value::Px(2.0), Colors::BLUE)));
+        let panel = gui.add_element(panel);
+        gui.set_entry(panel);
+
+        gui.update(0.0);
+        let instance = gui.get_element(panel).unwrap().instance();
+        assert!(Flags::Border.contained_in(instance.flags));
+        assert!(Flags::Outline.contained_in(instance.flags));
+        assert!(!Flags::BorderGradient.contained_in(instance.flags));
+        assert_eq!(instance.border_color, <[f32; 4]>::from(Colors::RED));
+        assert_eq!(instance.border_dash, Vector(6.0, 3.0));
+        assert_eq!(instance.outline_offset, 2.0);
+        assert_eq!(instance.container.size, Vector(800.0, 800.0));
+        assert_eq!(instance.clip_container().size, Vector(792.0, 796.0));
+
+        let styles = gui.get_element_mut(panel).unwrap().styles_mut();
+        styles.outline.set(None);
+        styles.border.set(Border::default());
+        gui.update(0.0);
+        let instance = gui.get_element(panel).unwrap().instance();
+        assert!(!Flags::Border.contained_in(instance.flags));
+        assert!(!Flags::Outline.contained_in(instance.flags));
+    }
+
+    #[test]
+    pub fn shadow_list() {
+        let mut gui: Gui = Gui::new((NonZero::new(800).unwrap(), NonZero::new(800).unwrap()));
+        let mut card = Element::default();
+        card.styles_mut().width.set(Value::Px(200.0));
+        card.styles_mut().shadows.set(vec![
+            Shadow::new(Value::Zero, Value::Px(4.0), Value::Px(8.0), Colors::BLACK),
+            Shadow::new(
+                Value::Zero,
+                Value::Zero,
+                Value::Px(-3.0),
+                Colors::FRgba(1.0, 0.0, 0.0, 0.5),
+            )
+            .with_spread(Value::Px(2.0))
+            .inset(),
+        ]);
+        let card = gui.add_element(card);
+        gui.set_entry(card);
+        gui.update(0.0);
+
+        let shadows = gui.get_element(card).unwrap().shadows();
+        assert_eq!(shadows.len(), 2);
+        assert_eq!(shadows[0].offset, Vector(0.0, 4.0));
+        assert_eq!(shadows[0].blur, 8.0);
+        assert!(!shadows[0].inset);
+        assert_eq!(shadows[1].blur, 0.0);
+        assert_eq!(shadows[1].spread, 2.0);
+        assert_eq!(shadows[1].color, [1.0, 0.0, 0.0, 0.5]);
+        assert!(shadows[1].inset);
+
+        gui.get_element_mut(card).unwrap().styles_mut().shadows.set(Vec::new());
+        gui.update(0.0);
+        assert!(gui.get_element(card).unwrap().shadows().is_empty());
+
+        // the legacy shadow becomes a single entry in the color of the element
+        let styles = gui.get_element_mut(card).unwrap().styles_mut();
+        styles.shadow.set(Some(Value::Px(10.0)));
+        styles.shadow_alpha.set(0.5);
+        styles.color.set(Colors::FRgba(0.0, 0.0, 1.0, 1.0));
+        gui.update(0.0);
+        let shadows = gui.get_element(card).unwrap().shadows();
+        assert_eq!(shadows.len(), 1);
+        assert_eq!((shadows[0].blur, shadows[0].spread), (10.0, 5.0));
+        assert_eq!(shadows[0].color, [0.0, 0.0, 1.0, 0.5]);
+    }
+
+    #[test]
+    pub fn rounded_hit_test() {
+        let mut gui: Gui<u32> = Gui::new((NonZero::new(800).unwrap(), NonZero::new(800).unwrap()));
+        let mut panel = Element::default();
+        panel.styles_mut().round.set(
+            Corners::new(Value::Px(200.0), Value::Zero, Value::Px(200.0), Value::Zero)
+                .with_shape(CornerShape::Squircle),
+        );
+        panel.events.add(EventListener::new(ElemEventTypes::Click).with_msg(1));
+        let panel = gui.add_element(panel);
+        gui.set_entry(panel);
+        gui.resize((NonZero::new(800).unwrap(), NonZero::new(800).unwrap()));
+        gui.update(0.0);
+        let instance = gui.get_element(panel).unwrap().instance();
+        assert_eq!(instance.round.top_left, 200.0);
+        assert_eq!(instance.round.shape, CornerShape::Squircle);
+
+        let mut click = |pos| {
+            gui.env_event(EnvEvents::CursorMove { pos });
+            gui.env_event(EnvEvents::MouseButton { button: MouseButtons::Left, press: true });
+            gui.env_event(EnvEvents::MouseButton { button: MouseButtons::Left, press: false });
+            let mut clicked = false;
+            while let Some(event) = gui.poll_event() {
+                clicked |= matches!(event.kind, ElemEvents::Click { press: true, .. });
+            }
+            clicked
+        };
+        assert!(!click(Vector(10.0, 10.0)));
+        assert!(click(Vector(790.0, 10.0)));
+        assert!(!click(Vector(790.0, 790.0)));
+        assert!(click(Vector(10.0, 790.0)));
+        assert!(click(Vector(60.0, 60.0)));
+    }
+
+    #[test]
+    pub fn pseudo_states() {
+        let mut gui: Gui<u32> = Gui::new((NonZero::new(800).unwrap(), NonZero::new(800).unwrap()));
+        let mut children = Vec::new();
+        for (msg, disabled) in [(1, false), (2, true)] {
+            let mut button = Element::default();
+            button.styles_mut().width.set(Value::Px(100.0));
+            button.styles_mut().height.set(Value::Px(100.0));
+            button.events.add(EventListener::new(ElemEventTypes::Click).with_msg(msg));
+            button.events.add(EventListener::new(ElemEventTypes::Selection).with_msg(msg));
+            button.state_style_mut(PseudoState::Hover).color = Some(Colors::RED);
+            button.set_disabled(disabled);
+            children.push(gui.add_element(button));
+        }
+        let (enabled, disabled) = (children[0], children[1]);
+        let mut root = Element::default();
+        root.children = Some(children);
+        let root = gui.add_element(root);
+        gui.set_entry(root);
+        gui.resize((NonZero::new(800).unwrap(), NonZero::new(800).unwrap()));
+        gui.update(0.0);
+
+        gui.env_event(EnvEvents::CursorMove { pos: Vector(400.0, 400.0) });
+        // only the elements whose state changed are restyled
+        assert!(gui.touched.contains(&enabled));
+        assert!(!gui.touched.contains(&disabled));
+        gui.update(0.0);
+        gui.env_event(EnvEvents::CursorMove { pos: Vector(401.0, 400.0) });
+        assert!(gui.touched.is_empty());
+        let button = gui.get_element(enabled).unwrap();
+        assert!(button.states().hover);
+        assert_eq!(button.styles().color.get(), &Colors::RED);
+        assert!(gui.get_element(root).unwrap().states().hover);
+        assert!(!gui.get_element(disabled).unwrap().states().hover);
+
+        gui.env_event(EnvEvents::MouseButton { button: MouseButtons::Left, press: true });
+        assert!(gui.get_element(enabled).unwrap().states().active);
+        let mut msgs = Vec::new();
+        while let Some(event) = gui.poll_event() {
+            msgs.push(event.msg);
+        }
+        assert_eq!(msgs, [Some(1)]);
+        gui.env_event(EnvEvents::MouseButton { button: MouseButtons::Left, press: false });
+        assert!(!gui.get_element(enabled).unwrap().states().active);
+
+        gui.env_event(EnvEvents::Select { opt: SelectOpts::Next });
+        assert_eq!(gui.selection.current, Some(enabled));
+        assert!(gui.get_element(enabled).unwrap().states().focus);
+        gui.env_event(EnvEvents::Select { opt: SelectOpts::Next });
+        assert_eq!(gui.selection.current, None);
+
+        gui.env_event(EnvEvents::CursorMove { pos: Vector(0.0, 0.0) });
+        gui.update(0.0);
+        let button = gui.get_element(enabled).unwrap();
+        assert!(!button.states().hover);
+        assert_eq!(button.styles().color.get(), Styles::<()>::default().color.get());
+    }
+
+    #[test]
+    pub fn transitions() {
+        let mut gui: Gui<u32> = Gui::new((NonZero::new(800).unwrap(), NonZero::new(800).unwrap()));
+        let mut element = Element::default();
+        element.styles_mut().color.set(Colors::BLACK);
+        element.styles_mut().color.set_transition(Some(Transition::new(1.0)));
+        element.events.add(EventListener::new(ElemEventTypes::TransitionEnd).with_msg(5));
+        let key = gui.add_element(element);
+        gui.set_entry(key);
+        gui.update(0.0);
+        assert_eq!(gui.get_element(key).unwrap().instance().color, [0.0, 0.0, 0.0, 1.0]);
+
+        gui.get_element_mut(key).unwrap().styles_mut().color.set(Colors::WHITE);
+        gui.update(1.0);
+        assert_eq!(gui.get_element(key).unwrap().instance().color, [0.0, 0.0, 0.0, 1.0]);
+        gui.update(1.5);
+        assert_eq!(gui.get_element(key).unwrap().instance().color, [0.5, 0.5, 0.5, 1.0]);
+        assert!(gui.poll_event().is_none());
+        gui.update(2.0);
+        assert_eq!(gui.get_element(key).unwrap().instance().color, [1.0; 4]);
+        let event = gui.poll_event().unwrap();
+        assert_eq!(event.msg, Some(5));
+        assert!(matches!(event.kind, ElemEvents::TransitionEnd { style: Style::Color }));
+
+        // setting the displayed value again starts nothing
+        gui.get_element_mut(key).unwrap().styles_mut().color.set(Colors::WHITE);
+        gui.update(2.5);
+        assert!(!gui.get_element(key).unwrap().layout.transitions.is_running());
+        assert!(gui.poll_event().is_none());
+    }
+
+    #[test]
+    pub fn size_and_position_transitions() {
+        let mut gui: Gui<u32> = Gui::new((NonZero::new(800).unwrap(), NonZero::new(800).unwrap()));
+        let mut parent = Element::default();
+        parent.styles_mut().rotation.set(Rotation {
+            rot: Rotations::Deg(90.0),
+            cont: crate::styles::Container::Container,
+        });
+        let mut child = Element::default();
+        let styles = child.styles_mut();
+        styles.width.set(Value::Px(100.0));
+        styles.height.set(Value::Px(100.0));
+        styles.position.set(Position {
+            width: Value::Px(600.0),
+            height: Value::Px(400.0),
+            container: crate::styles::Container::Container,
+        });
+        styles.width.set_transition(Some(Transition::new(1.0)));
+        styles.position.set_transition(Some(Transition::new(1.0)));
+        let child = gui.add_element(child);
+        let parent = gui.add_element(parent);
+        gui.set_parent(child, parent).unwrap();
+        gui.set_entry(parent);
+        gui.update(0.0);
+
+        let styles = gui.get_element_mut(child).unwrap().styles_mut();
+        styles.width.set(Value::Px(200.0));
+        styles.position.set(Position {
+            width: Value::Px(200.0),
+            height: Value::Px(400.0),
+            container: crate::styles::Container::Container,
+        });
+        gui.update(1.0);
+        gui.update(1.5);
+        let container = gui.get_element(child).unwrap().instance().container;
+        assert_eq!(container.size.0, 150.0);
+        // halfway between the two positions is the center of the rotated parent
+        let center = gui.get_element(parent).unwrap().instance().container.pos;
+        let offset = (container.pos - center).abs();
+        assert!(offset.0 < 1e-3 && offset.1 < 1e-3);
+        gui.update(2.0);
+        assert_eq!(gui.get_element(child).unwrap().instance().container.size.0, 200.0);
+    }
+
+    #[test]
+    pub fn springs_skip_first_update() {
+        let mut gui: Gui<u32> = Gui::new((NonZero::new(800).unwrap(), NonZero::new(800).unwrap()));
+        let root = gui.add_element(Element::default());
+        gui.set_entry(root);
+        let key = gui.variables.push_spring(0.0, Spring::critical(100.0));
+        gui.variables.set_target(key, 1.0).unwrap();
+        // time is measured from the first update, not from 0.0
+        gui.update(50.0);
+        assert_eq!(gui.variables.get(key), Some(0.0));
+        gui.update(50.1);
+        let value = gui.variables.get(key).unwrap();
+        assert!(value > 0.0 && value < 1.0);
+    }
+
     fn measure_task<T>(mut task: impl FnMut() -> T, label: Option<&str>) -> (T, Duration) {
         let start = Instant::now();
         let r = task();
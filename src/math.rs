@@ -3,6 +3,7 @@ use std::ops::{Add, Div, Mul, Rem, Sub};
 use crate::element::Container;
 
 #[derive(Debug, Copy, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(C)]
 pub struct Vector(pub f32, pub f32);
 
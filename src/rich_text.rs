@@ -48,6 +48,7 @@ pub struct TextStylesInstance {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TextStyles {
     pub align: StyleComponent<TextAlign>,
     pub line_offset: StyleComponent<Portion>,
@@ -66,6 +67,7 @@ pub struct SectionStylesInstance {
     pub italic: bool,
 }
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SectionStyles {
     pub left_pad: StyleComponent<Value>,
     pub right_pad: StyleComponent<Value>,
@@ -77,22 +79,29 @@ pub struct SectionStyles {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Text {
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub(crate)instance_data: TextStylesInstance,
     pub styles: TextStyles,
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub shape: ShapeStorages,
     pub sections: Vec<TextSection>,
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TextSection {
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub(crate)instance_data: SectionStylesInstance,
     pub styles: SectionStyles,
+    #[cfg_attr(feature = "serde", serde(with = "crate::snapshot::rope"))]
     pub text: Rope,
     pub kind: SectionKinds,
 }
 
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum SectionKinds {
     Section,
     NewLine,
@@ -372,6 +381,12 @@ impl Default for SectionStylesInstance {
     }
 }
 
+impl Default for ShapeStorages {
+    fn default() -> Self {
+        ShapeStorages::Internal(TextShape::default())
+    }
+}
+
 impl Default for TextShape {
     fn default() -> Self {
         TextShape {
@@ -0,0 +1,455 @@
+//! Serialization of elements and their styles
+//!
+//! Enabled by the `serde` feature. Images and messages can not be serialized
+//! directly, they are written by the names given by `ImageData::image_id` and
+//! `MsgResolver::msg_id` and looked up again when deserializing. Images are
+//! looked up by the resolver of `ImageSeed`.
+
+use std::marker::PhantomData;
+
+use serde::{
+    de::{DeserializeSeed, Error},
+    ser::SerializeStruct,
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+use crate::{
+    builder::ElementBuilder,
+    element::ElementStates,
+    styles::{Image, ImageFit, Sides},
+    stylesheet::StylePatch,
+    text::{TextRepr, TextVariants},
+    Element, ElementKey, Gui, ImageData, StyleComponent, Vector,
+};
+
+/// Conversion of messages to names and back
+pub trait MsgResolver: Sized {
+    /// Name the message is serialized under, listeners without one lose their message
+    fn msg_id(&self) -> Option<String>;
+
+    /// Finds the message that was serialized under `id`
+    fn resolve_msg(id: &str) -> Option<Self>;
+}
+
+impl MsgResolver for () {
+    fn msg_id(&self) -> Option<String> {
+        Some(String::new())
+    }
+
+    fn resolve_msg(_id: &str) -> Option<Self> {
+        Some(())
+    }
+}
+
+impl MsgResolver for String {
+    fn msg_id(&self) -> Option<String> {
+        Some(self.clone())
+    }
+
+    fn resolve_msg(id: &str) -> Option<Self> {
+        Some(id.to_string())
+    }
+}
+
+macro_rules! int_resolvers {
+    ($($ty: ty),*) => {
+        $(impl MsgResolver for $ty {
+            fn msg_id(&self) -> Option<String> {
+                Some(self.to_string())
+            }
+
+            fn resolve_msg(id: &str) -> Option<Self> {
+                id.parse().ok()
+            }
+        })*
+    };
+}
+
+int_resolvers!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+
+/// Owned copy of a subtree
+///
+/// Elements are stored with their children instead of `ElementKey`s,
+/// so the tree can be restored into any `Gui`. The images of an element
+/// are written next to it, see `ImageSeed` for reading them back.
+pub struct ElementTree<Msg: Clone, Img: Clone + ImageData> {
+    pub element: Element<Msg, Img>,
+    pub children: Vec<ElementTree<Msg, Img>>,
+}
+
+impl<Msg: Clone, Img: Clone + ImageData> ElementTree<Msg, Img> {
+    pub fn to_builder(self) -> ElementBuilder<Msg, Img> {
+        let mut builder = ElementBuilder::from_element(self.element);
+        for child in self.children {
+            builder = builder.child(child.to_builder());
+        }
+        builder
+    }
+}
+
+impl<Msg: Clone + MsgResolver, Img: Clone + ImageData> Serialize for ElementTree<Msg, Img> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut tree = serializer.serialize_struct("ElementTree", 3)?;
+        tree.serialize_field("element", &self.element)?;
+        tree.serialize_field("images", &ImageIds::new(&self.element))?;
+        tree.serialize_field("children", &self.children)?;
+        tree.end()
+    }
+}
+
+/// Fails on trees with images, they can only be found through `ImageSeed`
+impl<'de, Msg: Clone + MsgResolver, Img: Clone + ImageData> Deserialize<'de>
+    for ElementTree<Msg, Img>
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        ImageSeed::new(|_| None).deserialize(deserializer)
+    }
+}
+
+/// Deserialized form of `ElementTree` with the images not looked up yet
+#[derive(Deserialize)]
+#[serde(rename = "ElementTree", bound = "Msg: MsgResolver")]
+struct TreeRepr<Msg: Clone, Img: Clone + ImageData> {
+    element: Element<Msg, Img>,
+    #[serde(default)]
+    images: ImageIds,
+    children: Vec<TreeRepr<Msg, Img>>,
+}
+
+impl<Msg: Clone, Img: Clone + ImageData> TreeRepr<Msg, Img> {
+    /// Fails with the id of the first image `resolve` does not know
+    fn resolve(
+        self,
+        resolve: &mut impl FnMut(&str) -> Option<Img>,
+    ) -> Result<ElementTree<Msg, Img>, String> {
+        let mut element = self.element;
+        self.images.apply(&mut element, resolve)?;
+        let children = self
+            .children
+            .into_iter()
+            .map(|child| child.resolve(resolve))
+            .collect::<Result<_, _>>()?;
+        Ok(ElementTree { element, children })
+    }
+}
+
+/// Deserializes an `ElementTree`, images are looked up by their `ImageData::image_id`
+pub struct ImageSeed<Msg, Img, F> {
+    resolve: F,
+    marker: PhantomData<fn() -> (Msg, Img)>,
+}
+
+impl<Msg, Img, F: FnMut(&str) -> Option<Img>> ImageSeed<Msg, Img, F> {
+    pub fn new(resolve: F) -> Self {
+        Self {
+            resolve,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<'de, Msg, Img, F> DeserializeSeed<'de> for ImageSeed<Msg, Img, F>
+where
+    Msg: Clone + MsgResolver,
+    Img: Clone + ImageData,
+    F: FnMut(&str) -> Option<Img>,
+{
+    type Value = ElementTree<Msg, Img>;
+
+    fn deserialize<D: Deserializer<'de>>(
+        mut self,
+        deserializer: D,
+    ) -> Result<Self::Value, D::Error> {
+        TreeRepr::deserialize(deserializer)?
+            .resolve(&mut self.resolve)
+            .map_err(|id| D::Error::custom(format!("unknown image '{id}'")))
+    }
+}
+
+impl<Msg: Clone, Img: Clone + ImageData> Gui<Msg, Img> {
+    /// Copies the subtree under `key`, `None` if the key is not live
+    pub fn snapshot_subtree(&self, key: ElementKey) -> Option<ElementTree<Msg, Img>> {
+        let element = self.get_element(key)?;
+        let children = element
+            .children
+            .iter()
+            .flatten()
+            .filter_map(|child| self.snapshot_subtree(*child))
+            .collect();
+        Some(ElementTree {
+            element: Element {
+                label: element.label.clone(),
+                classes: element.classes.clone(),
+                overrides: element.overrides.clone(),
+                state_styles: element.state_styles.clone(),
+                states: ElementStates {
+                    disabled: element.states.disabled,
+                    ..Default::default()
+                },
+                events: element.events.clone(),
+                styles: element.styles.clone(),
+                procedures: element.procedures.clone(),
+                ..Default::default()
+            },
+            children,
+        })
+    }
+
+    /// Adds the elements of a snapshot, the root is not attached to any parent
+    pub fn restore_subtree(&mut self, tree: ElementTree<Msg, Img>) -> ElementKey {
+        tree.to_builder().build(self).root
+    }
+}
+
+/// Serialized form of `TextRepr`, the shaped text is rebuilt on the next update
+#[derive(Serialize, Deserialize)]
+enum TextDef {
+    Label(String),
+    Paragraph(String),
+    Editor(String),
+}
+
+impl Serialize for TextRepr {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let text = self.text.text.to_string();
+        match self.variant {
+            TextVariants::Label => TextDef::Label(text),
+            TextVariants::Paragraph { .. } => TextDef::Paragraph(text),
+            TextVariants::Editor { .. } => TextDef::Editor(text),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for TextRepr {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(match TextDef::deserialize(deserializer)? {
+            TextDef::Label(t) => TextRepr::new_label(&t),
+            TextDef::Paragraph(t) => TextRepr::new_paragraph(&t),
+            TextDef::Editor(t) => TextRepr::new_editor(&t),
+        })
+    }
+}
+
+/// Deserialized styles and listeners are always dirty
+pub(crate) fn dirty() -> bool {
+    true
+}
+
+/// Image written by its id together with how it is drawn
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ImageRepr {
+    id: String,
+    fit: ImageFit,
+    align: Vector,
+    source: Option<[u32; 4]>,
+    slice: Option<Sides<f32>>,
+}
+
+impl ImageRepr {
+    fn new<Img: Clone + ImageData>(image: &Image<Img>) -> Option<Self> {
+        Some(Self {
+            id: image.data.image_id()?,
+            fit: image.fit,
+            align: image.align,
+            source: image.source,
+            slice: image.slice,
+        })
+    }
+
+    fn resolve<Img: Clone + ImageData>(
+        &self,
+        resolve: &mut impl FnMut(&str) -> Option<Img>,
+    ) -> Result<Image<Img>, String> {
+        let data = resolve(&self.id).ok_or_else(|| self.id.clone())?;
+        Ok(Image {
+            data,
+            fit: self.fit,
+            align: self.align,
+            source: self.source,
+            slice: self.slice,
+        })
+    }
+}
+
+/// Images of an element, they are skipped by the serialization of the element itself
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+struct ImageIds {
+    styles: Option<StyleComponent<Option<ImageRepr>>>,
+    overrides: Option<Option<ImageRepr>>,
+    /// Images of `Element::state_styles` in the same order
+    states: Vec<Option<Option<ImageRepr>>>,
+}
+
+impl ImageIds {
+    fn new<Msg: Clone, Img: Clone + ImageData>(element: &Element<Msg, Img>) -> Self {
+        let image = &element.styles.image;
+        let mut styles = StyleComponent::new(image.get().as_ref().and_then(ImageRepr::new));
+        styles.set_dynamic(image.is_dynamic());
+        let patch = |patch: &StylePatch<Img>| {
+            patch
+                .image
+                .as_ref()
+                .map(|i| i.as_ref().and_then(ImageRepr::new))
+        };
+        Self {
+            styles: Some(styles),
+            overrides: patch(&element.overrides),
+            states: element.state_styles.iter().map(|(_, p)| patch(p)).collect(),
+        }
+    }
+
+    fn apply<Msg: Clone, Img: Clone + ImageData>(
+        self,
+        element: &mut Element<Msg, Img>,
+        resolve: &mut impl FnMut(&str) -> Option<Img>,
+    ) -> Result<(), String> {
+        if let Some(repr) = self.styles {
+            let image = repr
+                .get()
+                .as_ref()
+                .map(|r| r.resolve(resolve))
+                .transpose()?;
+            let mut component = StyleComponent::new(image);
+            component.set_dynamic(repr.is_dynamic());
+            component.set_dirty();
+            element.styles.image = component;
+        }
+        let mut patch = |repr: Option<ImageRepr>| repr.map(|r| r.resolve(resolve)).transpose();
+        if let Some(repr) = self.overrides {
+            element.overrides.image = Some(patch(repr)?);
+        }
+        for ((_, state), repr) in element.state_styles.iter_mut().zip(self.states) {
+            if let Some(repr) = repr {
+                state.image = Some(patch(repr)?);
+            }
+        }
+        Ok(())
+    }
+}
+
+pub(crate) mod msg {
+    use super::*;
+
+    pub fn serialize<S: Serializer, Msg: MsgResolver>(
+        msg: &Option<Msg>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        msg.as_ref().and_then(|m| m.msg_id()).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>, Msg: MsgResolver>(
+        deserializer: D,
+    ) -> Result<Option<Msg>, D::Error> {
+        match Option::<String>::deserialize(deserializer)? {
+            Some(id) => Msg::resolve_msg(&id)
+                .map(Some)
+                .ok_or_else(|| D::Error::custom(format!("unknown message '{id}'"))),
+            None => Ok(None),
+        }
+    }
+}
+
+pub(crate) mod rope {
+    use ropey::Rope;
+
+    use super::*;
+
+    pub fn serialize<S: Serializer>(rope: &Rope, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(rope)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Rope, D::Error> {
+        Ok(Rope::from_str(&String::deserialize(deserializer)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZero;
+
+    use crate::{
+        colors::Colors,
+        styles::{Image, Value},
+        text::TextRepr,
+        ui, Element, EventListener, Gui, ImageData, PseudoState,
+    };
+
+    use serde::de::DeserializeSeed;
+
+    use super::{ElementTree, ImageSeed};
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Named(String);
+
+    impl ImageData for Named {
+        fn get_size(&self) -> (u32, u32) {
+            (1, 1)
+        }
+
+        fn image_id(&self) -> Option<String> {
+            Some(self.0.clone())
+        }
+    }
+
+    #[test]
+    fn subtree_roundtrip() {
+        let mut gui: Gui<u32> = Gui::new((NonZero::new(800).unwrap(), NonZero::new(600).unwrap()));
+        let tree = ui!(&mut gui, {
+            label: "root",
+            styles { color: Colors::BLACK },
+            children [
+                {
+                    label: "button",
+                    styles { width: Value::Px(120.0), text: Some(TextRepr::new_editor("hi")) },
+                    events [EventListener::new(crate::ElemEventTypes::Click).with_msg(7)],
+                },
+            ],
+        });
+        let json = serde_json::to_string(&gui.snapshot_subtree(tree.root).unwrap()).unwrap();
+        let snapshot: ElementTree<u32, ()> = serde_json::from_str(&json).unwrap();
+
+        let mut other: Gui<u32> = Gui::new((NonZero::new(800).unwrap(), NonZero::new(600).unwrap()));
+        let root = other.restore_subtree(snapshot);
+        let button = *other.get_element(root).unwrap().child(0).unwrap();
+        let button = other.get_element(button).unwrap();
+        assert_eq!(button.label.as_deref(), Some("button"));
+        assert_eq!(button.events.click[0].msg, Some(7));
+        let text = button.styles().text.get().as_ref().unwrap();
+        assert_eq!(text.text.text.to_string(), "hi");
+        assert!(text.variant.editor().is_some());
+    }
+
+    #[test]
+    fn image_resolver() {
+        let mut gui: Gui<(), Named> =
+            Gui::new((NonZero::new(800).unwrap(), NonZero::new(600).unwrap()));
+        let mut element = Element::default();
+        element
+            .styles_mut()
+            .image
+            .set(Some(Image::new(Named("logo".into()))));
+        element.state_style_mut(PseudoState::Hover).image =
+            Some(Some(Image::new(Named("hover".into()))));
+        let key = gui.add_element(element);
+        let json = serde_json::to_string(&gui.snapshot_subtree(key).unwrap()).unwrap();
+
+        assert!(serde_json::from_str::<ElementTree<(), Named>>(&json).is_err());
+        let mut de = serde_json::Deserializer::from_str(&json);
+        let seed: ImageSeed<(), Named, _> =
+            ImageSeed::new(|id: &str| Some(Named(format!("resolved {id}"))));
+        let snapshot = seed.deserialize(&mut de).unwrap();
+        let image = snapshot.element.styles().image.get().as_ref().unwrap();
+        assert_eq!(image.data, Named("resolved logo".into()));
+        let hover = &snapshot.element.state_styles[0].1;
+        assert_eq!(
+            hover.image.as_ref().unwrap().as_ref().unwrap().data,
+            Named("resolved hover".into())
+        );
+
+        let mut de = serde_json::Deserializer::from_str(&json);
+        let seed: ImageSeed<(), Named, _> = ImageSeed::new(|_: &str| None);
+        assert!(seed.deserialize(&mut de).is_err());
+    }
+}
@@ -1,13 +1,24 @@
-use std::fmt::Debug;
+use std::{
+    fmt::Debug,
+    ops::{Add, Div, Mul, Neg, Rem, Sub},
+};
 
 use crate::{
+    animation::Transition,
+    element::ShadowInstance,
     rich_text::Text,
     text::{FontIdx, TextRepr, DEFAULT_FONT_SIZE},
-    variables::{VarKey, Variables},
+    variables::{FromVarValue, VarKey, Variables},
+    colors::ColorSpace,
     Colors, Vector,
 };
 
 #[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound = "", default)
+)]
 pub struct Styles<Img: Clone + ImageData> {
     /// Width of the element
     pub width: StyleComponent<Value>,
@@ -21,26 +32,37 @@ pub struct Styles<Img: Clone + ImageData> {
     pub max_height: StyleComponent<Option<Value>>,
     /// Minimum height of the element
     pub min_height: StyleComponent<Option<Value>>,
-    /// Gap between the element and its container
-    pub padding: StyleComponent<Value>,
-    /// Gap between the element and its children
-    pub margin: StyleComponent<Option<Value>>,
+    /// Gap between each side of the element and its container
+    ///
+    /// Sides are measured in the frame of the container.
+    pub padding: StyleComponent<Sides>,
+    /// Gap between each side of the element and its children
+    pub margin: StyleComponent<Sides>,
+    /// Border drawn inside the edges of the element
+    ///
+    /// Children are placed inside both the border and the margin.
+    pub border: StyleComponent<Border>,
+    /// Line drawn around the element, does not affect the layout
+    pub outline: StyleComponent<Option<Outline>>,
     /// Color of the element
     pub color: StyleComponent<Colors>,
     /// Rotation of the element
     pub rotation: StyleComponent<Rotation>,
-    /// Round edges
+    /// Round corners
     ///
-    /// Describes the radius of edge circle
-    pub round: StyleComponent<Option<Value>>,
+    /// Describes the radius of each corner and the shape of its curve
+    pub round: StyleComponent<Corners>,
     /// Shadow
     ///
-    /// Describes how far from the element will be rendered shadow
+    /// Describes how far from the element will be rendered shadow, drawn as the
+    /// first entry of `shadows` in the color of the element. Prefer `shadows`.
     pub shadow: StyleComponent<Option<Value>>,
-    /// Shadow
-    ///
-    /// Describes how far from the element will be rendered shadow
+    /// Opacity of the `shadow`
     pub shadow_alpha: StyleComponent<f32>,
+    /// Shadows drawn in the order of the list
+    ///
+    /// Outer shadows are drawn under the element, inset shadows over its background.
+    pub shadows: StyleComponent<Vec<Shadow>>,
     /// Overall opacity of element
     pub alpha: StyleComponent<f32>,
     /// Position of the Element
@@ -55,10 +77,17 @@ pub struct Styles<Img: Clone + ImageData> {
     pub grad_linear: StyleComponent<Option<Gradient>>,
     /// Radial gradient
     pub grad_radial: StyleComponent<Option<Gradient>>,
+    /// Conic gradient
+    ///
+    /// Sweeps around `p1` clockwise, starting in the direction of `p2`.
+    pub grad_conic: StyleComponent<Option<Gradient>>,
     /// Image
     ///
     /// Images are not part of Rugui2 API, see documentation
     /// of your drawing layer to learn about their Images
+    ///
+    /// Written by `snapshot::ElementTree` instead of the styles
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub image: StyleComponent<Option<Image<Img>>>,
     /// Image tint
     ///
@@ -79,11 +108,26 @@ pub struct Styles<Img: Clone + ImageData> {
     pub line_height: StyleComponent<LineHeight>,
     pub font_color: StyleComponent<Colors>,
     pub text_align: StyleComponent<TextAlign>,
+    /// Width of the box the text is shaped in
+    ///
+    /// Defaults to the width of the element. Text of elements whose width
+    /// resolves from `Container::Content` is not wrapped unless this is set.
     pub text_box_width: StyleComponent<Option<Value>>,
+    /// Height of the box the text is shaped in
+    ///
+    /// Defaults to the height of the element
     pub text_box_height: StyleComponent<Option<Value>>,
+    /// How the children of the element are placed
+    ///
+    /// Defaults to `Layout::Free`
+    pub layout: StyleComponent<Layout>,
+    /// Share of the free space the element takes inside a flex container
+    pub flex_grow: StyleComponent<f32>,
+    /// Share of the missing space the element gives up inside a flex container
+    pub flex_shrink: StyleComponent<f32>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Style {
     Width,
     MaxWidth,
@@ -96,11 +140,13 @@ pub enum Style {
     Round,
     Shadow,
     ShadowAlpha,
+    Shadows,
     Alpha,
     Center,
     Align,
     GradLinear,
     GradRadial,
+    GradConic,
     Image,
     ImageTint,
     ScrollY,
@@ -108,6 +154,8 @@ pub enum Style {
     Overflow,
     Padding,
     Margin,
+    Border,
+    Outline,
     Text,
     FontSize,
     FontIdx,
@@ -118,9 +166,13 @@ pub enum Style {
     TextBoxWidth,
     TextBoxHeight,
     RichText,
+    Layout,
+    FlexGrow,
+    FlexShrink,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TextWrap {
     #[default]
     Wrap,
@@ -128,6 +180,7 @@ pub enum TextWrap {
 }
 
 #[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum LineHeight {
     #[default]
     Auto,
@@ -136,12 +189,14 @@ pub enum LineHeight {
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Overflow {
     Shown,
     Hidden,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TextAlign {
     #[default]
     Left,
@@ -150,21 +205,246 @@ pub enum TextAlign {
     Portion(Portion),
 }
 
+/// Placement of the children of an element
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Layout {
+    /// Every child is placed by its own `position` and `origin`
+    #[default]
+    Free,
+    /// Children are stacked along an axis, their `position` and `origin`
+    /// are relative to the slot assigned by the layout
+    Flex(Flex),
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Flex {
+    pub direction: FlexDirection,
+    /// Space between children and between wrapped lines
+    pub gap: Value,
+    /// Distribution of the free space along the main axis
+    pub justify: FlexJustify,
+    /// Placement of the children along the cross axis
+    pub align: FlexAlign,
+    /// Children that do not fit are moved to a new line
+    pub wrap: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FlexDirection {
+    #[default]
+    Row,
+    Column,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FlexJustify {
+    #[default]
+    Start,
+    Center,
+    End,
+    SpaceBetween,
+    SpaceAround,
+    SpaceEvenly,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FlexAlign {
+    Start,
+    Center,
+    End,
+    #[default]
+    Stretch,
+}
+
+impl Default for Flex {
+    fn default() -> Self {
+        Self {
+            direction: FlexDirection::Row,
+            gap: Value::Zero,
+            justify: FlexJustify::Start,
+            align: FlexAlign::Stretch,
+            wrap: false,
+        }
+    }
+}
+
+impl Flex {
+    pub fn row() -> Self {
+        Self::default()
+    }
+
+    pub fn column() -> Self {
+        Self {
+            direction: FlexDirection::Column,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_gap(mut self, gap: Value) -> Self {
+        self.gap = gap;
+        self
+    }
+
+    pub fn with_justify(mut self, justify: FlexJustify) -> Self {
+        self.justify = justify;
+        self
+    }
+
+    pub fn with_align(mut self, align: FlexAlign) -> Self {
+        self.align = align;
+        self
+    }
+
+    pub fn with_wrap(mut self, wrap: bool) -> Self {
+        self.wrap = wrap;
+        self
+    }
+}
+
 #[derive(Clone)]
 pub struct Image<Img: Clone + ImageData> {
     pub data: Img,
+    /// How the image is scaled into the element, ignored with `slice`
+    pub fit: ImageFit,
+    /// Position of an image that does not cover the element
+    ///
+    /// 0.0 is the left or top edge, 1.0 the right or bottom edge.
+    pub align: Vector,
+    /// Part of the image that is drawn in pixels: x, y, width, height
+    ///
+    /// Picks a sprite out of a sprite sheet, the whole image is drawn when `None`.
+    pub source: Option<[u32; 4]>,
+    /// Nine-slice insets in pixels of the source
+    ///
+    /// Corners keep their size, edges stretch along their side and the middle
+    /// stretches over the rest of the element.
+    pub slice: Option<Sides<f32>>,
+}
+
+impl<Img: Clone + ImageData> Image<Img> {
+    pub fn new(data: Img) -> Self {
+        Self {
+            data,
+            fit: ImageFit::Fill,
+            align: Vector(0.5, 0.5),
+            source: None,
+            slice: None,
+        }
+    }
+
+    pub fn with_fit(mut self, fit: ImageFit) -> Self {
+        self.fit = fit;
+        self
+    }
+
+    pub fn with_align(mut self, x: f32, y: f32) -> Self {
+        self.align = Vector(x, y);
+        self
+    }
+
+    pub fn with_source(mut self, x: u32, y: u32, width: u32, height: u32) -> Self {
+        self.source = Some([x, y, width, height]);
+        self
+    }
+
+    pub fn with_slice(mut self, slice: Sides<f32>) -> Self {
+        self.slice = Some(slice);
+        self
+    }
+
+    /// Size of the drawn part of the image in pixels
+    pub fn source_size(&self) -> Vector {
+        match self.source {
+            Some([_, _, w, h]) => Vector(w as f32, h as f32),
+            None => self.data.get_size().into(),
+        }
+    }
+
+    /// Source rectangle as texture coordinates: min x, min y, max x, max y
+    pub fn uv(&self) -> [f32; 4] {
+        let (w, h) = self.data.get_size();
+        match self.source {
+            Some([x, y, sw, sh]) if w > 0 && h > 0 => {
+                let (w, h) = (w as f32, h as f32);
+                [
+                    x as f32 / w,
+                    y as f32 / h,
+                    (x + sw) as f32 / w,
+                    (y + sh) as f32 / h,
+                ]
+            }
+            _ => [0.0, 0.0, 1.0, 1.0],
+        }
+    }
+
+    /// Rectangle the image is drawn to inside of an element of `size`
+    ///
+    /// Returns the offset from the top left corner of the element and the size.
+    pub fn fit_into(&self, size: Vector) -> (Vector, Vector) {
+        let source = self.source_size();
+        if self.slice.is_some() || source.0 <= 0.0 || source.1 <= 0.0 {
+            return (Vector::ZERO, size);
+        }
+        let contain = (size.0 / source.0).min(size.1 / source.1);
+        let scale = match self.fit {
+            ImageFit::Fill => return (Vector::ZERO, size),
+            ImageFit::Contain => contain,
+            ImageFit::Cover => (size.0 / source.0).max(size.1 / source.1),
+            ImageFit::None => 1.0,
+            ImageFit::ScaleDown => contain.min(1.0),
+        };
+        let drawn = Vector(source.0 * scale, source.1 * scale);
+        let offset = Vector(
+            (size.0 - drawn.0) * self.align.0,
+            (size.1 - drawn.1) * self.align.1,
+        );
+        (offset, drawn)
+    }
 }
 
 impl<Img: Clone + ImageData> std::fmt::Debug for Image<Img> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Image")
             .field("size", &self.data.get_size())
+            .field("fit", &self.fit)
+            .field("align", &self.align)
+            .field("source", &self.source)
+            .field("slice", &self.slice)
             .finish()
     }
 }
 
+/// How an image is scaled into its element, same as CSS `object-fit`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ImageFit {
+    /// Stretches over the whole element
+    #[default]
+    Fill,
+    /// Largest size that fits inside of the element, keeps the aspect ratio
+    Contain,
+    /// Smallest size that covers the element, keeps the aspect ratio
+    Cover,
+    /// Keeps the size of the image
+    None,
+    /// `None` or `Contain`, whichever is smaller
+    ScaleDown,
+}
+
 pub trait ImageData {
     fn get_size(&self) -> (u32, u32);
+
+    /// Name the image is serialized under, images without one are skipped
+    ///
+    /// See `snapshot::ImageSeed` for the way back.
+    fn image_id(&self) -> Option<String> {
+        None
+    }
 }
 
 impl ImageData for () {
@@ -173,20 +453,468 @@ impl ImageData for () {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Gradient {
     pub p1: (Position, Colors),
     pub p2: (Position, Colors),
+    /// Colors between the end points in ascending order, 0.0 is at `p1` and 1.0 at `p2`
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub stops: Vec<(f32, Colors)>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub spread: Spread,
+    /// Space the colors are blended in
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub space: ColorSpace,
 }
 
-#[derive(Debug, Clone)]
+/// How a gradient continues past its end points
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Spread {
+    /// Colors of the end points continue
+    #[default]
+    Pad,
+    /// Starts over from `p1`
+    Repeat,
+    /// Goes back and forth between the end points
+    Reflect,
+}
+
+impl Gradient {
+    pub fn new(p1: (Position, Colors), p2: (Position, Colors)) -> Self {
+        Self {
+            p1,
+            p2,
+            stops: Vec::new(),
+            spread: Spread::Pad,
+            space: ColorSpace::Srgb,
+        }
+    }
+
+    /// Adds a color stop, `offset` goes from 0.0 at `p1` to 1.0 at `p2`
+    pub fn with_stop(mut self, offset: f32, color: Colors) -> Self {
+        let offset = offset.clamp(0.0, 1.0);
+        let idx = self.stops.partition_point(|(o, _)| *o <= offset);
+        self.stops.insert(idx, (offset, color));
+        self
+    }
+
+    pub fn with_spread(mut self, spread: Spread) -> Self {
+        self.spread = spread;
+        self
+    }
+
+    pub fn with_space(mut self, space: ColorSpace) -> Self {
+        self.space = space;
+        self
+    }
+
+    /// Color at `t` between the end points, `t` is clamped
+    ///
+    /// `first` and `last` are the colors of `p1` and `p2`, which are resolved
+    /// separately so that they can be transitioned.
+    pub fn sample(&self, first: [f32; 4], last: [f32; 4], t: f32) -> [f32; 4] {
+        let t = t.clamp(0.0, 1.0);
+        let stops = self.stops.iter().map(|(o, c)| (o.clamp(0.0, 1.0), (*c).into()));
+        let mut prev = (0.0, first);
+        for (offset, color) in stops.chain(std::iter::once((1.0, last))) {
+            let offset = offset.max(prev.0);
+            if t <= offset {
+                let span = offset - prev.0;
+                let t = if span > 0.0 { (t - prev.0) / span } else { 1.0 };
+                return self.space.mix(prev.1, color, t);
+            }
+            prev = (offset, color);
+        }
+        last
+    }
+}
+
+/// Border drawn inside the edges of an element
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Border {
+    /// Width of each side
+    pub width: Sides,
+    pub color: Colors,
+    /// Linear gradient drawn instead of `color`
+    pub gradient: Option<Gradient>,
+    /// Solid when `None`
+    pub dash: Option<Dash>,
+}
+
+/// Dashes of a border, measured along each side from its top or left end
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Dash {
+    pub length: Value,
+    pub gap: Value,
+}
+
+/// Line drawn around an element
+///
+/// Follows the rounded corners of the element, does not affect the layout.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Outline {
+    pub width: Value,
+    /// Gap between the element and the outline
+    pub offset: Value,
+    pub color: Colors,
+}
+
+/// Shadow of an element, follows its rounded corners
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Shadow {
+    /// Horizontal offset, measured in the rotated frame of the element
+    pub x: Value,
+    /// Vertical offset, measured in the rotated frame of the element
+    pub y: Value,
+    /// Distance over which the shadow fades out
+    pub blur: Value,
+    /// Grows the shadow on every side, shrinks it when negative
+    pub spread: Value,
+    pub color: Colors,
+    /// Drawn inside the element instead of around it
+    pub inset: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Position {
     pub width: Value,
     pub height: Value,
     pub container: Container,
 }
 
+/// Values of the four sides of an element
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Sides<T = Value> {
+    pub top: T,
+    pub right: T,
+    pub bottom: T,
+    pub left: T,
+}
+
+impl<T> Sides<T> {
+    pub fn new(top: T, right: T, bottom: T, left: T) -> Self {
+        Self {
+            top,
+            right,
+            bottom,
+            left,
+        }
+    }
+
+    pub fn all(v: T) -> Self
+    where
+        T: Clone,
+    {
+        Self::new(v.clone(), v.clone(), v.clone(), v)
+    }
+
+    /// Same top and bottom, same left and right
+    pub fn symmetric(vertical: T, horizontal: T) -> Self
+    where
+        T: Clone,
+    {
+        Self::new(vertical.clone(), horizontal.clone(), vertical, horizontal)
+    }
+
+    pub fn map<U>(&self, mut f: impl FnMut(&T) -> U) -> Sides<U> {
+        Sides::new(f(&self.top), f(&self.right), f(&self.bottom), f(&self.left))
+    }
+
+    /// Top, right, bottom and left side
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        [&self.top, &self.right, &self.bottom, &self.left].into_iter()
+    }
+}
+
+impl Sides<Value> {
+    pub const ZERO: Self = Self {
+        top: Value::Zero,
+        right: Value::Zero,
+        bottom: Value::Zero,
+        left: Value::Zero,
+    };
+
+    pub(crate) fn calc(&self, containers: &Containers, variables: &mut Variables) -> Sides<f32> {
+        self.map(|v| v.calc(containers, variables))
+    }
+}
+
+impl Sides<f32> {
+    /// Sum of the left and right side, sum of the top and bottom side
+    pub fn size(&self) -> Vector {
+        Vector(self.left + self.right, self.top + self.bottom)
+    }
+
+    /// Shift of the center of a box shrunk by the sides
+    pub fn offset(&self) -> Vector {
+        Vector(self.left - self.right, self.top - self.bottom) * 0.5
+    }
+}
+
+impl Add for Sides<f32> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Sides::new(
+            self.top + rhs.top,
+            self.right + rhs.right,
+            self.bottom + rhs.bottom,
+            self.left + rhs.left,
+        )
+    }
+}
+
+impl Border {
+    pub fn new(width: impl Into<Sides>, color: Colors) -> Self {
+        Self {
+            width: width.into(),
+            color,
+            gradient: None,
+            dash: None,
+        }
+    }
+
+    pub fn with_gradient(mut self, gradient: Gradient) -> Self {
+        self.gradient = Some(gradient);
+        self
+    }
+
+    pub fn with_dash(mut self, length: Value, gap: Value) -> Self {
+        self.dash = Some(Dash { length, gap });
+        self
+    }
+}
+
+impl Default for Border {
+    fn default() -> Self {
+        Self::new(Sides::ZERO, Colors::BLACK)
+    }
+}
+
+impl Outline {
+    pub fn new(width: Value, offset: Value, color: Colors) -> Self {
+        Self {
+            width,
+            offset,
+            color,
+        }
+    }
+}
+
+impl Shadow {
+    pub fn new(x: Value, y: Value, blur: Value, color: Colors) -> Self {
+        Self {
+            x,
+            y,
+            blur,
+            spread: Value::Zero,
+            color,
+            inset: false,
+        }
+    }
+
+    pub fn with_spread(mut self, spread: Value) -> Self {
+        self.spread = spread;
+        self
+    }
+
+    pub fn inset(mut self) -> Self {
+        self.inset = true;
+        self
+    }
+
+    pub(crate) fn calc(
+        &self,
+        containers: &Containers,
+        variables: &mut Variables,
+    ) -> ShadowInstance {
+        ShadowInstance {
+            offset: Vector(
+                self.x.calc(containers, variables),
+                self.y.calc(containers, variables),
+            ),
+            blur: self.blur.calc(containers, variables).max(0.0),
+            spread: self.spread.calc(containers, variables),
+            color: self.color.into(),
+            inset: self.inset,
+        }
+    }
+
+    fn values(&self) -> [&Value; 4] {
+        [&self.x, &self.y, &self.blur, &self.spread]
+    }
+}
+
+impl Default for Sides<Value> {
+    fn default() -> Self {
+        Self::ZERO
+    }
+}
+
+impl Default for Sides<f32> {
+    fn default() -> Self {
+        Self::all(0.0)
+    }
+}
+
+impl From<Value> for Sides<Value> {
+    fn from(value: Value) -> Self {
+        Self::all(value)
+    }
+}
+
+/// Radii of the four corners of an element
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Corners<T = Value> {
+    pub top_left: T,
+    pub top_right: T,
+    pub bottom_right: T,
+    pub bottom_left: T,
+    /// Curve of every corner
+    pub shape: CornerShape,
+}
+
+/// Curve that connects two sides of an element
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CornerShape {
+    /// Quarter of a circle
+    #[default]
+    Round,
+    /// Superellipse with the exponent of 4
+    Squircle,
+    /// Superellipse with the given exponent
+    ///
+    /// 2 is a circle, larger values are closer to a square corner,
+    /// values between 0 and 1 curve inwards.
+    Superellipse(f32),
+}
+
+impl CornerShape {
+    /// Exponent of the superellipse
+    pub fn exponent(&self) -> f32 {
+        match self {
+            Self::Round => 2.0,
+            Self::Squircle => 4.0,
+            Self::Superellipse(n) => n.max(0.1),
+        }
+    }
+}
+
+impl<T> Corners<T> {
+    pub fn new(top_left: T, top_right: T, bottom_right: T, bottom_left: T) -> Self {
+        Self {
+            top_left,
+            top_right,
+            bottom_right,
+            bottom_left,
+            shape: CornerShape::Round,
+        }
+    }
+
+    pub fn all(v: T) -> Self
+    where
+        T: Clone,
+    {
+        Self::new(v.clone(), v.clone(), v.clone(), v)
+    }
+
+    pub fn with_shape(mut self, shape: CornerShape) -> Self {
+        self.shape = shape;
+        self
+    }
+
+    pub fn map<U>(&self, mut f: impl FnMut(&T) -> U) -> Corners<U> {
+        Corners::new(
+            f(&self.top_left),
+            f(&self.top_right),
+            f(&self.bottom_right),
+            f(&self.bottom_left),
+        )
+        .with_shape(self.shape)
+    }
+
+    /// Top left, top right, bottom right and bottom left corner
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        [
+            &self.top_left,
+            &self.top_right,
+            &self.bottom_right,
+            &self.bottom_left,
+        ]
+        .into_iter()
+    }
+}
+
+impl Corners<Value> {
+    pub const ZERO: Self = Self {
+        top_left: Value::Zero,
+        top_right: Value::Zero,
+        bottom_right: Value::Zero,
+        bottom_left: Value::Zero,
+        shape: CornerShape::Round,
+    };
+
+    pub(crate) fn calc(&self, containers: &Containers, variables: &mut Variables) -> Corners<f32> {
+        self.map(|v| v.calc(containers, variables).max(0.0))
+    }
+}
+
+impl Corners<f32> {
+    /// Signed distance of `point` from the edge of a box with these corners
+    ///
+    /// `point` is relative to the center of the box and `half_size` is half of its size,
+    /// the distance is negative inside.
+    pub fn distance(&self, point: Vector, half_size: Vector) -> f32 {
+        let radius = match (point.0 > 0.0, point.1 > 0.0) {
+            (false, false) => self.top_left,
+            (true, false) => self.top_right,
+            (true, true) => self.bottom_right,
+            (false, true) => self.bottom_left,
+        };
+        let radius = radius.min(half_size.0).min(half_size.1);
+        let qx = point.0.abs() - half_size.0 + radius;
+        let qy = point.1.abs() - half_size.1 + radius;
+        let n = self.shape.exponent();
+        let outside = (qx.max(0.0).powf(n) + qy.max(0.0).powf(n)).powf(1.0 / n);
+        outside + qx.max(qy).min(0.0) - radius
+    }
+
+    /// Whether `point` relative to the center of a box lies inside of it
+    pub fn contains(&self, point: Vector, half_size: Vector) -> bool {
+        self.distance(point, half_size) <= 0.0
+    }
+}
+
+impl Default for Corners<Value> {
+    fn default() -> Self {
+        Self::ZERO
+    }
+}
+
+impl Default for Corners<f32> {
+    fn default() -> Self {
+        Self::all(0.0)
+    }
+}
+
+impl From<Value> for Corners<Value> {
+    fn from(value: Value) -> Self {
+        Self::all(value)
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Portion {
     Full,
     Half,
@@ -196,7 +924,8 @@ pub enum Portion {
     Div(f32),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Value {
     Px(f32),
     Time,
@@ -212,9 +941,63 @@ pub enum Value {
     Negative(Box<Value>),
     Sin(Box<Value>),
     Cos(Box<Value>),
+    Min(Box<(Value, Value)>),
+    Max(Box<(Value, Value)>),
+    /// Value, lower and upper bound, the lower bound wins when they cross
+    Clamp(Box<(Value, Value, Value)>),
+    /// From, to and the progress between them
+    Lerp(Box<(Value, Value, Value)>),
+    Abs(Box<Value>),
+    Floor(Box<Value>),
+    Round(Box<Value>),
+    Pow(Box<(Value, Value)>),
+    Sqrt(Box<Value>),
+    /// Angle in radians of the point (x, y), stored as (y, x)
+    Atan2(Box<(Value, Value)>),
+    /// First value if the condition holds, the second one otherwise
+    If(Box<(Condition, Value, Value)>),
     Zero,
 }
 
+/// Comparison of two values, see `Value::If`
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Condition {
+    pub left: Value,
+    pub cmp: Compare,
+    pub right: Value,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Compare {
+    Less,
+    LessEq,
+    Greater,
+    GreaterEq,
+    Equal,
+    NotEqual,
+}
+
+impl Condition {
+    pub fn new(left: Value, cmp: Compare, right: Value) -> Self {
+        Self { left, cmp, right }
+    }
+
+    pub(crate) fn check(&self, containers: &Containers, variables: &mut Variables) -> bool {
+        let left = self.left.calc(containers, variables);
+        let right = self.right.calc(containers, variables);
+        match self.cmp {
+            Compare::Less => left < right,
+            Compare::LessEq => left <= right,
+            Compare::Greater => left > right,
+            Compare::GreaterEq => left >= right,
+            Compare::Equal => left == right,
+            Compare::NotEqual => left != right,
+        }
+    }
+}
+
 impl Value {
     pub fn c_width(portion: Portion) -> Value {
         Value::Value(Container::Container, Values::Width, portion)
@@ -252,6 +1035,18 @@ impl Value {
         Value::Value(Container::ViewPort, value, portion)
     }
 
+    pub fn content_width(portion: Portion) -> Value {
+        Value::Value(Container::Content, Values::Width, portion)
+    }
+
+    pub fn content_height(portion: Portion) -> Value {
+        Value::Value(Container::Content, Values::Height, portion)
+    }
+
+    pub fn content(value: Values, portion: Portion) -> Value {
+        Value::Value(Container::Content, value, portion)
+    }
+
     pub fn set_variable(key: VarKey, value: Value) -> Value {
         Value::SetVariable(key, Box::new(value))
     }
@@ -303,9 +1098,88 @@ impl Value {
     pub fn scalar(value: f32) -> Value {
         Value::Px(value)
     }
+
+    pub fn min(left: Value, right: Value) -> Value {
+        Value::Min(Box::new((left, right)))
+    }
+
+    pub fn max(left: Value, right: Value) -> Value {
+        Value::Max(Box::new((left, right)))
+    }
+
+    pub fn clamp(value: Value, min: Value, max: Value) -> Value {
+        Value::Clamp(Box::new((value, min, max)))
+    }
+
+    pub fn lerp(from: Value, to: Value, t: Value) -> Value {
+        Value::Lerp(Box::new((from, to, t)))
+    }
+
+    pub fn abs(value: Value) -> Value {
+        Value::Abs(Box::new(value))
+    }
+
+    pub fn floor(value: Value) -> Value {
+        Value::Floor(Box::new(value))
+    }
+
+    pub fn round(value: Value) -> Value {
+        Value::Round(Box::new(value))
+    }
+
+    pub fn pow(base: Value, exp: Value) -> Value {
+        Value::Pow(Box::new((base, exp)))
+    }
+
+    pub fn sqrt(value: Value) -> Value {
+        Value::Sqrt(Box::new(value))
+    }
+
+    pub fn atan2(y: Value, x: Value) -> Value {
+        Value::Atan2(Box::new((y, x)))
+    }
+
+    pub fn if_else(cond: Condition, then: Value, otherwise: Value) -> Value {
+        Value::If(Box::new((cond, then, otherwise)))
+    }
 }
 
-#[derive(Debug, Clone)]
+impl From<f32> for Value {
+    fn from(value: f32) -> Self {
+        Value::Px(value)
+    }
+}
+
+macro_rules! value_ops {
+    ($($op: ident, $fn: ident, $variant: ident;)*) => {
+        $(impl<T: Into<Value>> $op<T> for Value {
+            type Output = Value;
+
+            fn $fn(self, rhs: T) -> Value {
+                Value::$variant(Box::new((self, rhs.into())))
+            }
+        })*
+    };
+}
+
+value_ops!(
+    Add, add, Add;
+    Sub, sub, Sub;
+    Mul, mul, Mul;
+    Div, div, Div;
+    Rem, rem, Mod;
+);
+
+impl Neg for Value {
+    type Output = Value;
+
+    fn neg(self) -> Value {
+        Value::Negative(Box::new(self))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Values {
     Width,
     Height,
@@ -316,19 +1190,40 @@ pub enum Values {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct StyleComponent<T: Debug + Clone> {
     val: T,
+    #[cfg_attr(feature = "serde", serde(skip, default = "crate::snapshot::dirty"))]
     dirty: bool,
     dynamic: bool,
+    #[cfg_attr(feature = "serde", serde(default))]
+    transition: Option<Box<Transition>>,
+    /// Value was set while a transition is configured
+    #[cfg_attr(feature = "serde", serde(skip))]
+    started: bool,
+    #[cfg_attr(feature = "serde", serde(default))]
+    binding: Option<Binding>,
 }
 
-#[derive(Debug, Clone, Default)]
+/// Variable a style follows, see `StyleComponent::bind`
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Binding {
+    key: VarKey,
+    /// `Variables::stamp` of the last applied value, `None` until the first one
+    #[cfg_attr(feature = "serde", serde(skip))]
+    applied: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Rotation {
     pub rot: Rotations,
     pub cont: Container,
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Rotations {
     #[default]
     None,
@@ -338,13 +1233,36 @@ pub enum Rotations {
     CalcRad(Value),
 }
 
-#[derive(Debug, Copy, Clone, Default)]
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Container {
     ViewPort,
     #[default]
     Container,
     This,
     Image,
+    /// Intrinsic size of the element
+    ///
+    /// Extent of its shaped text and of the union of its children, measured
+    /// during the previous layout pass. Padding is not included.
+    Content,
+}
+
+/// External inputs that the styles of an element read from
+///
+/// Used by `Gui::update` to decide which elements have to be laid out again.
+#[derive(Debug, Clone, Default)]
+pub struct Dependencies {
+    /// Reads `Value::Time`
+    pub time: bool,
+    /// Reads the viewport container
+    pub viewport: bool,
+    /// Reads the content of the element
+    pub content: bool,
+    /// Has a dynamic `StyleComponent`
+    pub dynamic: bool,
+    /// Variables read by `Value::Variable`
+    pub variables: Vec<VarKey>,
 }
 
 pub(crate) struct Containers<'a> {
@@ -352,6 +1270,7 @@ pub(crate) struct Containers<'a> {
     pub vp: &'a crate::element::Container,
     pub this: &'a crate::element::Container,
     pub image: &'a Vector,
+    pub content: &'a Vector,
     pub time: f32,
 }
 
@@ -391,9 +1310,10 @@ impl<Tex: ImageData + Clone> Default for Styles<Tex> {
                 rot: Rotations::None,
                 cont: Container::Container,
             }),
-            round: opt_val(None),
+            round: StyleComponent::new(Corners::ZERO),
             shadow: opt_val(None),
             shadow_alpha: float(1.0),
+            shadows: StyleComponent::new(Vec::new()),
             alpha: float(1.0),
             position: pos(Position {
                 width: Value::Value(Container::Container, Values::Width, Portion::Half),
@@ -406,14 +1326,17 @@ impl<Tex: ImageData + Clone> Default for Styles<Tex> {
                 container: Container::This,
             }),
             grad_linear: opt_grad.clone(),
-            grad_radial: opt_grad,
+            grad_radial: opt_grad.clone(),
+            grad_conic: opt_grad,
             image: opt_img,
             image_tint: color(Colors::ALPHA_FULL),
             scroll_y: val(Value::Zero),
             scroll_x: val(Value::Zero),
             overflow: overflow(Overflow::Shown),
-            padding: val(Value::Zero),
-            margin: opt_val(None),
+            padding: StyleComponent::new(Sides::ZERO),
+            margin: StyleComponent::new(Sides::ZERO),
+            border: StyleComponent::new(Border::default()),
+            outline: StyleComponent::new(None),
             text,
             font_size: val(Value::Px(DEFAULT_FONT_SIZE)),
             font: font_idx,
@@ -424,6 +1347,9 @@ impl<Tex: ImageData + Clone> Default for Styles<Tex> {
             text_box_width: opt_val(None),
             text_box_height: opt_val(None),
             rich_text: StyleComponent::new(None),
+            layout: StyleComponent::new(Layout::Free),
+            flex_grow: float(0.0),
+            flex_shrink: float(1.0),
         }
     }
 }
@@ -437,6 +1363,7 @@ impl Value {
                     Container::ViewPort => containers.vp.size,
                     Container::This => containers.this.size,
                     Container::Image => *containers.image,
+                    Container::Content => *containers.content,
                 };
                 let p = p.calc();
                 let v = match v {
@@ -451,10 +1378,10 @@ impl Value {
             }
             Self::Px(px) => *px,
             Self::Zero => 0.0,
-            Self::Variable(key) => variables.get(*key).expect("Variable key should be valid"),
+            Self::Variable(key) => variables.read(*key),
             Self::SetVariable(key, value) => {
                 let val = value.calc(containers, variables);
-                variables.set(*key, val).unwrap()
+                variables.write(*key, val)
             }
             Self::Time => containers.time,
             Self::Mul(v) => {
@@ -485,8 +1412,379 @@ impl Value {
                 value
             }
             Self::Negative(v) => -v.calc(containers, variables),
+            Self::Min(v) => v.0.calc(containers, variables).min(v.1.calc(containers, variables)),
+            Self::Max(v) => v.0.calc(containers, variables).max(v.1.calc(containers, variables)),
+            Self::Clamp(v) => {
+                let value = v.0.calc(containers, variables);
+                let min = v.1.calc(containers, variables);
+                let max = v.2.calc(containers, variables);
+                value.min(max).max(min)
+            }
+            Self::Lerp(v) => {
+                let from = v.0.calc(containers, variables);
+                let to = v.1.calc(containers, variables);
+                from + (to - from) * v.2.calc(containers, variables)
+            }
+            Self::Abs(v) => v.calc(containers, variables).abs(),
+            Self::Floor(v) => v.calc(containers, variables).floor(),
+            Self::Round(v) => v.calc(containers, variables).round(),
+            Self::Pow(v) => v.0.calc(containers, variables).powf(v.1.calc(containers, variables)),
+            Self::Sqrt(v) => v.calc(containers, variables).sqrt(),
+            Self::Atan2(v) => v.0.calc(containers, variables).atan2(v.1.calc(containers, variables)),
+            Self::If(v) => {
+                if v.0.check(containers, variables) {
+                    v.1.calc(containers, variables)
+                } else {
+                    v.2.calc(containers, variables)
+                }
+            }
+        }
+    }
+}
+
+impl Value {
+    /// Collects everything this value reads from, except the containers
+    /// of the element and its parent
+    pub fn dependencies(&self, deps: &mut Dependencies) {
+        match self {
+            Self::Value(c, _, _) => deps.container(c),
+            Self::Px(_) | Self::Zero => (),
+            Self::Time => deps.time = true,
+            Self::Variable(key) => {
+                if !deps.variables.contains(key) {
+                    deps.variables.push(*key)
+                }
+            }
+            Self::SetVariable(_, v)
+            | Self::Debug(v, _)
+            | Self::Negative(v)
+            | Self::Sin(v)
+            | Self::Cos(v)
+            | Self::Abs(v)
+            | Self::Floor(v)
+            | Self::Round(v)
+            | Self::Sqrt(v) => v.dependencies(deps),
+            Self::Add(v)
+            | Self::Sub(v)
+            | Self::Mul(v)
+            | Self::Div(v)
+            | Self::Mod(v)
+            | Self::Min(v)
+            | Self::Max(v)
+            | Self::Pow(v)
+            | Self::Atan2(v) => {
+                v.0.dependencies(deps);
+                v.1.dependencies(deps);
+            }
+            Self::Clamp(v) | Self::Lerp(v) => {
+                v.0.dependencies(deps);
+                v.1.dependencies(deps);
+                v.2.dependencies(deps);
+            }
+            Self::If(v) => {
+                v.0.left.dependencies(deps);
+                v.0.right.dependencies(deps);
+                v.1.dependencies(deps);
+                v.2.dependencies(deps);
+            }
+        }
+    }
+}
+
+impl Dependencies {
+    fn container(&mut self, c: &Container) {
+        match c {
+            Container::ViewPort => self.viewport = true,
+            Container::Content => self.content = true,
+            _ => (),
+        }
+    }
+
+    fn position(&mut self, p: &Position) {
+        self.container(&p.container);
+        p.width.dependencies(self);
+        p.height.dependencies(self);
+    }
+
+    fn opt_value(&mut self, v: &Option<Value>) {
+        if let Some(v) = v {
+            v.dependencies(self)
         }
     }
+
+    fn gradient(&mut self, g: &Option<Gradient>) {
+        if let Some(g) = g {
+            self.position(&g.p1.0);
+            self.position(&g.p2.0);
+        }
+    }
+
+    /// Whether the dependencies can change without the element being edited
+    ///
+    /// Content is left out, `Gui::measure_content` reports its changes, and so are
+    /// the variables, `Gui` indexes the elements by the variables they depend on.
+    pub(crate) fn polled(&self) -> bool {
+        self.time || self.viewport || self.dynamic
+    }
+
+    /// Checks whether anything in the dependencies changed
+    pub(crate) fn changed(
+        &self,
+        time: bool,
+        viewport: bool,
+        variables: &Variables,
+        stamp: u64,
+    ) -> bool {
+        self.dynamic
+            || (self.time && time)
+            || (self.viewport && viewport)
+            || self
+                .variables
+                .iter()
+                .any(|v| variables.changed_since(*v, stamp))
+    }
+}
+
+impl Value {
+    /// Calls `f` with every value this one is computed from
+    pub(crate) fn for_each_child(&self, mut f: impl FnMut(&Value)) {
+        match self {
+            Self::Px(_) | Self::Zero | Self::Time | Self::Value(..) | Self::Variable(_) => (),
+            Self::SetVariable(_, v)
+            | Self::Debug(v, _)
+            | Self::Negative(v)
+            | Self::Sin(v)
+            | Self::Cos(v)
+            | Self::Abs(v)
+            | Self::Floor(v)
+            | Self::Round(v)
+            | Self::Sqrt(v) => f(v),
+            Self::Add(v)
+            | Self::Sub(v)
+            | Self::Mul(v)
+            | Self::Div(v)
+            | Self::Mod(v)
+            | Self::Min(v)
+            | Self::Max(v)
+            | Self::Pow(v)
+            | Self::Atan2(v) => {
+                f(&v.0);
+                f(&v.1);
+            }
+            Self::Clamp(v) | Self::Lerp(v) => {
+                f(&v.0);
+                f(&v.1);
+                f(&v.2);
+            }
+            Self::If(v) => {
+                f(&v.0.left);
+                f(&v.0.right);
+                f(&v.1);
+                f(&v.2);
+            }
+        }
+    }
+}
+
+impl<Img: Clone + ImageData> Styles<Img> {
+    /// Calls `f` with every `Value` of the styles, roughly in the order of the layout
+    pub(crate) fn for_each_value(&self, mut f: impl FnMut(Style, &Value)) {
+        let mut opt = |style, v: Option<&Value>| {
+            if let Some(v) = v {
+                f(style, v)
+            }
+        };
+        opt(Style::Width, Some(self.width.get()));
+        opt(Style::MaxWidth, self.max_width.get().as_ref());
+        opt(Style::MinWidth, self.min_width.get().as_ref());
+        opt(Style::Height, Some(self.height.get()));
+        opt(Style::MaxHeight, self.max_height.get().as_ref());
+        opt(Style::MinHeight, self.min_height.get().as_ref());
+        for side in self.padding.get().iter() {
+            opt(Style::Padding, Some(side));
+        }
+        for side in self.margin.get().iter() {
+            opt(Style::Margin, Some(side));
+        }
+        let border = self.border.get();
+        for side in border.width.iter() {
+            opt(Style::Border, Some(side));
+        }
+        if let Some(dash) = &border.dash {
+            opt(Style::Border, Some(&dash.length));
+            opt(Style::Border, Some(&dash.gap));
+        }
+        if let Some(grad) = &border.gradient {
+            for pos in [&grad.p1.0, &grad.p2.0] {
+                opt(Style::Border, Some(&pos.width));
+                opt(Style::Border, Some(&pos.height));
+            }
+        }
+        if let Some(outline) = self.outline.get() {
+            opt(Style::Outline, Some(&outline.width));
+            opt(Style::Outline, Some(&outline.offset));
+        }
+        for (style, pos) in [
+            (Style::Center, self.position.get()),
+            (Style::Align, self.origin.get()),
+        ] {
+            opt(style, Some(&pos.width));
+            opt(style, Some(&pos.height));
+        }
+        if let Rotations::CalcDeg(v) | Rotations::CalcRad(v) = &self.rotation.get().rot {
+            opt(Style::Rotation, Some(v));
+        }
+        for corner in self.round.get().iter() {
+            opt(Style::Round, Some(corner));
+        }
+        opt(Style::Shadow, self.shadow.get().as_ref());
+        for shadow in self.shadows.get() {
+            for v in shadow.values() {
+                opt(Style::Shadows, Some(v));
+            }
+        }
+        for (style, grad) in [
+            (Style::GradLinear, self.grad_linear.get()),
+            (Style::GradRadial, self.grad_radial.get()),
+            (Style::GradConic, self.grad_conic.get()),
+        ] {
+            if let Some(grad) = grad {
+                for pos in [&grad.p1.0, &grad.p2.0] {
+                    opt(style, Some(&pos.width));
+                    opt(style, Some(&pos.height));
+                }
+            }
+        }
+        opt(Style::ScrollY, Some(self.scroll_y.get()));
+        opt(Style::ScrollX, Some(self.scroll_x.get()));
+        opt(Style::FontSize, Some(self.font_size.get()));
+        opt(Style::TextBoxWidth, self.text_box_width.get().as_ref());
+        opt(Style::TextBoxHeight, self.text_box_height.get().as_ref());
+        if let Layout::Flex(flex) = self.layout.get() {
+            opt(Style::Layout, Some(&flex.gap));
+        }
+    }
+
+    /// Sets the bound styles whose variables changed, returns whether any did
+    pub(crate) fn apply_bindings(&mut self, variables: &Variables) -> bool {
+        let mut changed = false;
+        macro_rules! bound {
+            ($($style: ident),*) => {
+                $(changed |= self.$style.apply_binding(variables);)*
+            };
+        }
+        bound!(
+            width, max_width, min_width, height, max_height, min_height, padding, margin, border,
+            color, round, shadow, shadow_alpha, alpha, position, origin, image_tint, scroll_y,
+            scroll_x, text, font_size, font_color, text_box_width, text_box_height, flex_grow,
+            flex_shrink
+        );
+        changed
+    }
+
+    /// Collects the dependencies of all styles
+    pub fn dependencies(&self, deps: &mut Dependencies) {
+        macro_rules! dynamic {
+            ($($style: ident),*) => {
+                $(deps.dynamic |= self.$style.is_dynamic();)*
+            };
+        }
+        dynamic!(
+            width, max_width, min_width, height, max_height, min_height, padding, margin, border,
+            outline, color, rotation, round, shadow, shadow_alpha, shadows, alpha, position,
+            origin, grad_linear, grad_radial, grad_conic, image, image_tint, scroll_y, scroll_x,
+            overflow, rich_text, text, font_size, font, text_wrap, line_height, font_color,
+            text_align, text_box_width, text_box_height, layout, flex_grow, flex_shrink
+        );
+        macro_rules! bound {
+            ($($style: ident),*) => {
+                $(if let Some(key) = self.$style.binding() {
+                    if !deps.variables.contains(&key) {
+                        deps.variables.push(key);
+                    }
+                })*
+            };
+        }
+        bound!(
+            width, max_width, min_width, height, max_height, min_height, padding, margin, border,
+            color, round, shadow, shadow_alpha, alpha, position, origin, image_tint, scroll_y,
+            scroll_x, text, font_size, font_color, text_box_width, text_box_height, flex_grow,
+            flex_shrink
+        );
+        self.width.get().dependencies(deps);
+        self.height.get().dependencies(deps);
+        deps.opt_value(self.max_width.get());
+        deps.opt_value(self.min_width.get());
+        deps.opt_value(self.max_height.get());
+        deps.opt_value(self.min_height.get());
+        for sides in [self.padding.get(), self.margin.get(), &self.border.get().width] {
+            for side in sides.iter() {
+                side.dependencies(deps);
+            }
+        }
+        let border = self.border.get();
+        if let Some(dash) = &border.dash {
+            dash.length.dependencies(deps);
+            dash.gap.dependencies(deps);
+        }
+        deps.gradient(&border.gradient);
+        if let Some(outline) = self.outline.get() {
+            outline.width.dependencies(deps);
+            outline.offset.dependencies(deps);
+        }
+        let rotation = self.rotation.get();
+        deps.container(&rotation.cont);
+        match &rotation.rot {
+            Rotations::CalcDeg(v) | Rotations::CalcRad(v) => v.dependencies(deps),
+            _ => (),
+        }
+        for corner in self.round.get().iter() {
+            corner.dependencies(deps);
+        }
+        deps.opt_value(self.shadow.get());
+        for shadow in self.shadows.get() {
+            for v in shadow.values() {
+                v.dependencies(deps);
+            }
+        }
+        deps.position(self.position.get());
+        deps.position(self.origin.get());
+        deps.gradient(self.grad_linear.get());
+        deps.gradient(self.grad_radial.get());
+        deps.gradient(self.grad_conic.get());
+        self.scroll_y.get().dependencies(deps);
+        self.scroll_x.get().dependencies(deps);
+        self.font_size.get().dependencies(deps);
+        if let LineHeight::Value(v) = self.line_height.get() {
+            v.dependencies(deps);
+        }
+        deps.opt_value(self.text_box_width.get());
+        deps.opt_value(self.text_box_height.get());
+        if let Some(text) = self.rich_text.get() {
+            for section in &text.sections {
+                section.styles.font_size.get().dependencies(deps);
+                section.styles.left_pad.get().dependencies(deps);
+                section.styles.right_pad.get().dependencies(deps);
+            }
+        }
+        if let Layout::Flex(flex) = self.layout.get() {
+            flex.gap.dependencies(deps);
+        }
+    }
+}
+
+impl<Img: Clone + ImageData> Styles<Img> {
+    /// Checks whether the width resolves from the content of the element
+    ///
+    /// Text of such elements is measured without wrapping, unless
+    /// `text_box_width` gives it an explicit width.
+    pub(crate) fn fits_content_width(&self) -> bool {
+        let mut deps = Dependencies::default();
+        self.width.get().dependencies(&mut deps);
+        deps.opt_value(self.max_width.get());
+        deps.opt_value(self.min_width.get());
+        deps.content
+    }
 }
 
 impl TextAlign {
@@ -534,6 +1832,11 @@ impl Position {
                 size: *containers.image,
                 rotation: 0.0,
             },
+            Container::Content => &crate::element::Container {
+                pos: containers.container.pos,
+                size: *containers.content,
+                rotation: 0.0,
+            },
         };
 
         let pos = Vector::new(
@@ -554,6 +1857,11 @@ impl Position {
                 size: *containers.image,
                 rotation: containers.this.rotation,
             },
+            Container::Content => &crate::element::Container {
+                pos: containers.container.pos,
+                size: *containers.content,
+                rotation: containers.this.rotation,
+            },
         };
 
         let x = self.width.calc(containers, variables);
@@ -578,6 +1886,11 @@ impl Position {
                 size: *containers.image,
                 rotation: 0.0,
             },
+            Container::Content => &crate::element::Container {
+                pos: containers.container.pos,
+                size: *containers.content,
+                rotation: 0.0,
+            },
         };
         Vector::new(
             self.width.calc(containers, variables),
@@ -592,7 +1905,7 @@ impl Rotation {
             Container::Container => containers.container.rotation,
             Container::ViewPort => containers.vp.rotation,
             Container::This => containers.this.rotation,
-            Container::Image => 0.0,
+            Container::Image | Container::Content => 0.0,
         };
         match &self.rot {
             Rotations::None => c,
@@ -610,6 +1923,9 @@ impl<T: Debug + Clone> StyleComponent<T> {
             val: v,
             dirty: false,
             dynamic: false,
+            transition: None,
+            started: false,
+            binding: None,
         }
     }
 
@@ -622,15 +1938,46 @@ impl<T: Debug + Clone> StyleComponent<T> {
         &mut self.val
     }
 
+    /// Replaces the value, starting the transition if there is one and the value changed
     pub fn set(&mut self, val: T) {
         self.val = val;
         self.dirty = true;
+        self.started = self.transition.is_some();
+    }
+
+    pub fn transition(&self) -> Option<&Transition> {
+        self.transition.as_deref()
+    }
+
+    /// Animates the following changes made by `StyleComponent::set`
+    ///
+    /// Supported by `width`, `height`, `padding`, `round`, `color`, `alpha`, `shadow_alpha`,
+    /// `image_tint`, `rotation`, `position`, `origin`, the gradients and the color of `border`,
+    /// other styles change at once.
+    pub fn set_transition(&mut self, transition: Option<Transition>) {
+        self.transition = transition.map(Box::new);
+    }
+
+    pub(crate) fn take_started(&mut self) -> Option<Transition> {
+        if !std::mem::take(&mut self.started) {
+            return None;
+        }
+        self.transition.as_deref().copied()
     }
 
     pub fn set_dirty(&mut self) {
         self.dirty = true;
     }
 
+    /// Variable the style is bound to
+    pub fn binding(&self) -> Option<VarKey> {
+        self.binding.map(|b| b.key)
+    }
+
+    pub fn unbind(&mut self) {
+        self.binding = None;
+    }
+
     pub(crate) fn fix_dirty(&mut self) -> Option<&T> {
         if !self.dirty {
             return None;
@@ -671,10 +2018,117 @@ impl<T: Debug + Clone> StyleComponent<T> {
     }
 }
 
+impl<T: Debug + Clone + FromVarValue> StyleComponent<T> {
+    /// Sets the style from the variable whenever the variable changes
+    ///
+    /// Only the elements bound to a variable are laid out again when it is set.
+    pub fn bind(&mut self, key: VarKey) {
+        self.binding = Some(Binding { key, applied: None });
+    }
+
+    /// Takes the value of the bound variable if it changed, returns whether it did
+    fn apply_binding(&mut self, variables: &Variables) -> bool {
+        let Some(binding) = &mut self.binding else {
+            return false;
+        };
+        if binding
+            .applied
+            .is_some_and(|stamp| !variables.changed_since(binding.key, stamp))
+        {
+            return false;
+        }
+        binding.applied = Some(variables.stamp());
+        match variables
+            .value(binding.key)
+            .and_then(|v| T::update_from_var(&self.val, v))
+        {
+            Some(value) => {
+                self.set(value);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn corner_shapes() {
+        let half = Vector(50.0, 50.0);
+        let round = Corners::all(20.0);
+        assert!(round.contains(Vector(0.0, 0.0), half));
+        assert!(!round.contains(Vector(49.0, 49.0), half));
+        assert!(round.contains(Vector(49.0, 20.0), half));
+        assert!(!round.contains(Vector(51.0, 0.0), half));
+        assert_eq!(round.distance(Vector(50.0, 0.0), half), 0.0);
+
+        let squircle = round.with_shape(CornerShape::Squircle);
+        assert!(squircle.contains(Vector(45.0, 45.0), half));
+        assert!(!round.contains(Vector(45.0, 45.0), half));
+
+        let square = Corners::new(0.0, 20.0, 20.0, 20.0);
+        assert!(square.contains(Vector(-49.0, -49.0), half));
+        assert!(!square.contains(Vector(49.0, -49.0), half));
+    }
+
+    #[test]
+    fn gradient_stops() {
+        let origin = Position {
+            container: Container::This,
+            width: Value::Zero,
+            height: Value::Zero,
+        };
+        let gradient = Gradient::new((origin.clone(), Colors::BLACK), (origin, Colors::WHITE))
+            .with_stop(0.75, Colors::RED)
+            .with_stop(0.25, Colors::BLUE);
+        assert_eq!(gradient.stops[0].0, 0.25);
+        let (black, white): ([f32; 4], [f32; 4]) = (Colors::BLACK.into(), Colors::WHITE.into());
+
+        assert_eq!(gradient.sample(black, white, -1.0), black);
+        assert_eq!(gradient.sample(black, white, 0.25), [0.0, 0.0, 1.0, 1.0]);
+        assert_eq!(gradient.sample(black, white, 0.5), [0.5, 0.0, 0.5, 1.0]);
+        assert_eq!(gradient.sample(black, white, 1.0), white);
+
+        let oklab = gradient.with_space(ColorSpace::Oklab);
+        let mid = oklab.sample(black, white, 0.5);
+        assert!(mid[0] > 0.5 && mid[2] > 0.5);
+    }
+
+    #[test]
+    fn image_fit() {
+        #[derive(Clone)]
+        struct Pixels(u32, u32);
+        impl ImageData for Pixels {
+            fn get_size(&self) -> (u32, u32) {
+                (self.0, self.1)
+            }
+        }
+        let image = Image::new(Pixels(200, 100));
+        let size = Vector(100.0, 100.0);
+        assert_eq!(image.fit_into(size), (Vector::ZERO, size));
+
+        let contain = image.clone().with_fit(ImageFit::Contain);
+        assert_eq!(contain.fit_into(size), (Vector(0.0, 25.0), Vector(100.0, 50.0)));
+        let cover = image.clone().with_fit(ImageFit::Cover).with_align(0.0, 0.0);
+        assert_eq!(cover.fit_into(size), (Vector::ZERO, Vector(200.0, 100.0)));
+        let none = image.clone().with_fit(ImageFit::None);
+        assert_eq!(none.fit_into(size), (Vector(-50.0, 0.0), Vector(200.0, 100.0)));
+        let scale_down = image.clone().with_fit(ImageFit::ScaleDown);
+        assert_eq!(
+            scale_down.fit_into(Vector(400.0, 400.0)),
+            (Vector(100.0, 150.0), Vector(200.0, 100.0))
+        );
+
+        let source = image.with_source(50, 0, 50, 50).with_fit(ImageFit::Contain);
+        assert_eq!(source.uv(), [0.25, 0.0, 0.5, 0.5]);
+        assert_eq!(source.fit_into(size), (Vector::ZERO, size));
+        let sliced = source.with_slice(Sides::all(10.0));
+        assert_eq!(sliced.fit_into(Vector(300.0, 20.0)), (Vector::ZERO, Vector(300.0, 20.0)));
+    }
+
     #[test]
     pub fn style_enum_validity() {
         let style = Style::Height;
@@ -702,6 +2156,9 @@ mod tests {
             Style::ShadowAlpha => {
                 let _ = styles.shadow_alpha;
             }
+            Style::Shadows => {
+                let _ = styles.shadows;
+            }
             Style::Alpha => {
                 let _ = styles.alpha;
             }
@@ -729,6 +2186,9 @@ mod tests {
             Style::GradLinear => {
                 let _ = styles.grad_linear;
             }
+            Style::GradConic => {
+                let _ = styles.grad_conic;
+            }
             Style::Image => {
                 let _ = styles.image;
             }
@@ -750,6 +2210,12 @@ mod tests {
             Style::Margin => {
                 let _ = styles.margin;
             }
+            Style::Border => {
+                let _ = styles.border;
+            }
+            Style::Outline => {
+                let _ = styles.outline;
+            }
             Style::Text => {
                 let _ = styles.text;
             }
@@ -780,6 +2246,15 @@ mod tests {
             Style::RichText => {
                 let _ = styles.rich_text;
             }
+            Style::Layout => {
+                let _ = styles.layout;
+            }
+            Style::FlexGrow => {
+                let _ = styles.flex_grow;
+            }
+            Style::FlexShrink => {
+                let _ = styles.flex_shrink;
+            }
         }
 
         let Styles {
@@ -798,6 +2273,7 @@ mod tests {
             min_height,
             grad_radial,
             grad_linear,
+            grad_conic,
             image,
             image_tint,
             scroll_y,
@@ -805,7 +2281,10 @@ mod tests {
             overflow,
             margin,
             padding,
+            border,
+            outline,
             shadow_alpha,
+            shadows,
             text,
             font_size,
             font,
@@ -816,6 +2295,9 @@ mod tests {
             text_box_width: fit_text_width,
             text_box_height: fit_text_height,
             rich_text,
+            layout,
+            flex_grow,
+            flex_shrink,
         } = styles;
         let _ = (width, Style::Width);
         let _ = (height, Style::Height);
@@ -831,6 +2313,7 @@ mod tests {
         let _ = (min_width, Style::MinWidth);
         let _ = (grad_radial, Style::GradRadial);
         let _ = (grad_linear, Style::GradLinear);
+        let _ = (grad_conic, Style::GradConic);
         let _ = (image, Style::Image);
         let _ = (image_tint, Style::ImageTint);
         let _ = (scroll_y, Style::ScrollY);
@@ -838,8 +2321,11 @@ mod tests {
         let _ = (overflow, Style::Overflow);
         let _ = (padding, Style::Padding);
         let _ = (margin, Style::Margin);
+        let _ = (border, Style::Border);
+        let _ = (outline, Style::Outline);
         let _ = (shadow, Style::Shadow);
         let _ = (shadow_alpha, Style::ShadowAlpha);
+        let _ = (shadows, Style::Shadows);
         let _ = (text, Style::Text);
         let _ = (font_size, Style::FontSize);
         let _ = (font, Style::FontIdx);
@@ -850,5 +2336,50 @@ mod tests {
         let _ = (fit_text_width, Style::TextBoxWidth);
         let _ = (fit_text_height, Style::TextBoxHeight);
         let _ = (rich_text, Style::RichText);
+        let _ = (layout, Style::Layout);
+        let _ = (flex_grow, Style::FlexGrow);
+        let _ = (flex_shrink, Style::FlexShrink);
+    }
+
+    #[test]
+    fn value_expressions() {
+        let container = crate::element::Container {
+            pos: Vector::ZERO,
+            size: Vector(800.0, 300.0),
+            rotation: 0.0,
+        };
+        let containers = Containers {
+            container: &container,
+            vp: &container,
+            this: &container,
+            image: &Vector::ZERO,
+            content: &Vector::ZERO,
+            time: 0.0,
+        };
+        let mut variables = Variables::default();
+        let mut calc = |v: Value| v.calc(&containers, &mut variables);
+
+        let half = Value::c_width(Portion::Half);
+        let clamped = Value::clamp(half.clone(), Value::Px(200.0), Value::Px(600.0));
+        assert_eq!(calc(clamped), 400.0);
+        assert_eq!(calc(Value::min(half.clone(), Value::Px(250.0))), 250.0);
+        assert_eq!(calc(Value::max(half.clone(), Value::Px(250.0))), 400.0);
+        assert_eq!(calc(half.clone() * 0.5 + 10.0), 210.0);
+        assert_eq!(calc(-(Value::Px(3.0) - 5.0)), 2.0);
+        assert_eq!(calc(Value::Px(7.0) % 4.0), 3.0);
+        assert_eq!(calc(Value::lerp(Value::Zero, half.clone(), Value::Px(0.25))), 100.0);
+        assert_eq!(calc(Value::abs(Value::Px(-2.0))), 2.0);
+        assert_eq!(calc(Value::floor(Value::Px(2.7))), 2.0);
+        assert_eq!(calc(Value::round(Value::Px(2.5))), 3.0);
+        assert_eq!(calc(Value::pow(Value::Px(2.0), Value::Px(3.0))), 8.0);
+        assert_eq!(calc(Value::sqrt(Value::Px(16.0))), 4.0);
+        assert_eq!(calc(Value::atan2(Value::Px(1.0), Value::Px(0.0))), std::f32::consts::FRAC_PI_2);
+
+        let wide = Condition::new(
+            Value::c_width(Portion::Full),
+            Compare::Greater,
+            Value::c_height(Portion::Full),
+        );
+        assert_eq!(calc(Value::if_else(wide, Value::Px(1.0), Value::Px(2.0))), 1.0);
     }
 }
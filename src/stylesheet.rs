@@ -0,0 +1,654 @@
+//! Styles shared by elements through selectors
+//!
+//! Rules of a `StyleSheet` match elements by their label, classes and ancestors.
+//! Matching rules are applied in the order of their specificity, later rules
+//! winning ties, then the element's own `Element::overrides` and last the styles
+//! of its active states, see `Element::state_style_mut`.
+//! Only the styles named by a rule are written, the rest of `Styles` is left
+//! to the element.
+//!
+//! ```
+//! use rugui2::{colors::Colors, stylesheet::StylePatch, Gui};
+//! use std::num::NonZero;
+//!
+//! let mut gui: Gui = Gui::new((NonZero::new(800).unwrap(), NonZero::new(600).unwrap()));
+//! gui.stylesheet_mut().add(
+//!     "#menu > .button".parse().unwrap(),
+//!     StylePatch {
+//!         color: Some(Colors::RED),
+//!         ..Default::default()
+//!     },
+//! );
+//! ```
+
+use std::{collections::HashSet, fmt::Debug, str::FromStr};
+
+use crate::{
+    colors::Colors,
+    styles::{
+        Border, Corners, Gradient, Image, ImageData, Layout, LineHeight, Outline, Overflow, Position,
+        Rotation, Shadow, Sides, TextAlign, TextWrap, Value,
+    },
+    text::FontIdx,
+    Element, ElementKey, Gui, Styles,
+};
+
+macro_rules! style_patch {
+    ($($(#[$meta: meta])* $field: ident: $ty: ty),* $(,)?) => {
+        /// Partial `Styles`, every field that is `Some` is written when applied
+        #[derive(Clone, Debug)]
+        #[cfg_attr(
+            feature = "serde",
+            derive(serde::Serialize, serde::Deserialize),
+            serde(bound = "", default)
+        )]
+        pub struct StylePatch<Img: Clone + ImageData> {
+            $($(#[$meta])* pub $field: Option<$ty>,)*
+        }
+
+        impl<Img: Clone + ImageData> Default for StylePatch<Img> {
+            fn default() -> Self {
+                Self {
+                    $($field: None,)*
+                }
+            }
+        }
+
+        impl<Img: Clone + ImageData> StylePatch<Img> {
+            /// Takes the fields that are set in `other`
+            pub fn merge(&mut self, other: &Self) {
+                $(if other.$field.is_some() {
+                    self.$field = other.$field.clone();
+                })*
+            }
+
+            pub fn is_empty(&self) -> bool {
+                true $(&& self.$field.is_none())*
+            }
+
+            pub fn apply(&self, styles: &mut Styles<Img>) {
+                $(if let Some(v) = &self.$field {
+                    styles.$field.set(v.clone());
+                })*
+            }
+
+            /// Applies `self` in place of `old`
+            ///
+            /// The value of the element is kept in `authored` when a field is patched
+            /// for the first time and restored once no patch sets the field.
+            fn reapply(&self, old: &Self, authored: &mut Self, styles: &mut Styles<Img>) {
+                $(match &self.$field {
+                    Some(v) => {
+                        if old.$field.is_none() {
+                            authored.$field = Some(styles.$field.get().clone());
+                        }
+                        styles.$field.set(v.clone());
+                    }
+                    None => {
+                        if let Some(v) = authored.$field.take() {
+                            styles.$field.set(v);
+                        }
+                    }
+                })*
+            }
+        }
+    };
+}
+
+style_patch! {
+    width: Value,
+    max_width: Option<Value>,
+    min_width: Option<Value>,
+    height: Value,
+    max_height: Option<Value>,
+    min_height: Option<Value>,
+    padding: Sides,
+    margin: Sides,
+    border: Border,
+    outline: Option<Outline>,
+    color: Colors,
+    rotation: Rotation,
+    round: Corners,
+    shadow: Option<Value>,
+    shadow_alpha: f32,
+    shadows: Vec<Shadow>,
+    alpha: f32,
+    position: Position,
+    origin: Position,
+    grad_linear: Option<Gradient>,
+    grad_radial: Option<Gradient>,
+    grad_conic: Option<Gradient>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    image: Option<Image<Img>>,
+    image_tint: Colors,
+    scroll_y: Value,
+    scroll_x: Value,
+    overflow: Overflow,
+    font_size: Value,
+    font: FontIdx,
+    text_wrap: TextWrap,
+    line_height: LineHeight,
+    font_color: Colors,
+    text_align: TextAlign,
+    text_box_width: Option<Value>,
+    text_box_height: Option<Value>,
+    layout: Layout,
+    flex_grow: f32,
+    flex_shrink: f32,
+}
+
+/// Elements matched by a rule
+///
+/// Written as `#label` for labels, `.class` for classes, `:state` for
+/// `PseudoState`s and `*` for any element. Parts written together must all
+/// match the same element, parts separated by whitespace match an ancestor
+/// and by `>` the parent.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Selector {
+    pub label: Option<String>,
+    pub classes: Vec<String>,
+    pub states: Vec<PseudoState>,
+    pub ancestor: Option<(Combinator, Box<Selector>)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Combinator {
+    Descendant,
+    Child,
+}
+
+/// Interactive state of an element, toggled by `Gui::env_event`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PseudoState {
+    /// Cursor is over the element or one of its descendants
+    Hover,
+    /// Mouse button was pressed over the element and not released yet
+    Active,
+    /// Element is `Selection::current`
+    Focus,
+    /// Set by `Element::set_disabled`
+    Disabled,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SelectorError {
+    Empty,
+    UnexpectedChar(char),
+    /// `.`, `#` or `:` is not followed by a name
+    MissingName,
+    UnknownState(String),
+}
+
+pub struct Rule<Img: Clone + ImageData> {
+    pub selector: Selector,
+    pub styles: StylePatch<Img>,
+}
+
+/// Ordered list of rules, see the module documentation
+pub struct StyleSheet<Img: Clone + ImageData> {
+    rules: Vec<Rule<Img>>,
+}
+
+/// Result of the last cascade of an element
+#[derive(Debug, Clone)]
+pub(crate) struct Cascade<Img: Clone + ImageData> {
+    /// Indices of the matched rules in the order they were applied
+    pub matched: Vec<usize>,
+    /// Styles written by the cascade
+    pub applied: StylePatch<Img>,
+    /// Values of the element that are covered by `applied`
+    pub authored: StylePatch<Img>,
+    /// Classes or overrides changed
+    pub restyle: bool,
+}
+
+impl<Img: Clone + ImageData> Default for Cascade<Img> {
+    fn default() -> Self {
+        Self {
+            matched: Vec::new(),
+            applied: StylePatch::default(),
+            authored: StylePatch::default(),
+            restyle: true,
+        }
+    }
+}
+
+impl Selector {
+    pub fn any() -> Self {
+        Self::default()
+    }
+
+    pub fn label(label: impl Into<String>) -> Self {
+        Self {
+            label: Some(label.into()),
+            ..Default::default()
+        }
+    }
+
+    pub fn class(class: impl Into<String>) -> Self {
+        Self::any().with_class(class)
+    }
+
+    pub fn with_class(mut self, class: impl Into<String>) -> Self {
+        self.classes.push(class.into());
+        self
+    }
+
+    pub fn with_state(mut self, state: PseudoState) -> Self {
+        self.states.push(state);
+        self
+    }
+
+    /// Requires some ancestor of the element to match `ancestor`
+    pub fn descendant_of(mut self, ancestor: Selector) -> Self {
+        self.ancestor = Some((Combinator::Descendant, Box::new(ancestor)));
+        self
+    }
+
+    /// Requires the parent of the element to match `parent`
+    pub fn child_of(mut self, parent: Selector) -> Self {
+        self.ancestor = Some((Combinator::Child, Box::new(parent)));
+        self
+    }
+
+    /// Number of labels and of classes with states in the selector, compared in this order
+    pub fn specificity(&self) -> (u32, u32) {
+        let (labels, classes) = self
+            .ancestor
+            .as_ref()
+            .map_or((0, 0), |(_, a)| a.specificity());
+        (
+            labels + self.label.is_some() as u32,
+            classes + (self.classes.len() + self.states.len()) as u32,
+        )
+    }
+
+    /// `ancestors` start at the root and end with the parent of `element`
+    pub fn matches<Msg: Clone, Img: Clone + ImageData>(
+        &self,
+        element: &Element<Msg, Img>,
+        ancestors: &[&Element<Msg, Img>],
+    ) -> bool {
+        if self.label.is_some() && self.label != element.label {
+            return false;
+        }
+        if !self.classes.iter().all(|c| element.has_class(c)) {
+            return false;
+        }
+        if !self.states.iter().all(|s| element.states.contains(*s)) {
+            return false;
+        }
+        match &self.ancestor {
+            None => true,
+            Some((Combinator::Child, parent)) => ancestors
+                .split_last()
+                .is_some_and(|(p, rest)| parent.matches(p, rest)),
+            Some((Combinator::Descendant, ancestor)) => (0..ancestors.len())
+                .rev()
+                .any(|i| ancestor.matches(ancestors[i], &ancestors[..i])),
+        }
+    }
+}
+
+impl FromStr for Selector {
+    type Err = SelectorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut result: Option<Selector> = None;
+        let mut combinator = None;
+        let mut chars = s.chars().peekable();
+        loop {
+            while chars.next_if(|c| c.is_whitespace()).is_some() {}
+            match chars.peek() {
+                None => break,
+                Some('>') if result.is_some() && combinator.is_none() => {
+                    chars.next();
+                    combinator = Some(Combinator::Child);
+                    continue;
+                }
+                _ => (),
+            }
+            let mut selector = Selector::any();
+            let mut empty = true;
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() || c == '>' {
+                    break;
+                }
+                chars.next();
+                empty = false;
+                match c {
+                    '*' => (),
+                    '.' | '#' | ':' => {
+                        let mut name = String::new();
+                        while let Some(c) =
+                            chars.next_if(|c| c.is_alphanumeric() || *c == '-' || *c == '_')
+                        {
+                            name.push(c);
+                        }
+                        if name.is_empty() {
+                            return Err(SelectorError::MissingName);
+                        }
+                        match c {
+                            '.' => selector.classes.push(name),
+                            '#' => selector.label = Some(name),
+                            _ => selector.states.push(name.parse()?),
+                        }
+                    }
+                    c => return Err(SelectorError::UnexpectedChar(c)),
+                }
+            }
+            if empty {
+                return Err(SelectorError::UnexpectedChar('>'));
+            }
+            if let Some(ancestor) = result.take() {
+                let combinator = combinator.take().unwrap_or(Combinator::Descendant);
+                selector.ancestor = Some((combinator, Box::new(ancestor)));
+            }
+            result = Some(selector);
+        }
+        if combinator.is_some() {
+            return Err(SelectorError::UnexpectedChar('>'));
+        }
+        result.ok_or(SelectorError::Empty)
+    }
+}
+
+impl PseudoState {
+    /// Order in which the styles of active states are applied, the last one wins
+    pub const PRECEDENCE: [Self; 4] = [Self::Hover, Self::Focus, Self::Active, Self::Disabled];
+}
+
+impl FromStr for PseudoState {
+    type Err = SelectorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "hover" => Ok(Self::Hover),
+            "active" => Ok(Self::Active),
+            "focus" => Ok(Self::Focus),
+            "disabled" => Ok(Self::Disabled),
+            _ => Err(SelectorError::UnknownState(s.to_string())),
+        }
+    }
+}
+
+impl<Img: Clone + ImageData> StyleSheet<Img> {
+    pub fn new() -> Self {
+        Self { rules: Vec::new() }
+    }
+
+    /// Appends a rule and returns its index
+    pub fn add(&mut self, selector: Selector, styles: StylePatch<Img>) -> usize {
+        self.rules.push(Rule { selector, styles });
+        self.rules.len() - 1
+    }
+
+    pub fn rules(&self) -> &[Rule<Img>] {
+        &self.rules
+    }
+
+    pub fn rule_mut(&mut self, idx: usize) -> Option<&mut Rule<Img>> {
+        self.rules.get_mut(idx)
+    }
+
+    /// # Panic
+    ///
+    /// Panics if `idx` is out of bounds
+    pub fn remove(&mut self, idx: usize) -> Rule<Img> {
+        self.rules.remove(idx)
+    }
+
+    pub fn clear(&mut self) {
+        self.rules.clear();
+    }
+
+    /// Indices of the rules matching the element, in the order they apply
+    fn matching<Msg: Clone>(
+        &self,
+        element: &Element<Msg, Img>,
+        ancestors: &[&Element<Msg, Img>],
+    ) -> Vec<usize> {
+        let mut matched: Vec<usize> = (0..self.rules.len())
+            .filter(|i| self.rules[*i].selector.matches(element, ancestors))
+            .collect();
+        matched.sort_by_key(|i| self.rules[*i].selector.specificity());
+        matched
+    }
+}
+
+impl<Img: Clone + ImageData> Default for StyleSheet<Img> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Msg: Clone, Img: Clone + ImageData> Gui<Msg, Img> {
+    pub fn stylesheet(&self) -> &StyleSheet<Img> {
+        &self.stylesheet
+    }
+
+    /// The whole tree is restyled on the next update
+    pub fn stylesheet_mut(&mut self) -> &mut StyleSheet<Img> {
+        self.restyle = true;
+        &mut self.stylesheet
+    }
+
+    /// Restyles the whole tree on the next update
+    ///
+    /// Needed after changing `Element::label` of a styled element.
+    pub fn restyle(&mut self) {
+        self.restyle = true;
+    }
+
+    /// Runs the cascade for the subtrees whose classes, states or place in the tree changed
+    ///
+    /// Changing the stylesheet runs it for every element.
+    pub(crate) fn apply_stylesheet(&mut self) {
+        let sheet_changed = std::mem::take(&mut self.restyle);
+        let roots: Vec<ElementKey> = if sheet_changed {
+            (0..self.elements.len())
+                .filter(|idx| self.slots[*idx].occupied)
+                .map(|idx| self.slot_key(idx))
+                .filter(|key| self.parent_of(*key).is_none())
+                .collect()
+        } else {
+            let touched = self.touched.iter().filter(|key| {
+                self.contains(**key) && {
+                    let element = &self.elements[key.index()];
+                    element.cascade.restyle || element.layout.dirty_tree
+                }
+            });
+            let touched: HashSet<ElementKey> = touched.copied().collect();
+            // subtrees of other restyled elements are matched with them
+            touched
+                .iter()
+                .filter(|key| !self.ancestors(**key).any(|a| touched.contains(&a)))
+                .copied()
+                .collect()
+        };
+        if roots.is_empty() {
+            return;
+        }
+
+        let mut matches = Vec::new();
+        let mut ancestors = Vec::new();
+        for root in roots {
+            ancestors.clear();
+            let mut parent = self.parent_of(root);
+            while let Some(p) = parent {
+                ancestors.push(&self.elements[p.index()]);
+                parent = self.parent_of(p);
+            }
+            ancestors.reverse();
+            self.match_subtree(root, &mut ancestors, &mut matches);
+        }
+
+        for (key, matched) in matches {
+            let element = &mut self.elements[key.index()];
+            if !sheet_changed && !element.cascade.restyle && matched == element.cascade.matched {
+                continue;
+            }
+
+            let mut patch = StylePatch::default();
+            for rule in &matched {
+                patch.merge(&self.stylesheet.rules[*rule].styles);
+            }
+            patch.merge(&element.overrides);
+            for state in PseudoState::PRECEDENCE {
+                if let (true, Some(styles)) =
+                    (element.states.contains(state), element.state_style(state))
+                {
+                    patch.merge(styles);
+                }
+            }
+            let cascade = &mut element.cascade;
+            let changed = !patch.is_empty() || !cascade.applied.is_empty();
+            if changed {
+                element.dirty_styles = true;
+                patch.reapply(&cascade.applied, &mut cascade.authored, &mut element.styles);
+            }
+            cascade.matched = matched;
+            cascade.applied = patch;
+            cascade.restyle = false;
+            if changed {
+                self.touch(key);
+            }
+        }
+    }
+
+    /// Collects the rules matching every element of the subtree
+    fn match_subtree<'a>(
+        &'a self,
+        key: ElementKey,
+        ancestors: &mut Vec<&'a Element<Msg, Img>>,
+        matches: &mut Vec<(ElementKey, Vec<usize>)>,
+    ) {
+        let element = &self.elements[key.index()];
+        matches.push((key, self.stylesheet.matching(element, ancestors)));
+        if let Some(children) = &element.children {
+            ancestors.push(element);
+            for child in children.iter().filter(|c| self.contains(**c)) {
+                self.match_subtree(*child, ancestors, matches);
+            }
+            ancestors.pop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZero;
+
+    use crate::{colors::Colors, ui, Gui};
+
+    use super::*;
+
+    #[test]
+    fn selector_parse() {
+        let s: Selector = "#menu .row > .button.wide:hover".parse().unwrap();
+        assert_eq!(s.specificity(), (1, 4));
+        assert_eq!(
+            s,
+            Selector::class("button")
+                .with_class("wide")
+                .with_state(PseudoState::Hover)
+                .child_of(Selector::class("row").descendant_of(Selector::label("menu")))
+        );
+        assert_eq!("".parse::<Selector>(), Err(SelectorError::Empty));
+        assert_eq!(
+            "> .a".parse::<Selector>(),
+            Err(SelectorError::UnexpectedChar('>'))
+        );
+        assert_eq!(
+            ".a >".parse::<Selector>(),
+            Err(SelectorError::UnexpectedChar('>'))
+        );
+        assert_eq!(".".parse::<Selector>(), Err(SelectorError::MissingName));
+        assert_eq!(
+            ":shiny".parse::<Selector>(),
+            Err(SelectorError::UnknownState("shiny".into()))
+        );
+    }
+
+    #[test]
+    fn cascade() {
+        let mut gui: Gui = Gui::new((NonZero::new(800).unwrap(), NonZero::new(600).unwrap()));
+        let sheet = gui.stylesheet_mut();
+        let specific = sheet.add(
+            "#menu > .button.primary".parse().unwrap(),
+            StylePatch {
+                color: Some(Colors::RED),
+                ..Default::default()
+            },
+        );
+        sheet.add(
+            ".button".parse().unwrap(),
+            StylePatch {
+                color: Some(Colors::BLUE),
+                alpha: Some(0.5),
+                ..Default::default()
+            },
+        );
+        let tree = ui!(&mut gui, {
+            label: "menu",
+            children [
+                button @ { classes ["button", "primary"] },
+                plain @ { classes ["button"] },
+            ],
+        });
+        gui.get_element_mut(tree["plain"])
+            .unwrap()
+            .overrides_mut()
+            .alpha = Some(1.0);
+        gui.set_entry(tree.root);
+        gui.update(0.0);
+
+        let button = gui.get_element(tree["button"]).unwrap();
+        assert_eq!(button.styles().color.get(), &Colors::RED);
+        assert_eq!(*button.styles().alpha.get(), 0.5);
+        assert_eq!(
+            *gui.get_element(tree["plain"]).unwrap().styles().alpha.get(),
+            1.0
+        );
+
+        gui.stylesheet_mut().remove(specific);
+        gui.get_element_mut(tree["plain"])
+            .unwrap()
+            .remove_class("button");
+        gui.update(0.0);
+        let button = gui.get_element(tree["button"]).unwrap();
+        assert_eq!(button.styles().color.get(), &Colors::BLUE);
+        let plain = gui.get_element(tree["plain"]).unwrap();
+        assert_eq!(
+            plain.styles().color.get(),
+            Styles::<()>::default().color.get()
+        );
+        assert_eq!(*plain.styles().alpha.get(), 1.0);
+    }
+
+    #[test]
+    fn authored_styles() {
+        let mut gui: Gui = Gui::new((NonZero::new(800).unwrap(), NonZero::new(600).unwrap()));
+        gui.stylesheet_mut().add(
+            ".button".parse().unwrap(),
+            StylePatch {
+                color: Some(Colors::BLUE),
+                ..Default::default()
+            },
+        );
+        let tree = ui!(&mut gui, {
+            classes ["button"],
+            styles { color: Colors::GREEN },
+        });
+        gui.set_entry(tree.root);
+        gui.update(0.0);
+        let element = gui.get_element(tree.root).unwrap();
+        assert_eq!(element.styles().color.get(), &Colors::BLUE);
+
+        gui.get_element_mut(tree.root).unwrap().remove_class("button");
+        gui.update(0.0);
+        let element = gui.get_element(tree.root).unwrap();
+        assert_eq!(element.styles().color.get(), &Colors::GREEN);
+    }
+}
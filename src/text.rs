@@ -19,6 +19,7 @@ pub struct TextProccesor {
 }
 
 #[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FontIdx(pub(crate) u16);
 
 impl FontIdx {
@@ -742,6 +743,40 @@ impl PhysicalText {
         }
     }
 
+    /// Size of the text shaped by the last `TextProccesor::procces`
+    pub fn size(&self) -> crate::Vector {
+        let bb = Rect::minimal(self.lines.iter().take(self.active_lines).flat_map(|l| {
+            l.wraps.iter().take(l.active_wraps).map(|w| w.bb)
+        }));
+        crate::Vector(bb.width, bb.height)
+    }
+
+    /// Width of the widest word shaped by the last `TextProccesor::procces`
+    pub fn min_width(&self) -> f32 {
+        let mut widest = 0.0f32;
+        for line in self.lines.iter().take(self.active_lines) {
+            // words broken by the wrapping continue on the next wrap,
+            // whitespace at the wrap is not shaped
+            let mut word = 0.0;
+            let mut next = None;
+            for wrap in line.wraps.iter().take(line.active_wraps) {
+                for char in wrap.phys_chars.iter().take(wrap.active_chars) {
+                    if next.is_some_and(|next| next != char.idx) {
+                        word = 0.0;
+                    }
+                    next = Some(char.idx + 1);
+                    if self.text.get_char(char.idx).is_none_or(char::is_whitespace) {
+                        word = 0.0;
+                    } else {
+                        word += char.width;
+                        widest = widest.max(word);
+                    }
+                }
+            }
+        }
+        widest
+    }
+
     pub fn hit(&self, point: crate::Vector) -> Option<usize> {
         /*if !self.bb.hit(point) {
             return None;
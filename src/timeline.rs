@@ -0,0 +1,687 @@
+//! Keyframe animations
+//!
+//! A `Timeline` holds keyframes for style properties of any number of elements.
+//! Timelines are added to a `Gui` with `Gui::add_timeline` and are advanced by
+//! the time passed to `Gui::update`. Markers placed on a timeline fire
+//! `ElemEvents::TimelineMarker` on their element when the playhead crosses them.
+
+use crate::{
+    animation::{Easing, Lerp},
+    colors::Colors,
+    events::{ElemEvent, ElemEvents},
+    styles::{
+        Border, Corners, Dash, Gradient, Outline, Position, Rotation, Rotations, Shadow, Sides,
+        StyleComponent, Value,
+    },
+    ElementKey, Gui, ImageData, Styles,
+};
+
+/// Style values that can be animated by a `Timeline`
+pub trait Animatable: Clone + PartialEq + std::fmt::Debug {
+    /// Value at `t` between `self` (0.0) and `to` (1.0)
+    fn interpolate(&self, to: &Self, t: f32) -> Self;
+}
+
+impl Animatable for f32 {
+    fn interpolate(&self, to: &Self, t: f32) -> Self {
+        self.lerp(*to, t)
+    }
+}
+
+/// Blends in the space of the target color, see `Colors::space`
+impl Animatable for Colors {
+    fn interpolate(&self, to: &Self, t: f32) -> Self {
+        self.mix(*to, t, to.space())
+    }
+}
+
+impl Animatable for Value {
+    /// Pixel values are interpolated directly, other values
+    /// are blended by an expression evaluated during layout
+    fn interpolate(&self, to: &Self, t: f32) -> Self {
+        match (self, to) {
+            (Value::Px(a), Value::Px(b)) => Value::Px(a.lerp(*b, t)),
+            (Value::Zero, Value::Zero) => Value::Zero,
+            _ if t <= 0.0 => self.clone(),
+            _ if t >= 1.0 => to.clone(),
+            _ => Value::lerp(self.clone(), to.clone(), Value::Px(t)),
+        }
+    }
+}
+
+impl Animatable for Position {
+    fn interpolate(&self, to: &Self, t: f32) -> Self {
+        Position {
+            width: self.width.interpolate(&to.width, t),
+            height: self.height.interpolate(&to.height, t),
+            container: if t < 0.5 {
+                self.container
+            } else {
+                to.container
+            },
+        }
+    }
+}
+
+impl Animatable for Rotation {
+    fn interpolate(&self, to: &Self, t: f32) -> Self {
+        let rot = match (&self.rot, &to.rot) {
+            (Rotations::Deg(a), Rotations::Deg(b)) => Rotations::Deg(a.lerp(*b, t)),
+            (Rotations::Rad(a), Rotations::Rad(b)) => Rotations::Rad(a.lerp(*b, t)),
+            (a, b) => Rotations::CalcRad(radians(a).interpolate(&radians(b), t)),
+        };
+        Rotation {
+            rot,
+            cont: if t < 0.5 { self.cont } else { to.cont },
+        }
+    }
+}
+
+fn radians(rot: &Rotations) -> Value {
+    match rot {
+        Rotations::None => Value::Zero,
+        Rotations::Deg(d) => Value::Px(d.to_radians()),
+        Rotations::Rad(r) => Value::Px(*r),
+        Rotations::CalcDeg(v) => Value::Mul(Box::new((
+            v.clone(),
+            Value::Px(std::f32::consts::PI / 180.0),
+        ))),
+        Rotations::CalcRad(v) => v.clone(),
+    }
+}
+
+impl<T: Animatable> Animatable for Sides<T> {
+    fn interpolate(&self, to: &Self, t: f32) -> Self {
+        Sides::new(
+            self.top.interpolate(&to.top, t),
+            self.right.interpolate(&to.right, t),
+            self.bottom.interpolate(&to.bottom, t),
+            self.left.interpolate(&to.left, t),
+        )
+    }
+}
+
+/// The corner shape can not be interpolated, it switches halfway
+impl<T: Animatable> Animatable for Corners<T> {
+    fn interpolate(&self, to: &Self, t: f32) -> Self {
+        Corners::new(
+            self.top_left.interpolate(&to.top_left, t),
+            self.top_right.interpolate(&to.top_right, t),
+            self.bottom_right.interpolate(&to.bottom_right, t),
+            self.bottom_left.interpolate(&to.bottom_left, t),
+        )
+        .with_shape(if t < 0.5 { self.shape } else { to.shape })
+    }
+}
+
+/// Stops are interpolated pairwise when both gradients have the same number of them,
+/// otherwise they switch halfway along with the spread and color space
+impl Animatable for Gradient {
+    fn interpolate(&self, to: &Self, t: f32) -> Self {
+        let discrete = if t < 0.5 { self } else { to };
+        let stops = if self.stops.len() == to.stops.len() {
+            self.stops
+                .iter()
+                .zip(&to.stops)
+                .map(|((o1, c1), (o2, c2))| (o1.interpolate(o2, t), c1.interpolate(c2, t)))
+                .collect()
+        } else {
+            discrete.stops.clone()
+        };
+        Gradient {
+            p1: (
+                self.p1.0.interpolate(&to.p1.0, t),
+                self.p1.1.interpolate(&to.p1.1, t),
+            ),
+            p2: (
+                self.p2.0.interpolate(&to.p2.0, t),
+                self.p2.1.interpolate(&to.p2.1, t),
+            ),
+            stops,
+            spread: discrete.spread,
+            space: discrete.space,
+        }
+    }
+}
+
+impl Animatable for Dash {
+    fn interpolate(&self, to: &Self, t: f32) -> Self {
+        Dash {
+            length: self.length.interpolate(&to.length, t),
+            gap: self.gap.interpolate(&to.gap, t),
+        }
+    }
+}
+
+impl Animatable for Border {
+    fn interpolate(&self, to: &Self, t: f32) -> Self {
+        Border {
+            width: self.width.interpolate(&to.width, t),
+            color: self.color.interpolate(&to.color, t),
+            gradient: self.gradient.interpolate(&to.gradient, t),
+            dash: self.dash.interpolate(&to.dash, t),
+        }
+    }
+}
+
+impl Animatable for Outline {
+    fn interpolate(&self, to: &Self, t: f32) -> Self {
+        Outline {
+            width: self.width.interpolate(&to.width, t),
+            offset: self.offset.interpolate(&to.offset, t),
+            color: self.color.interpolate(&to.color, t),
+        }
+    }
+}
+
+impl Animatable for Shadow {
+    fn interpolate(&self, to: &Self, t: f32) -> Self {
+        Shadow {
+            x: self.x.interpolate(&to.x, t),
+            y: self.y.interpolate(&to.y, t),
+            blur: self.blur.interpolate(&to.blur, t),
+            spread: self.spread.interpolate(&to.spread, t),
+            color: self.color.interpolate(&to.color, t),
+            inset: if t < 0.5 { self.inset } else { to.inset },
+        }
+    }
+}
+
+/// Lists of different lengths can not be interpolated, the list switches halfway
+impl<T: Animatable> Animatable for Vec<T> {
+    fn interpolate(&self, to: &Self, t: f32) -> Self {
+        if self.len() != to.len() {
+            return if t < 0.5 { self.clone() } else { to.clone() };
+        }
+        self.iter().zip(to).map(|(a, b)| a.interpolate(b, t)).collect()
+    }
+}
+
+/// Missing values can not be interpolated, the value switches halfway
+impl<T: Animatable> Animatable for Option<T> {
+    fn interpolate(&self, to: &Self, t: f32) -> Self {
+        match (self, to) {
+            (Some(a), Some(b)) => Some(a.interpolate(b, t)),
+            _ if t < 0.5 => self.clone(),
+            _ => to.clone(),
+        }
+    }
+}
+
+/// Value of a style at a point of a timeline
+#[derive(Debug, Clone)]
+pub struct Keyframe<T> {
+    pub time: f32,
+    pub value: T,
+    /// Easing of the interpolation from the previous keyframe to this one
+    pub easing: Easing,
+}
+
+impl<T> Keyframe<T> {
+    pub fn new(time: f32, value: T) -> Self {
+        Self {
+            time,
+            value,
+            easing: Easing::Linear,
+        }
+    }
+
+    pub fn with_easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+}
+
+/// Keyframes of a single style of an element
+pub struct Track<T: Animatable, Img: Clone + ImageData> {
+    pub element: ElementKey,
+    /// Selects the animated style, e.g. `|s| &mut s.alpha`
+    pub style: fn(&mut Styles<Img>) -> &mut StyleComponent<T>,
+    keyframes: Vec<Keyframe<T>>,
+}
+
+impl<T: Animatable, Img: Clone + ImageData> Track<T, Img> {
+    pub fn new(
+        element: ElementKey,
+        style: fn(&mut Styles<Img>) -> &mut StyleComponent<T>,
+        mut keyframes: Vec<Keyframe<T>>,
+    ) -> Self {
+        keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+        Self {
+            element,
+            style,
+            keyframes,
+        }
+    }
+
+    pub fn keyframes(&self) -> &[Keyframe<T>] {
+        &self.keyframes
+    }
+
+    /// Value of the style at `time`, `None` without keyframes
+    pub fn sample(&self, time: f32) -> Option<T> {
+        let next = self.keyframes.iter().position(|k| k.time > time);
+        match next {
+            None => self.keyframes.last().map(|k| k.value.clone()),
+            Some(0) => Some(self.keyframes[0].value.clone()),
+            Some(i) => {
+                let (from, to) = (&self.keyframes[i - 1], &self.keyframes[i]);
+                let t = (time - from.time) / (to.time - from.time);
+                Some(from.value.interpolate(&to.value, to.easing.apply(t)))
+            }
+        }
+    }
+}
+
+/// Type erased `Track` so a timeline can hold tracks of different styles
+trait AnyTrack<Img: Clone + ImageData> {
+    fn element(&self) -> ElementKey;
+    fn end(&self) -> f32;
+    /// Sets the style to its value at `time`, returns whether the value changed
+    fn apply(&self, time: f32, styles: &mut Styles<Img>) -> bool;
+}
+
+impl<T: Animatable, Img: Clone + ImageData> AnyTrack<Img> for Track<T, Img> {
+    fn element(&self) -> ElementKey {
+        self.element
+    }
+
+    fn end(&self) -> f32 {
+        self.keyframes.last().map_or(0.0, |k| k.time)
+    }
+
+    fn apply(&self, time: f32, styles: &mut Styles<Img>) -> bool {
+        let Some(value) = self.sample(time) else {
+            return false;
+        };
+        let style = (self.style)(styles);
+        if *style.get() == value {
+            return false;
+        }
+        style.set(value);
+        true
+    }
+}
+
+/// Named point of a timeline, fires `ElemEvents::TimelineMarker` on `element`
+#[derive(Debug, Clone)]
+pub struct Marker {
+    pub time: f32,
+    pub element: ElementKey,
+    pub name: String,
+}
+
+/// What happens when the playhead reaches an end of the timeline
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Playback {
+    /// Stops at the end
+    #[default]
+    Once,
+    /// Jumps back to the start
+    Loop,
+    /// Turns around and plays in the other direction
+    PingPong,
+}
+
+/// Identifies a timeline added to a `Gui`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TimelineKey(u32);
+
+impl TimelineKey {
+    pub fn raw(&self) -> u32 {
+        self.0
+    }
+}
+
+/// Keyframed styles of multiple elements played together
+pub struct Timeline<Img: Clone + ImageData = ()> {
+    tracks: Vec<Box<dyn AnyTrack<Img>>>,
+    markers: Vec<Marker>,
+    pub playback: Playback,
+    /// Multiplier of the update time, negative speeds play backwards
+    pub speed: f32,
+    position: f32,
+    reversed: bool,
+    paused: bool,
+    finished: bool,
+    /// Styles need to be applied even when paused, after a seek
+    seeked: bool,
+}
+
+impl<Img: Clone + ImageData> Default for Timeline<Img> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Img: Clone + ImageData> Timeline<Img> {
+    pub fn new() -> Self {
+        Self {
+            tracks: Vec::new(),
+            markers: Vec::new(),
+            playback: Playback::Once,
+            speed: 1.0,
+            position: 0.0,
+            reversed: false,
+            paused: false,
+            finished: false,
+            seeked: true,
+        }
+    }
+
+    pub fn with_track<T: Animatable + 'static>(mut self, track: Track<T, Img>) -> Self
+    where
+        Img: 'static,
+    {
+        self.add_track(track);
+        self
+    }
+
+    pub fn with_marker(mut self, time: f32, element: ElementKey, name: &str) -> Self {
+        self.add_marker(time, element, name);
+        self
+    }
+
+    pub fn with_playback(mut self, playback: Playback) -> Self {
+        self.playback = playback;
+        self
+    }
+
+    pub fn with_speed(mut self, speed: f32) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    /// Starts playing from the end towards the start
+    pub fn reversed(mut self) -> Self {
+        self.reverse();
+        self.position = self.duration();
+        self
+    }
+
+    pub fn add_track<T: Animatable + 'static>(&mut self, track: Track<T, Img>)
+    where
+        Img: 'static,
+    {
+        self.tracks.push(Box::new(track));
+        self.seeked = true;
+    }
+
+    pub fn add_marker(&mut self, time: f32, element: ElementKey, name: &str) {
+        self.markers.push(Marker {
+            time,
+            element,
+            name: name.to_string(),
+        });
+    }
+
+    pub fn markers(&self) -> &[Marker] {
+        &self.markers
+    }
+
+    /// Time of the last keyframe or marker
+    pub fn duration(&self) -> f32 {
+        let tracks = self.tracks.iter().map(|t| t.end());
+        let markers = self.markers.iter().map(|m| m.time);
+        tracks.chain(markers).fold(0.0, f32::max)
+    }
+
+    /// Current time of the playhead
+    pub fn position(&self) -> f32 {
+        self.position
+    }
+
+    /// Moves the playhead to `time` without firing markers
+    pub fn seek(&mut self, time: f32) {
+        self.position = time.clamp(0.0, self.duration());
+        self.finished = false;
+        self.seeked = true;
+    }
+
+    pub fn play(&mut self) {
+        if self.finished {
+            self.position = if self.backwards() {
+                self.duration()
+            } else {
+                0.0
+            };
+            self.finished = false;
+        }
+        self.paused = false;
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Reached the end with `Playback::Once`
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Flips the direction of the playback
+    pub fn reverse(&mut self) {
+        self.reversed = !self.reversed;
+        self.finished = false;
+    }
+
+    pub fn is_reversed(&self) -> bool {
+        self.reversed
+    }
+
+    fn backwards(&self) -> bool {
+        self.reversed != (self.speed < 0.0)
+    }
+
+    /// Moves the playhead by `delta` time, returns the markers it crossed
+    fn advance(&mut self, delta: f32) -> Vec<usize> {
+        let mut crossed = Vec::new();
+        let duration = self.duration();
+        if self.paused || self.finished || duration <= 0.0 {
+            return crossed;
+        }
+        let mut remaining = (delta * self.speed).abs();
+        while remaining > 0.0 {
+            let backwards = self.backwards();
+            let end = if backwards { 0.0 } else { duration };
+            let step = remaining.min((end - self.position).abs());
+            let from = self.position;
+            self.position += if backwards { -step } else { step };
+            remaining -= step;
+            crossed.extend(self.markers.iter().enumerate().filter_map(|(i, m)| {
+                let hit = if backwards {
+                    m.time < from && m.time >= self.position
+                } else {
+                    m.time > from && m.time <= self.position
+                        || from == 0.0 && m.time == 0.0 && step > 0.0
+                };
+                hit.then_some(i)
+            }));
+            if remaining <= 0.0 {
+                break;
+            }
+            match self.playback {
+                Playback::Once => {
+                    self.finished = true;
+                    break;
+                }
+                Playback::Loop => self.position = duration - end,
+                Playback::PingPong => self.reversed = !self.reversed,
+            }
+        }
+        crossed
+    }
+}
+
+impl<Msg: Clone, Img: Clone + ImageData> Gui<Msg, Img> {
+    pub fn add_timeline(&mut self, timeline: Timeline<Img>) -> TimelineKey {
+        self.timelines.push(Some(timeline));
+        TimelineKey(self.timelines.len() as u32 - 1)
+    }
+
+    pub fn timeline(&self, key: TimelineKey) -> Option<&Timeline<Img>> {
+        self.timelines.get(key.0 as usize)?.as_ref()
+    }
+
+    pub fn timeline_mut(&mut self, key: TimelineKey) -> Option<&mut Timeline<Img>> {
+        self.timelines.get_mut(key.0 as usize)?.as_mut()
+    }
+
+    /// Removes the timeline, styles keep the values it set last
+    pub fn remove_timeline(&mut self, key: TimelineKey) -> Option<Timeline<Img>> {
+        self.timelines.get_mut(key.0 as usize)?.take()
+    }
+
+    /// Moves the playheads by `delta`, the time since the last update, and sets the styles
+    pub(crate) fn advance_timelines(&mut self, delta: f32) {
+        let mut timelines = std::mem::take(&mut self.timelines);
+        for (idx, slot) in timelines.iter_mut().enumerate() {
+            let Some(timeline) = slot else {
+                continue;
+            };
+            let was_playing = !timeline.paused && !timeline.finished;
+            let crossed = timeline.advance(delta);
+            if !was_playing && !timeline.seeked {
+                continue;
+            }
+            timeline.seeked = false;
+            for track in &timeline.tracks {
+                let key = track.element();
+                if !self.contains(key) {
+                    continue;
+                }
+                let element = &mut self.elements[key.index()];
+                if track.apply(timeline.position, &mut element.styles) {
+                    element.dirty_styles = true;
+                    self.touch(key);
+                }
+            }
+            for marker in crossed.into_iter().map(|i| &timeline.markers[i]) {
+                if !self.contains(marker.element) {
+                    continue;
+                }
+                let element = &self.elements[marker.element.index()];
+                for listener in &element.events.timeline_marker {
+                    self.events.push(ElemEvent {
+                        kind: ElemEvents::TimelineMarker {
+                            timeline: TimelineKey(idx as u32),
+                            name: marker.name.clone(),
+                        },
+                        element_key: marker.element,
+                        msg: listener.msg.clone(),
+                    });
+                }
+            }
+        }
+        self.timelines = timelines;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZero;
+
+    use super::*;
+    use crate::{ElemEventTypes, Element, EventListener};
+
+    #[test]
+    fn keyframes_and_playback() {
+        let mut gui: Gui<u32> = Gui::new((NonZero::new(800).unwrap(), NonZero::new(600).unwrap()));
+        let root = gui.add_element(Element::default());
+        let mut panel = Element::default();
+        panel
+            .events
+            .add(EventListener::new(ElemEventTypes::TimelineMarker).with_msg(3));
+        let panel = gui.add_element(panel);
+        gui.insert_child_at(root, 0, panel).unwrap();
+        gui.set_entry(root);
+
+        let timeline = Timeline::new()
+            .with_track(Track::new(
+                panel,
+                |s| &mut s.alpha,
+                vec![
+                    Keyframe::new(0.0, 0.0),
+                    Keyframe::new(2.0, 1.0).with_easing(Easing::Linear),
+                ],
+            ))
+            .with_track(Track::new(
+                root,
+                |s| &mut s.width,
+                vec![
+                    Keyframe::new(0.0, Value::Px(100.0)),
+                    Keyframe::new(2.0, Value::Px(300.0)),
+                ],
+            ))
+            .with_marker(1.0, panel, "half")
+            .with_playback(Playback::PingPong);
+        assert_eq!(timeline.duration(), 2.0);
+        let key = gui.add_timeline(timeline);
+
+        gui.update(0.0);
+        assert_eq!(*gui.get_element(panel).unwrap().styles().alpha.get(), 0.0);
+        gui.update(1.5);
+        assert_eq!(*gui.get_element(panel).unwrap().styles().alpha.get(), 0.75);
+        assert!(matches!(
+            gui.get_element(root).unwrap().styles().width.get(),
+            Value::Px(w) if *w == 250.0
+        ));
+        let event = gui.poll_event().unwrap();
+        assert_eq!(event.msg, Some(3));
+        assert!(matches!(event.kind, ElemEvents::TimelineMarker { name, .. } if name == "half"));
+        assert!(gui.poll_event().is_none());
+
+        // bounces back from the end
+        gui.update(2.5);
+        assert_eq!(*gui.get_element(panel).unwrap().styles().alpha.get(), 0.75);
+        assert!(gui.timeline(key).unwrap().is_reversed());
+
+        gui.timeline_mut(key).unwrap().pause();
+        gui.update(3.0);
+        assert_eq!(gui.timeline(key).unwrap().position(), 1.5);
+
+        gui.timeline_mut(key).unwrap().seek(0.5);
+        gui.update(3.5);
+        assert_eq!(*gui.get_element(panel).unwrap().styles().alpha.get(), 0.25);
+
+        let timeline = gui.timeline_mut(key).unwrap();
+        timeline.playback = Playback::Once;
+        timeline.speed = 2.0;
+        timeline.play();
+        gui.update(4.0);
+        assert!(gui.timeline(key).unwrap().is_finished());
+        assert_eq!(*gui.get_element(panel).unwrap().styles().alpha.get(), 0.0);
+    }
+
+    #[test]
+    fn unchanged_values() {
+        let mut gui: Gui<u32> = Gui::new((NonZero::new(800).unwrap(), NonZero::new(600).unwrap()));
+        let panel = gui.add_element(Element::default());
+        gui.set_entry(panel);
+        let key = gui.add_timeline(Timeline::new().with_track(Track::new(
+            panel,
+            |s| &mut s.alpha,
+            vec![
+                Keyframe::new(0.0, 1.0),
+                Keyframe::new(1.0, 1.0),
+                Keyframe::new(2.0, 0.0),
+            ],
+        )));
+
+        // the first update starts the playback instead of measuring from 0.0
+        gui.update(100.0);
+        assert_eq!(gui.timeline(key).unwrap().position(), 0.0);
+        assert_eq!(*gui.get_element(panel).unwrap().styles().alpha.get(), 1.0);
+
+        // holding a value does not queue the element again
+        gui.advance_timelines(0.5);
+        assert!(gui.touched.is_empty());
+        gui.advance_timelines(1.0);
+        assert_eq!(gui.touched, [panel]);
+        assert_eq!(*gui.get_element(panel).unwrap().styles().alpha.get(), 0.5);
+    }
+}
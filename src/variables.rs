@@ -1,9 +1,36 @@
+use std::collections::HashMap;
+
+use crate::{
+    colors::Colors,
+    expression::ParseError,
+    styles::{Border, Corners, Position, Sides, Value},
+    text::TextRepr,
+    Vector,
+};
+
 #[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Variables {
     variables: Vec<Variable>,
+    springs: Vec<(VarKey, Spring)>,
+    names: HashMap<String, VarKey>,
+    stamp: u64,
+    /// Reads and writes that failed since `Gui` last reported them
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) faults: Vec<VarFault>,
+}
+
+/// Failed access of a `Value` to a variable, reported as a `diagnostics::VarIssue`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum VarFault {
+    /// Variable without a value was read, 0.0 was used instead
+    Read(VarKey),
+    /// Constant was written, the value was used but not stored
+    Write(VarKey),
 }
 
 #[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct VarKey(pub(crate) u64);
 
 impl VarKey {
@@ -14,18 +41,69 @@ impl VarKey {
 }
 
 #[derive(Debug, Copy, Clone, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum VarKind {
     Constant,
     Variable,
+    /// Moves towards a target by a `Spring`, advanced by `Gui::update`
+    Spring,
 }
 
+/// Damped spring driving a `VarKind::Spring` variable
 #[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Spring {
+    pub stiffness: f32,
+    pub damping: f32,
+    target: f32,
+    velocity: f32,
+}
+
+/// Longest step of the spring integration, longer updates are split
+const SPRING_STEP: f32 = 1.0 / 120.0;
+/// Longest update a spring integrates, the rest of a longer pause is dropped
+const SPRING_MAX_DELTA: f32 = 0.25;
+/// Distance and velocity under which a spring snaps to its target
+const SPRING_REST: f32 = 1e-4;
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Variable {
     initialized: bool,
-    value: f32,
+    value: VarValue,
+    changed: u64,
     pub kind: VarKind,
 }
 
+/// Typed value of a variable, the type is fixed when the variable is created
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum VarValue {
+    Scalar(f32),
+    Vector(Vector),
+    Color(Colors),
+    Bool(bool),
+    Text(String),
+}
+
+/// Tracks changes of a single variable, see `Variables::subscribe`
+#[derive(Debug, Clone, Copy)]
+pub struct Subscription {
+    key: VarKey,
+    stamp: u64,
+}
+
+/// Style values that can be bound to a variable with `StyleComponent::bind`
+pub trait FromVarValue: Sized {
+    fn from_var(value: &VarValue) -> Option<Self>;
+
+    /// New value of a bound style whose variable changed, replaces `current` by default
+    fn update_from_var(current: &Self, value: &VarValue) -> Option<Self> {
+        _ = current;
+        Self::from_var(value)
+    }
+}
+
 impl Variables {
     pub fn push(&mut self, variable: Variable) -> VarKey {
         let len = self.variables.len();
@@ -33,6 +111,141 @@ impl Variables {
         VarKey(len as u64)
     }
 
+    /// Adds a variable that can be found by `name`, an existing variable of the name is replaced
+    pub fn push_named(&mut self, name: &str, mut variable: Variable) -> VarKey {
+        if let Some(key) = self.key(name) {
+            self.stamp += 1;
+            variable.changed = self.stamp;
+            self.variables[key.raw() as usize] = variable;
+            return key;
+        }
+        let key = self.push(variable);
+        self.names.insert(name.to_string(), key);
+        key
+    }
+
+    pub fn key(&self, name: &str) -> Option<VarKey> {
+        self.names.get(name).copied()
+    }
+
+    /// Parses an expression, `$name` refers to the named variables
+    pub fn parse(&self, src: &str) -> Result<Value, ParseError> {
+        Value::parse(src, |name| self.key(name))
+    }
+
+    pub fn value(&self, key: VarKey) -> Option<&VarValue> {
+        let v = self.variables.get(key.raw() as usize)?;
+        v.initialized.then_some(&v.value)
+    }
+
+    pub fn kind(&self, key: VarKey) -> Option<VarKind> {
+        self.variables.get(key.raw() as usize).map(|v| v.kind)
+    }
+
+    /// Sets a value of any type, it has to match the type the variable was created with
+    ///
+    /// Elements bound to the variable are laid out again on the next update.
+    /// Springs jump to the value and rest there, like with `Variables::set`.
+    pub fn set_value(&mut self, key: VarKey, value: impl Into<VarValue>) -> Result<(), VarError> {
+        let v = self
+            .variables
+            .get_mut(key.raw() as usize)
+            .ok_or(VarError::NotFound)?;
+        let value = value.into();
+        if std::mem::discriminant(&v.value) != std::mem::discriminant(&value) {
+            return Err(VarError::TypeMismatch);
+        }
+        if v.kind == VarKind::Constant {
+            return Err(VarError::ConstAssign);
+        }
+        if let (VarKind::Spring, VarValue::Scalar(target)) = (v.kind, &value) {
+            let target = *target;
+            if let Some(spring) = self.spring_mut(key) {
+                spring.target = target;
+                spring.velocity = 0.0;
+            }
+        }
+        let v = &mut self.variables[key.raw() as usize];
+        if v.initialized && v.value == value {
+            return Ok(());
+        }
+        v.value = value;
+        v.initialized = true;
+        self.stamp += 1;
+        v.changed = self.stamp;
+        Ok(())
+    }
+
+    /// Starts tracking changes of the variable made after this call
+    pub fn subscribe(&self, key: VarKey) -> Subscription {
+        Subscription {
+            key,
+            stamp: self.stamp,
+        }
+    }
+
+    /// Adds a variable starting at rest on `value`
+    pub fn push_spring(&mut self, value: f32, mut spring: Spring) -> VarKey {
+        spring.target = value;
+        spring.velocity = 0.0;
+        let key = self.push(Variable {
+            initialized: true,
+            value: VarValue::Scalar(value),
+            changed: 0,
+            kind: VarKind::Spring,
+        });
+        self.springs.push((key, spring));
+        key
+    }
+
+    pub fn spring(&self, key: VarKey) -> Option<&Spring> {
+        self.springs.iter().find(|(k, _)| *k == key).map(|(_, s)| s)
+    }
+
+    pub fn spring_mut(&mut self, key: VarKey) -> Option<&mut Spring> {
+        self.springs.iter_mut().find(|(k, _)| *k == key).map(|(_, s)| s)
+    }
+
+    /// Sets the value the spring moves towards, can be called every frame
+    pub fn set_target(&mut self, key: VarKey, target: f32) -> Result<(), VarError> {
+        let spring = self.spring_mut(key).ok_or(VarError::NotSpring)?;
+        spring.target = target;
+        Ok(())
+    }
+
+    /// Current velocity of a spring in units per update time
+    pub fn velocity(&self, key: VarKey) -> Option<f32> {
+        self.spring(key).map(|s| s.velocity)
+    }
+
+    /// Gives the spring a push, e.g. the velocity of a released drag
+    pub fn set_velocity(&mut self, key: VarKey, velocity: f32) -> Result<(), VarError> {
+        let spring = self.spring_mut(key).ok_or(VarError::NotSpring)?;
+        spring.velocity = velocity;
+        Ok(())
+    }
+
+    /// Integrates all springs that are not at rest over `delta` time
+    pub(crate) fn step_springs(&mut self, delta: f32) {
+        if delta <= 0.0 {
+            return;
+        }
+        for (key, spring) in &mut self.springs {
+            let Some(var) = self.variables.get_mut(key.raw() as usize) else {
+                continue;
+            };
+            let VarValue::Scalar(value) = &mut var.value else {
+                continue;
+            };
+            if spring.is_resting(*value) {
+                continue;
+            }
+            *value = spring.step(*value, delta);
+            self.stamp += 1;
+            var.changed = self.stamp;
+        }
+    }
+
     pub fn prepare(&mut self) {
         for var in &mut self.variables {
             var.prepare();
@@ -47,6 +260,10 @@ impl Variables {
         }
     }
 
+    /// Sets a scalar variable
+    ///
+    /// Springs jump to the value and rest there, their target moves along so they
+    /// do not pull back. Use `Variables::set_target` to animate them towards it instead.
     pub fn set(&mut self, key: VarKey, value: f32) -> Result<f32, VarError> {
         let v = if let Some(v) = self.variables.get_mut(key.raw() as usize) {
             v
@@ -54,7 +271,33 @@ impl Variables {
             return Err(VarError::NotFound);
         };
 
-        v.set(value)
+        let old = v.get();
+        let result = v.set(value);
+        if old != v.get() {
+            self.stamp += 1;
+            v.changed = self.stamp;
+        }
+        if let Some(spring) = self.spring_mut(key) {
+            spring.target = value;
+            spring.velocity = 0.0;
+        }
+        result
+    }
+
+    /// Value read by `Value::Variable`, 0.0 for a variable without a value
+    pub(crate) fn read(&mut self, key: VarKey) -> f32 {
+        self.get(key).unwrap_or_else(|| {
+            self.faults.push(VarFault::Read(key));
+            0.0
+        })
+    }
+
+    /// Value written by `Value::SetVariable`, it is passed on even when it can not be stored
+    pub(crate) fn write(&mut self, key: VarKey, value: f32) -> f32 {
+        self.set(key, value).unwrap_or_else(|_| {
+            self.faults.push(VarFault::Write(key));
+            value
+        })
     }
 
     pub fn set_const(&mut self, key: VarKey, value: f32) -> Result<f32, VarError> {
@@ -64,7 +307,26 @@ impl Variables {
             return Err(VarError::NotFound);
         };
 
-        v.set_const(value)
+        let old = v.get();
+        let result = v.set_const(value);
+        if old != v.get() {
+            self.stamp += 1;
+            v.changed = self.stamp;
+        }
+        result
+    }
+
+    /// Counter that increases with every change of any variable
+    pub fn stamp(&self) -> u64 {
+        self.stamp
+    }
+
+    /// Checks whether the variable changed after the given `Variables::stamp`
+    pub fn changed_since(&self, key: VarKey, stamp: u64) -> bool {
+        match self.variables.get(key.raw() as usize) {
+            Some(v) => v.changed > stamp,
+            None => false,
+        }
     }
 }
 
@@ -72,7 +334,8 @@ impl Variable {
     pub fn new_var() -> Self {
         Self {
             initialized: false,
-            value: 0.0,
+            value: VarValue::Scalar(0.0),
+            changed: 0,
             kind: VarKind::Variable,
         }
     }
@@ -80,37 +343,58 @@ impl Variable {
     pub fn new_const(value: f32) -> Self {
         Self {
             initialized: true,
-            value,
+            value: VarValue::Scalar(value),
+            changed: 0,
             kind: VarKind::Constant,
         }
     }
 
+    /// Variable of any type with an initial value
+    pub fn new_value(value: impl Into<VarValue>) -> Self {
+        Self {
+            initialized: true,
+            value: value.into(),
+            changed: 0,
+            kind: VarKind::Variable,
+        }
+    }
+
     pub fn prepare(&mut self) {
         match self.kind {
-            VarKind::Constant => (),
+            VarKind::Constant | VarKind::Spring => (),
             VarKind::Variable => self.initialized = false,
         }
     }
 
+    /// Scalar value, booleans read as 1.0 and 0.0
     pub fn get(&self) -> Option<f32> {
-        self.initialized.then_some(self.value)
+        if !self.initialized {
+            return None;
+        }
+        self.value.as_scalar()
     }
 
     fn set(&mut self, v: f32) -> Result<f32, VarError> {
+        let VarValue::Scalar(value) = &mut self.value else {
+            return Err(VarError::TypeMismatch);
+        };
         match self.kind {
             VarKind::Constant => return Err(VarError::ConstAssign),
-            VarKind::Variable => self.value = v,
+            VarKind::Variable | VarKind::Spring => *value = v,
         }
         self.initialized = true;
-        Ok(self.value)
+        Ok(v)
     }
 
     pub fn set_const(&mut self, v: f32) -> Result<f32, VarError> {
+        let VarValue::Scalar(value) = &mut self.value else {
+            return Err(VarError::TypeMismatch);
+        };
         match self.kind {
-            VarKind::Constant => self.value = v,
-            VarKind::Variable => return Err(VarError::ConstAssignOnVariable),
+            VarKind::Constant => *value = v,
+            VarKind::Variable | VarKind::Spring => return Err(VarError::ConstAssignOnVariable),
         }
-        Ok(self.value)
+        Ok(v)
     }
 }
 
@@ -119,4 +403,268 @@ pub enum VarError {
     NotFound,
     ConstAssign,
     ConstAssignOnVariable,
+    NotSpring,
+    /// Value has a different type than the variable
+    TypeMismatch,
+}
+
+impl VarValue {
+    pub fn as_scalar(&self) -> Option<f32> {
+        match self {
+            VarValue::Scalar(v) => Some(*v),
+            VarValue::Bool(b) => Some(if *b { 1.0 } else { 0.0 }),
+            _ => None,
+        }
+    }
+
+    pub fn as_vector(&self) -> Option<Vector> {
+        match self {
+            VarValue::Vector(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    pub fn as_color(&self) -> Option<Colors> {
+        match self {
+            VarValue::Color(c) => Some(*c),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            VarValue::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            VarValue::Text(t) => Some(t),
+            _ => None,
+        }
+    }
+}
+
+impl From<f32> for VarValue {
+    fn from(value: f32) -> Self {
+        VarValue::Scalar(value)
+    }
+}
+
+impl From<Vector> for VarValue {
+    fn from(value: Vector) -> Self {
+        VarValue::Vector(value)
+    }
+}
+
+impl From<Colors> for VarValue {
+    fn from(value: Colors) -> Self {
+        VarValue::Color(value)
+    }
+}
+
+impl From<bool> for VarValue {
+    fn from(value: bool) -> Self {
+        VarValue::Bool(value)
+    }
+}
+
+impl From<String> for VarValue {
+    fn from(value: String) -> Self {
+        VarValue::Text(value)
+    }
+}
+
+impl From<&str> for VarValue {
+    fn from(value: &str) -> Self {
+        VarValue::Text(value.to_string())
+    }
+}
+
+impl Subscription {
+    pub fn key(&self) -> VarKey {
+        self.key
+    }
+
+    /// New value of the variable if it changed since the last poll
+    pub fn poll<'a>(&mut self, variables: &'a Variables) -> Option<&'a VarValue> {
+        if !variables.changed_since(self.key, self.stamp) {
+            return None;
+        }
+        self.stamp = variables.stamp();
+        variables.value(self.key)
+    }
+}
+
+impl FromVarValue for f32 {
+    fn from_var(value: &VarValue) -> Option<Self> {
+        value.as_scalar()
+    }
+}
+
+impl FromVarValue for Value {
+    fn from_var(value: &VarValue) -> Option<Self> {
+        value.as_scalar().map(Value::Px)
+    }
+}
+
+impl FromVarValue for Option<Value> {
+    fn from_var(value: &VarValue) -> Option<Self> {
+        value.as_scalar().map(|v| Some(Value::Px(v)))
+    }
+}
+
+/// Scalars set all sides
+impl FromVarValue for Sides<Value> {
+    fn from_var(value: &VarValue) -> Option<Self> {
+        value.as_scalar().map(|v| Sides::all(Value::Px(v)))
+    }
+}
+
+/// Scalars set the width of all sides, a bound border keeps its color and dashes
+impl FromVarValue for Border {
+    fn from_var(value: &VarValue) -> Option<Self> {
+        Self::update_from_var(&Border::default(), value)
+    }
+
+    fn update_from_var(current: &Self, value: &VarValue) -> Option<Self> {
+        let width = Sides::<Value>::from_var(value)?;
+        Some(Border {
+            width,
+            ..current.clone()
+        })
+    }
+}
+
+/// Scalars set all corners
+impl FromVarValue for Corners<Value> {
+    fn from_var(value: &VarValue) -> Option<Self> {
+        value.as_scalar().map(|v| Corners::all(Value::Px(v)))
+    }
+}
+
+impl FromVarValue for Colors {
+    fn from_var(value: &VarValue) -> Option<Self> {
+        value.as_color()
+    }
+}
+
+impl FromVarValue for Position {
+    fn from_var(value: &VarValue) -> Option<Self> {
+        value.as_vector().map(Position::from)
+    }
+}
+
+impl FromVarValue for Option<TextRepr> {
+    fn from_var(value: &VarValue) -> Option<Self> {
+        value.as_str().map(|t| Some(TextRepr::new_label(t)))
+    }
+}
+
+impl Spring {
+    pub fn new(stiffness: f32, damping: f32) -> Self {
+        Self {
+            stiffness,
+            damping,
+            target: 0.0,
+            velocity: 0.0,
+        }
+    }
+
+    /// Spring that settles as fast as possible without overshooting
+    pub fn critical(stiffness: f32) -> Self {
+        Self::new(stiffness, 2.0 * stiffness.sqrt())
+    }
+
+    pub fn target(&self) -> f32 {
+        self.target
+    }
+
+    pub fn velocity(&self) -> f32 {
+        self.velocity
+    }
+
+    pub fn is_resting(&self, value: f32) -> bool {
+        self.velocity == 0.0 && value == self.target
+    }
+
+    /// Semi-implicit Euler integration of a unit mass
+    fn step(&mut self, mut value: f32, delta: f32) -> f32 {
+        let delta = delta.min(SPRING_MAX_DELTA);
+        let steps = (delta / SPRING_STEP).ceil() as u32;
+        let dt = delta / steps as f32;
+        for _ in 0..steps {
+            let force = self.stiffness * (self.target - value) - self.damping * self.velocity;
+            self.velocity += force * dt;
+            value += self.velocity * dt;
+        }
+        if (self.target - value).abs() < SPRING_REST && self.velocity.abs() < SPRING_REST {
+            self.velocity = 0.0;
+            value = self.target;
+        }
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spring_settles() {
+        let mut vars = Variables::default();
+        let plain = vars.push(Variable::new_var());
+        let key = vars.push_spring(0.0, Spring::critical(100.0));
+        assert!(matches!(vars.set_target(plain, 1.0), Err(VarError::NotSpring)));
+
+        vars.set_target(key, 1.0).unwrap();
+        vars.step_springs(0.1);
+        let value = vars.get(key).unwrap();
+        assert!(value > 0.0 && value < 1.0);
+        assert!(vars.velocity(key).unwrap() > 0.0);
+
+        let stamp = vars.stamp();
+        for _ in 0..100 {
+            vars.step_springs(1.0 / 60.0);
+            assert!(vars.get(key).unwrap() <= 1.0);
+        }
+        assert!(vars.stamp() > stamp);
+        assert_eq!(vars.get(key), Some(1.0));
+        assert_eq!(vars.velocity(key), Some(0.0));
+
+        // resting springs do not change the stamp
+        let stamp = vars.stamp();
+        vars.step_springs(1.0);
+        assert_eq!(vars.stamp(), stamp);
+
+        vars.set_target(key, 3.0).unwrap();
+        vars.step_springs(0.1);
+        vars.set(key, 5.0).unwrap();
+        assert_eq!(vars.spring(key).unwrap().target(), 5.0);
+        assert_eq!(vars.velocity(key), Some(0.0));
+        vars.step_springs(0.1);
+        assert_eq!(vars.get(key), Some(5.0));
+
+        // typed writes move the target along, the spring does not pull back
+        vars.set_target(key, 8.0).unwrap();
+        vars.step_springs(0.1);
+        vars.set_value(key, 2.0).unwrap();
+        assert_eq!(vars.spring(key).unwrap().target(), 2.0);
+        assert_eq!(vars.velocity(key), Some(0.0));
+        vars.step_springs(0.1);
+        assert_eq!(vars.get(key), Some(2.0));
+    }
+
+    #[test]
+    fn spring_large_delta() {
+        let mut vars = Variables::default();
+        let key = vars.push_spring(0.0, Spring::new(10000.0, 20.0));
+        vars.set_target(key, 1.0).unwrap();
+        // a suspended app resumes without the spring blowing up
+        vars.step_springs(3600.0);
+        let value = vars.get(key).unwrap();
+        assert!(value.is_finite() && value.abs() < 2.0);
+        assert!(vars.velocity(key).unwrap().is_finite());
+    }
 }
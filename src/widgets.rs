@@ -1,7 +1,7 @@
 use crate::{
     events::{ElemEvents, SelectOpts},
     styles::Container,
-    variables::Variable,
+    variables::{Spring, VarKey, Variable},
     ElemEvent, ElemEventTypes, Element, ElementKey, EventListener, Gui, ImageData, MouseButtons,
     Overflow, Portion, Position, SelectionStates, Styles, Value, Values, Vector,
 };
@@ -136,7 +136,8 @@ impl<Msg: Clone, Img: Clone + ImageData, Data, Response: Clone>
                             parent,
                             left,
                             right,
-                            beam: *beam
+                            beam: *beam,
+                            spring: None,
                         },
                     )),
                 );
@@ -171,6 +172,53 @@ impl<Msg: Clone, Img: Clone + ImageData, Data, Response: Clone>
                 let parent_e = gui.get_element_mut_unchecked(parent);
                 parent_e.add_child(*beam);
             }
+            SplitOptions::Spring { split, beam, spring } => {
+                let var = gui.variables.push_spring(split.unwrap_or(0.5), *spring);
+                let parent_e = gui.get_element_mut_unchecked(parent);
+                parent_e.events.add(EventListener::new(ElemEventTypes::MouseMove).with_msg(
+                    (self.msg)(WidgetMsgs::SplitBeam {
+                        parent,
+                        left,
+                        right,
+                        beam: *beam,
+                        spring: Some(var),
+                    }),
+                ));
+                let beam_e = gui.get_element_mut_unchecked(*beam);
+                beam_e.events.add(EventListener::new(ElemEventTypes::Click).with_msg((self.msg)(
+                    WidgetMsgs::Hold {
+                        press: beam_press,
+                        release: beam_release,
+                    },
+                )));
+                let splits_width = Value::Value(Container::Container, Values::Width, Portion::Full);
+                let value = Value::Variable(var);
+                let rest = Value::Sub(Box::new((Value::Px(1.0), value.clone())));
+                beam_e.styles_mut().position.get_mut().width =
+                    Value::Mul(Box::new((splits_width.clone(), value.clone())));
+                let beam_width = beam_e.styles().width.get().clone();
+                let half_beam_width = Value::Mul(Box::new((beam_width, Value::Px(0.5))));
+                let left = gui.get_element_mut_unchecked(left);
+                let left_styles = left.styles_mut();
+                left_styles.width.set(Value::Sub(Box::new((
+                    Value::Mul(Box::new((splits_width.clone(), value))),
+                    half_beam_width.clone(),
+                ))));
+                left_styles.position.get_mut().width = Value::Px(0.0);
+                left_styles.origin.get_mut().width = Value::Px(0.0);
+                let right = gui.get_element_mut_unchecked(right);
+                let right_styles = right.styles_mut();
+                right_styles.width.set(Value::Sub(Box::new((
+                    Value::Mul(Box::new((splits_width, rest))),
+                    half_beam_width,
+                ))));
+                right_styles.position.get_mut().width =
+                    Value::Value(Container::Container, Values::Width, Portion::Full);
+                right_styles.origin.get_mut().width =
+                    Value::Value(Container::This, Values::Width, Portion::Full);
+                let parent_e = gui.get_element_mut_unchecked(parent);
+                parent_e.add_child(*beam);
+            }
             SplitOptions::Fixed(value) => {
                 let value = match value {
                     Some(value) => value,
@@ -231,6 +279,13 @@ pub enum SplitOptions {
         split: Option<f32>,
         beam: ElementKey,
     },
+    /// Like `Dynamic`, but the beam follows the cursor on a spring
+    /// and settles with the velocity it had when released
+    Spring {
+        split: Option<f32>,
+        beam: ElementKey,
+        spring: Spring,
+    },
     Fixed(Option<Value>),
 }
 
@@ -251,6 +306,8 @@ pub enum WidgetMsgs<Msg: Clone, Img: Clone + ImageData, Data, Response: Clone =
         left: ElementKey,
         right: ElementKey,
         beam: ElementKey,
+        /// Variable of a `SplitOptions::Spring` split
+        spring: Option<VarKey>,
     },
     Hold {
         press: OnEvent<Msg, Img, Data, Response>,
@@ -372,6 +429,7 @@ impl<Msg: Clone, Img: Clone + ImageData, Data, Response: Clone>
                 left,
                 right,
                 beam,
+                spring,
             } => match event.kind {
                 ElemEvents::CursorMove {
                     vp_pos,
@@ -384,6 +442,10 @@ impl<Msg: Clone, Img: Clone + ImageData, Data, Response: Clone>
                     let (_, hit) = vp_pos.container_colision_with_pos(&parent_e.instance.container);
                     let size = parent_e.instance.container.size;
                     let value = ((hit + size * 0.5) / size).max(0.0).min(1.0);
+                    if let Some(spring) = spring {
+                        _ = gui.variables.set_target(*spring, value.0);
+                        return;
+                    }
                     let beam_e = gui.get_element_mut_unchecked(*beam);
 
                     beam_e.styles_mut().position.get_mut().width =